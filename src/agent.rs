@@ -0,0 +1,124 @@
+use crate::math::{Distance, Normalize, Vec2};
+use crate::pathfinding::Path;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum State {
+    Idle,
+    Following,
+    Arrived,
+}
+
+// NOTE: `node` is the last waypoint the agent reached and is where paths should be planned from.
+// Setting `state` back to `Idle` re-arms the arrival callback without moving the agent.
+pub struct Agent {
+    pub position: Vec2<f32>,
+    pub velocity: Vec2<f32>,
+    pub node: usize,
+    pub state: State,
+    pub accel: f32,
+    pub drag: f32,
+    pub reach: f32,
+}
+
+impl Agent {
+    pub fn new(position: Vec2<f32>, node: usize, accel: f32, drag: f32, reach: f32) -> Self {
+        Self {
+            position,
+            velocity: Vec2::default(),
+            node,
+            state: State::Idle,
+            accel,
+            drag,
+            reach,
+        }
+    }
+
+    // NOTE: `path` starts at `node` and ends at `goal` (or only holds `node` while no path is
+    // known). The agent claims every path node it comes within reach of, steers toward the furthest
+    // node it can see, and calls `on_arrive` once each time it reaches `goal`; it stays `Arrived`
+    // (settling on the spot) until the goal moves or `state` is reset.
+    pub fn update<V: Fn(Vec2<f32>, Vec2<f32>) -> bool, F: FnMut(usize)>(
+        &mut self,
+        nodes: &[Vec2<f32>],
+        path: &Path,
+        goal: usize,
+        visible: V,
+        mut on_arrive: F,
+    ) {
+        let ahead: Vec<usize> = path.iter().copied().skip_while(|i| *i != self.node).collect();
+        let claimed = (1..ahead.len())
+            .rev()
+            .find(|k| self.position.distance(nodes[ahead[*k]]) <= self.reach)
+            .unwrap_or(0);
+        let ahead = &ahead[claimed..];
+        if let Some(node) = ahead.first() {
+            self.node = *node;
+        }
+
+        let target = ahead
+            .iter()
+            .skip(1)
+            .rev()
+            .find(|i| visible(self.position, nodes[**i]))
+            .or_else(|| ahead.get(1))
+            .map_or(nodes[self.node], |i| nodes[*i]);
+        let near = self.position.distance(target) <= self.reach;
+        if !near {
+            self.velocity += (target - self.position).normalize() * self.accel.into();
+        }
+
+        let state = if 1 < ahead.len() {
+            State::Following
+        } else if self.node != goal {
+            State::Idle
+        } else if near || (self.state == State::Arrived) {
+            State::Arrived
+        } else {
+            State::Following
+        };
+        if (state == State::Arrived) && (self.state != State::Arrived) {
+            on_arrive(goal);
+        }
+        self.state = state;
+
+        self.velocity *= self.drag.into();
+        self.position += self.velocity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_agent() {
+        let nodes = [
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 1.0, y: 0.0 },
+            Vec2 { x: 2.0, y: 0.0 },
+            Vec2 { x: 2.0, y: 1.0 },
+        ];
+        let path = Path::from([0, 1, 2, 3]);
+        let mut agent = Agent::new(nodes[0], 0, 0.05, 0.8, 0.1);
+
+        agent.update(&nodes, &Path::from([0]), 3, |_, _| true, |_| panic!());
+        assert!(agent.state == State::Idle);
+
+        // NOTE: With `2` occluding `3`, the agent looks ahead to `2` rather than `1`.
+        agent.update(&nodes, &path, 3, |_, b| b.y == 0.0, |_| panic!());
+        assert!(agent.state == State::Following);
+        assert!((0.0 < agent.velocity.x) && (agent.velocity.y == 0.0));
+
+        let mut arrivals = vec![];
+        for _ in 0..200 {
+            let path: Path = path.iter().copied().skip_while(|i| *i != agent.node).collect();
+            agent.update(&nodes, &path, 3, |_, _| true, |goal| arrivals.push(goal));
+        }
+        assert!((agent.node, agent.state) == (3, State::Arrived));
+        assert!(arrivals == [3]);
+
+        agent.state = State::Idle;
+        agent.update(&nodes, &Path::from([3]), 3, |_, _| true, |goal| arrivals.push(goal));
+        assert!(arrivals == [3, 3]);
+    }
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// NOTE: Copies of the files the app can't start without, built in so the binary also runs from
+// outside the repository. The files on disk still win when they are there, so edits to them show up
+// without a rebuild.
+const EMBEDDED: [(&str, &[u8]); 3] = [
+    ("assets/floor-plan.txt", include_bytes!("../assets/floor-plan.txt")),
+    ("src/vert.glsl", include_bytes!("vert.glsl")),
+    ("src/frag.glsl", include_bytes!("frag.glsl")),
+];
+
+pub fn embedded(path: &Path) -> Option<&'static [u8]> {
+    EMBEDDED
+        .iter()
+        .find(|(other, _)| Path::new(other) == path)
+        .map(|(_, bytes)| *bytes)
+}
+
+// NOTE: `fs::read`, falling back to the embedded copy of a missing file.
+pub fn read(path: &Path) -> Vec<u8> {
+    match fs::read(path) {
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            embedded(path).unwrap_or_else(|| panic!("{}: {error}", path.display())).to_vec()
+        }
+        result => result.unwrap(),
+    }
+}
+
+pub fn read_to_string(path: &Path) -> String {
+    String::from_utf8(read(path)).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_embedded() {
+        for (path, bytes) in EMBEDDED {
+            assert!(read(Path::new(path)) == bytes);
+        }
+        assert!(embedded(&Path::new("src").join("vert.glsl")).is_some());
+        assert!(embedded(Path::new("assets/floor-plan.graph")).is_none());
+    }
+}
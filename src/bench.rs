@@ -0,0 +1,210 @@
+use crate::geom::GridToWorld;
+use crate::level;
+use crate::math::Vec2;
+use crate::pathfinding::{self, Graph, Heuristic, Options, PathResult};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::time;
+
+const QUERIES: usize = 64;
+const BATCH_THREADS: usize = 4;
+
+// NOTE: Past this many waypoints the weight matrix alone runs to hundreds of megabytes, so only the
+// grid planner is run.
+const GRAPH_MAX_NODES: usize = 4096;
+
+type Algorithm = fn(&Graph<Vec2<f32>>, usize, usize) -> PathResult;
+
+macro_rules! astar {
+    ($heuristic:expr) => {
+        astar!($heuristic, 0)
+    };
+    ($heuristic:expr, $epsilon:expr) => {
+        |graph, start, end| {
+            let options = Options {
+                heuristic: $heuristic,
+                epsilon: $epsilon,
+                ..Options::default()
+            };
+            pathfinding::shortest_path(graph, start, end, options)
+        }
+    };
+}
+
+// NOTE: IDA* re-expands nodes on every iteration and has no transposition table, so it is only
+// run on maps with at most this many nodes.
+const IDA_MAX_NODES: usize = 128;
+
+const ALGORITHMS: [(&str, Algorithm, usize); 7] = [
+    ("astar-euclidean", astar!(Heuristic::Euclidean), usize::MAX),
+    ("astar-octile", astar!(Heuristic::Octile), usize::MAX),
+    ("astar-manhattan", astar!(Heuristic::Manhattan), usize::MAX),
+    ("astar-landmarks", astar!(Heuristic::Landmarks), usize::MAX),
+    ("astar-weighted-2", astar!(Heuristic::Euclidean, 10), usize::MAX),
+    ("dijkstra", astar!(Heuristic::Zero), usize::MAX),
+    (
+        "ida-euclidean",
+        |graph, start, end| pathfinding::ida_star(graph, start, end, Options::default()),
+        IDA_MAX_NODES,
+    ),
+];
+
+struct Row {
+    map: String,
+    algorithm: &'static str,
+    queries: usize,
+    expansions: usize,
+    nanos: u128,
+    cost: f32,
+}
+
+// NOTE: Synthetic floor plans covering shapes the hand-drawn maps lack: a tiny room, long
+// corridors joined at alternating ends, a wide open field, and a maze of pillars too large for a
+// graph.
+fn generated() -> [(&'static str, Vec<u8>); 4] {
+    let plan = |w: usize, h: usize, wall: &dyn Fn(usize, usize) -> bool| {
+        let mut bytes = Vec::with_capacity((w + 1) * h);
+        for y in 0..h {
+            bytes.extend((0..w).map(|x| if wall(x, y) { b'-' } else { b'.' }));
+            bytes.push(b'\n');
+        }
+        bytes
+    };
+    [
+        ("generated-small", plan(6, 6, &|_, _| false)),
+        (
+            "generated-corridors",
+            plan(24, 17, &|x, y| (y % 4 == 3) && (x != if y % 8 == 3 { 23 } else { 0 })),
+        ),
+        ("generated-open", plan(32, 32, &|_, _| false)),
+        ("generated-huge", plan(240, 240, &|x, y| (x % 6 == 3) && (y % 6 != 0))),
+    ]
+}
+
+// NOTE: Query endpoints are derived from the node count alone so reports are reproducible.
+fn queries(n: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..QUERIES).map(move |k| ((k * 7919) % n, ((k * 104_729) + (n / 2)) % n))
+}
+
+fn graph_rows(map: &str, level: &level::Level, scale: f32, rows: &mut Vec<Row>) {
+    let mut graph = level::graph(level, GridToWorld::new(level.bounds, scale), &level::Standard);
+    // NOTE: Landmarks are picked at load time, so they stay out of the measured runtimes.
+    graph.update_landmarks(pathfinding::LANDMARKS);
+
+    for (algorithm, f, max_nodes) in ALGORITHMS {
+        if max_nodes < graph.nodes.len() {
+            continue;
+        }
+        let mut row = Row {
+            map: map.to_owned(),
+            algorithm,
+            queries: 0,
+            expansions: 0,
+            nanos: 0,
+            cost: 0.0,
+        };
+        for (start, end) in queries(graph.nodes.len()) {
+            let now = time::Instant::now();
+            let result = f(&graph, start, end);
+            row.nanos += now.elapsed().as_nanos();
+            row.queries += 1;
+            row.expansions += result.expanded;
+            row.cost += result.cost;
+        }
+        rows.push(row);
+    }
+
+    let batch: Vec<(usize, usize)> = queries(graph.nodes.len()).collect();
+    let now = time::Instant::now();
+    let results =
+        pathfinding::shortest_paths_batch(&graph, &batch, Options::default(), BATCH_THREADS);
+    rows.push(Row {
+        map: map.to_owned(),
+        algorithm: "astar-batch",
+        queries: results.len(),
+        expansions: results.iter().map(|result| result.expanded).sum(),
+        nanos: now.elapsed().as_nanos(),
+        cost: results.iter().map(|result| result.cost).sum(),
+    });
+}
+
+pub fn run(directory: &Path, scale: f32) {
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+    let maps = paths
+        .into_iter()
+        .map(|path| {
+            let floor_plan = fs::read(&path).unwrap();
+            (path.file_name().unwrap().to_string_lossy().into_owned(), floor_plan)
+        })
+        .chain(generated().into_iter().map(|(map, floor_plan)| (map.to_owned(), floor_plan)));
+
+    let mut rows = vec![];
+    for (map, floor_plan) in maps {
+        let level = level::parse(&floor_plan, false).unwrap();
+        if level.waypoints.is_empty() {
+            continue;
+        }
+        if level.waypoints.len() <= GRAPH_MAX_NODES {
+            graph_rows(&map, &level, scale, &mut rows);
+        }
+
+        let mut row = Row {
+            map,
+            algorithm: "grid-astar",
+            queries: 0,
+            expansions: 0,
+            nanos: 0,
+            cost: 0.0,
+        };
+        let width = usize::from(level.bounds.x);
+        for (start, end) in queries(level.waypoints.len()) {
+            let (start, end) =
+                (level.index(level.waypoints[start]), level.index(level.waypoints[end]));
+            let now = time::Instant::now();
+            let result = pathfinding::grid_path(
+                &level.walls,
+                &level.costs,
+                width,
+                start,
+                end,
+                Options::default(),
+            );
+            row.nanos += now.elapsed().as_nanos();
+            row.queries += 1;
+            row.expansions += result.expanded;
+            // NOTE: Grid costs are in cells; scale them to match the graph rows.
+            row.cost += result.cost * scale;
+        }
+        rows.push(row);
+    }
+
+    let mut markdown = String::from(
+        "| map | algorithm | queries | expansions | runtime (ns) | path cost |\n\
+         |-----|-----------|--------:|-----------:|-------------:|----------:|\n",
+    );
+    let mut csv = String::from("map,algorithm,queries,expansions,runtime_ns,path_cost\n");
+    for row in &rows {
+        writeln!(
+            markdown,
+            "| {} | {} | {} | {} | {} | {:.2} |",
+            row.map, row.algorithm, row.queries, row.expansions, row.nanos, row.cost,
+        )
+        .unwrap();
+        writeln!(
+            csv,
+            "{},{},{},{},{},{:.2}",
+            row.map, row.algorithm, row.queries, row.expansions, row.nanos, row.cost,
+        )
+        .unwrap();
+    }
+
+    fs::write("bench-maps.md", &markdown).unwrap();
+    fs::write("bench-maps.csv", csv).unwrap();
+    print!("{markdown}");
+}
@@ -0,0 +1,82 @@
+use crate::math::{self, Mat4, Vec2, Vec3};
+
+const UP: Vec3<f32> = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+
+// NOTE: Keeps the spectator from looking straight along `UP`, where `look_at` degenerates.
+pub const MAX_PITCH: f32 = 1.55;
+
+// NOTE: Free-flying camera, independent from the gameplay camera. The ground plane is `z = 0`;
+// `yaw` turns about `z` and `pitch` tilts away from the ground plane (both in radians).
+pub struct Spectator {
+    pub position: Vec3<f32>,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl Spectator {
+    pub fn looking_at(from: Vec3<f32>, to: Vec3<f32>) -> Self {
+        let delta = to - from;
+        Self {
+            position: from,
+            yaw: delta.y.atan2(delta.x),
+            pitch: delta.z.atan2(delta.x.hypot(delta.y)).clamp(-MAX_PITCH, MAX_PITCH),
+        }
+    }
+
+    pub fn forward(&self) -> Vec3<f32> {
+        Vec3 {
+            x: self.pitch.cos() * self.yaw.cos(),
+            y: self.pitch.cos() * self.yaw.sin(),
+            z: self.pitch.sin(),
+        }
+    }
+
+    pub fn right(&self) -> Vec3<f32> {
+        Vec3 {
+            x: self.yaw.sin(),
+            y: -self.yaw.cos(),
+            z: 0.0,
+        }
+    }
+
+    // NOTE: `delta` is raw mouse motion already scaled to radians; moving right turns right and
+    // moving down looks down.
+    pub fn turn(&mut self, delta: Vec2<f32>) {
+        self.yaw -= delta.x;
+        self.pitch = (self.pitch - delta.y).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    // NOTE: `step.y` flies along the view direction and `step.x` strafes.
+    pub fn fly(&mut self, step: Vec2<f32>) {
+        self.position =
+            self.position + (self.forward() * step.y.into()) + (self.right() * step.x.into());
+    }
+
+    pub fn view(&self) -> Mat4<f32> {
+        math::look_at(self.position, self.position + self.forward(), UP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spectator() {
+        let mut spectator = Spectator::looking_at(
+            Vec3 { x: 0.0, y: 0.0, z: 10.0 },
+            Vec3 { x: 0.0, y: 10.0, z: 0.0 },
+        );
+        let forward = spectator.forward();
+        assert!((forward.x.abs() < 0.001) && (0.7 < forward.y) && (forward.z < -0.7));
+
+        spectator.fly(Vec2 { x: 1.0, y: 0.0 });
+        assert!((spectator.position.x - 1.0).abs() < 0.001);
+        assert!((spectator.position.z - 10.0).abs() < 0.001);
+
+        spectator.turn(Vec2 { x: 0.0, y: 10.0 });
+        assert!((spectator.pitch + MAX_PITCH).abs() < f32::EPSILON);
+        spectator.turn(Vec2 { x: 0.0, y: -20.0 });
+        assert!((spectator.pitch - MAX_PITCH).abs() < f32::EPSILON);
+    }
+}
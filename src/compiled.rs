@@ -0,0 +1,161 @@
+use crate::format;
+use crate::geom::GridToWorld;
+use crate::level::{self, Error, Level};
+use crate::math::Vec2;
+use crate::pathfinding::Graph;
+
+// NOTE: A compiled level keeps what `parse` would otherwise redo on every start: the plan's floors
+// already stacked into one grid (see `level::stack`), each run of a glyph stored once, and the
+// graph built from it. Loading skips validation and glyph translation, and graph construction too
+// while the graph's key still matches. The body is little-endian: the level section's length (u64),
+// then the section, then the graph as `Graph::serialize` writes it. The section holds the directive
+// lines (u32 length, then the text), the first row of each floor (u32 count, then u32s), the grid's
+// width and height (u32s), and its runs row after row with the newlines left out (u64 count, then a
+// u32 length and a glyph byte each).
+const KIND: [u8; 4] = *b"LEVL";
+const VERSION: u32 = 1;
+
+fn section(floor_plan: &[u8]) -> Vec<u8> {
+    let (header, grid, floors) = level::stack(floor_plan);
+    let width = grid.iter().position(|byte| *byte == b'\n').unwrap_or(0);
+    let height = grid.len() / (width + 1);
+
+    let mut runs: Vec<(u32, u8)> = vec![];
+    for byte in grid.iter().copied().filter(|byte| *byte != b'\n') {
+        match runs.last_mut() {
+            Some((n, glyph)) if (*glyph == byte) && (*n < u32::MAX) => *n += 1,
+            _ => runs.push((1, byte)),
+        }
+    }
+
+    let mut section = vec![];
+    section.extend_from_slice(&u32::try_from(header.len()).unwrap().to_le_bytes());
+    section.extend_from_slice(header);
+    section.extend_from_slice(&u32::try_from(floors.len()).unwrap().to_le_bytes());
+    for floor in floors {
+        section.extend_from_slice(&u32::from(floor).to_le_bytes());
+    }
+    section.extend_from_slice(&u32::try_from(width).unwrap().to_le_bytes());
+    section.extend_from_slice(&u32::try_from(height).unwrap().to_le_bytes());
+    section.extend_from_slice(&(runs.len() as u64).to_le_bytes());
+    for (n, glyph) in runs {
+        section.extend_from_slice(&n.to_le_bytes());
+        section.push(glyph);
+    }
+    section
+}
+
+fn level(section: &[u8]) -> Option<Level> {
+    let mut reader = format::Reader::new(section);
+    let n = usize::try_from(reader.u32()?).ok()?;
+    let header = reader.take(n)?;
+    let mut floors = vec![];
+    for _ in 0..reader.u32()? {
+        floors.push(u16::try_from(reader.u32()?).ok()?);
+    }
+    let width = usize::try_from(reader.u32()?).ok()?;
+    let height = usize::try_from(reader.u32()?).ok()?;
+    let mut cells = Vec::with_capacity(width * height);
+    for _ in 0..reader.u64()? {
+        let n = usize::try_from(reader.u32()?).ok()?;
+        let glyph = reader.take(1)?[0];
+        cells.resize(cells.len() + n, glyph);
+    }
+    // NOTE: `N` and `S` are the grid's own bytes for `↑` and `↓` (see `level::glyph`).
+    let glyph =
+        |byte: u8| matches!(byte, b'N' | b'S') || (level::glyph(char::from(byte)) == Some(byte));
+    if !reader.is_empty()
+        || (cells.len() != width * height)
+        || (width == 0)
+        || !cells.iter().all(|byte| glyph(*byte))
+    {
+        return None;
+    }
+    let mut grid = Vec::with_capacity((width + 1) * height);
+    for row in cells.chunks_exact(width) {
+        grid.extend_from_slice(row);
+        grid.push(b'\n');
+    }
+    level::build(header, &grid, floors, false).ok()
+}
+
+fn sections(bytes: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let body = format::migrate(bytes, KIND, None, &[])?;
+    let mut reader = format::Reader::new(&body);
+    let n = usize::try_from(reader.u64()?).ok()?;
+    let section = reader.take(n)?.to_vec();
+    Some((section, reader.rest().to_vec()))
+}
+
+// NOTE: `scale` is the world size of a cell on plans that don't set their own, as it will be when
+// the level is loaded.
+pub fn compile(floor_plan: &[u8], scale: f32) -> Result<Vec<u8>, Error> {
+    level::parse(floor_plan, false)?;
+    let section = section(floor_plan);
+    let level = level(&section).unwrap();
+    let grid = GridToWorld::new(level.bounds, level.scale.unwrap_or(scale));
+    let graph = level::graph(&level, grid, &level::Standard);
+
+    let mut body = vec![];
+    body.extend_from_slice(&(section.len() as u64).to_le_bytes());
+    body.extend_from_slice(&section);
+    body.extend_from_slice(&graph.serialize(level::key(&section, &level, grid)));
+    Ok(format::write(KIND, VERSION, &body))
+}
+
+pub fn load(bytes: &[u8]) -> Result<Level, Error> {
+    sections(bytes)
+        .and_then(|(section, _)| level(&section))
+        .ok_or_else(|| Error::Import {
+            format: "compiled level",
+            message: "not a compiled level of this version".to_owned(),
+        })
+}
+
+// NOTE: `None` when the stored graph doesn't match `level` and `grid` (after `level::scatter`,
+// say), in which case it has to be rebuilt.
+pub fn graph(bytes: &[u8], level: &Level, grid: GridToWorld) -> Option<Graph<Vec2<f32>>> {
+    let (section, graph) = sections(bytes)?;
+    let mut graph = Graph::deserialize(&graph, level::key(&section, level, grid))?;
+    if graph.nodes.len() != level.waypoints.len() {
+        return None;
+    }
+    graph.tags.clone_from(&level.waypoint_tags);
+    Some(graph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile() {
+        let floor_plan = "!jitter 3\n!tag hall 1 1 8 1\n+----------+\n|^.........|\n|.>>>>.~~~.|\n\
+                          |..↑.......|\n+----------+\n=== floor 1 ===\n+----------+\n|v....X...|\n"
+            .as_bytes();
+        let parsed = level::parse(floor_plan, false).unwrap();
+        let bytes = compile(floor_plan, 1.0).unwrap();
+        let level = load(&bytes).unwrap();
+        assert!(level::floor_plan(&level) == level::floor_plan(&parsed));
+        assert!(level.tags == parsed.tags);
+
+        let grid = GridToWorld::new(level.bounds, 1.0);
+        let graph = graph(&bytes, &level, grid).unwrap();
+        let built = level::graph(&parsed, grid, &level::Standard);
+        let n = graph.nodes.len();
+        assert!(n == built.nodes.len());
+        assert!((0..n)
+            .all(|i| (0..n).all(|j| graph.weight(i, j).to_bits() == built.weight(i, j).to_bits())));
+        assert!(graph.tags == built.tags);
+        assert!(super::graph(&bytes, &level, GridToWorld::new(level.bounds, 2.0)).is_none());
+
+        // NOTE: A long corridor is a handful of runs, whatever its length.
+        let wide =
+            format!("+{}+\n|{}|\n+{}+\n", "-".repeat(2000), ".".repeat(2000), "-".repeat(2000));
+        assert!(section(wide.as_bytes()).len() < 128);
+        assert!(super::graph(&bytes[..(bytes.len() - 1)], &level, grid).is_none());
+        assert!(sections(b"PTHRGRPH\x01\x00\x00\x00").is_none());
+        assert!(load(&bytes[..40]).is_err());
+        assert!(compile(b"!warp 1 2\n..\n", 1.0).is_err());
+    }
+}
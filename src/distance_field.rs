@@ -0,0 +1,32 @@
+use crate::ffi;
+use crate::math::{Vec2, Vec4};
+use crate::overlay::{DebugOverlay, DrawList, Frame};
+use std::ffi::c_int;
+
+const SCALE: f32 = 4.0;
+const NEAR_COLOR: Vec4<f32> = Vec4 { x: 0.9, y: 0.2, z: 0.3, w: 0.7 };
+const FAR_COLOR: Vec4<f32> = Vec4 { x: 0.2, y: 0.5, z: 0.95, w: 0.7 };
+
+// NOTE: Colors each waypoint by its clearance (distance to the nearest wall), from `NEAR_COLOR`
+// hugging a wall to `FAR_COLOR` at the most open spot on the map.
+pub struct DistanceField;
+
+impl DebugOverlay for DistanceField {
+    fn name(&self) -> &'static str {
+        "distance_field"
+    }
+
+    fn key(&self) -> c_int {
+        ffi::GLFW_KEY_2
+    }
+
+    fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+        let level = frame.level;
+        let clearance = |cell: Vec2<u16>| level.clearance[level.index(cell)];
+        let max = level.waypoints.iter().copied().map(clearance).fold(1.0, f32::max);
+        for (waypoint, node) in level.waypoints.iter().zip(&frame.graph.nodes) {
+            let t = clearance(*waypoint) / max;
+            draw.point(*node, SCALE, NEAR_COLOR + ((FAR_COLOR - NEAR_COLOR) * t.into()));
+        }
+    }
+}
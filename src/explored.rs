@@ -0,0 +1,32 @@
+use crate::ffi;
+use crate::math::Vec4;
+use crate::overlay::{DebugOverlay, DrawList, Frame};
+use std::ffi::c_int;
+
+const SCALE: f32 = 3.0;
+const COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.45, z: 0.2, w: 0.6 };
+
+// NOTE: Marks every node the cursor preview search has closed so far, which shows how far a
+// heuristic lets the search wander off the straight line.
+pub struct Explored;
+
+impl DebugOverlay for Explored {
+    fn name(&self) -> &'static str {
+        "explored"
+    }
+
+    fn key(&self) -> c_int {
+        ffi::GLFW_KEY_1
+    }
+
+    fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+        let Some(search) = frame.preview else {
+            return;
+        };
+        for (i, node) in frame.graph.nodes.iter().enumerate() {
+            if search.closed(i) {
+                draw.point(*node, SCALE, COLOR);
+            }
+        }
+    }
+}
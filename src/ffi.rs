@@ -15,6 +15,16 @@ macro_rules! opaque_struct {
 opaque_struct!(GLFWwindow);
 opaque_struct!(GLFWmonitor);
 
+#[repr(C)]
+pub struct GLFWvidmode {
+    pub width: c_int,
+    pub height: c_int,
+    pub red_bits: c_int,
+    pub green_bits: c_int,
+    pub blue_bits: c_int,
+    pub refresh_rate: c_int,
+}
+
 pub type GLenum = c_uint;
 pub type GLbitfield = c_uint;
 pub type GLint = c_int;
@@ -30,6 +40,10 @@ pub type GLsizeiptr = c_long;
 pub type GLFWerrorfun = extern "C" fn(error_code: c_int, description: *const c_char);
 pub type GLFWkeyfun =
     extern "C" fn(window: *mut GLFWwindow, key: c_int, scancode: c_int, action: c_int, mods: c_int);
+pub type GLFWmousebuttonfun =
+    extern "C" fn(window: *mut GLFWwindow, button: c_int, action: c_int, mods: c_int);
+pub type GLFWframebuffersizefun =
+    extern "C" fn(window: *mut GLFWwindow, width: c_int, height: c_int);
 
 #[allow(clippy::upper_case_acronyms)]
 pub type GLDEBUGPROC = extern "C" fn(
@@ -57,21 +71,57 @@ unsafe extern "C" {
         monitor: *mut GLFWmonitor,
         share: *mut GLFWwindow,
     ) -> *mut GLFWwindow;
+    pub fn glfwSetWindowTitle(window: *mut GLFWwindow, title: *const c_char);
     pub fn glfwDestroyWindow(window: *mut GLFWwindow);
 
+    pub fn glfwGetPrimaryMonitor() -> *mut GLFWmonitor;
+    pub fn glfwGetVideoMode(monitor: *mut GLFWmonitor) -> *const GLFWvidmode;
+    pub fn glfwGetWindowMonitor(window: *mut GLFWwindow) -> *mut GLFWmonitor;
+    pub fn glfwSetWindowMonitor(
+        window: *mut GLFWwindow,
+        monitor: *mut GLFWmonitor,
+        xpos: c_int,
+        ypos: c_int,
+        width: c_int,
+        height: c_int,
+        refreshRate: c_int,
+    );
+    pub fn glfwGetWindowPos(window: *mut GLFWwindow, xpos: *mut c_int, ypos: *mut c_int);
+
     pub fn glfwSetErrorCallback(callback: GLFWerrorfun) -> GLFWerrorfun;
 
     pub fn glfwSetKeyCallback(window: *mut GLFWwindow, callback: GLFWkeyfun) -> GLFWkeyfun;
+    pub fn glfwSetMouseButtonCallback(
+        window: *mut GLFWwindow,
+        callback: GLFWmousebuttonfun,
+    ) -> GLFWmousebuttonfun;
+    pub fn glfwSetFramebufferSizeCallback(
+        window: *mut GLFWwindow,
+        callback: GLFWframebuffersizefun,
+    ) -> GLFWframebuffersizefun;
+    pub fn glfwSetWindowUserPointer(window: *mut GLFWwindow, pointer: *mut c_void);
+    pub fn glfwGetWindowUserPointer(window: *mut GLFWwindow) -> *mut c_void;
+    pub fn glfwGetTime() -> c_double;
     pub fn glfwSetWindowShouldClose(window: *mut GLFWwindow, value: c_int);
+    pub fn glfwSetInputMode(window: *mut GLFWwindow, mode: c_int, value: c_int);
+    pub fn glfwRawMouseMotionSupported() -> c_int;
+
+    pub fn glfwGetWindowContentScale(
+        window: *mut GLFWwindow,
+        xscale: *mut c_float,
+        yscale: *mut c_float,
+    );
+    pub fn glfwGetFramebufferSize(window: *mut GLFWwindow, width: *mut c_int, height: *mut c_int);
+    pub fn glfwGetWindowSize(window: *mut GLFWwindow, width: *mut c_int, height: *mut c_int);
 
     pub fn glfwMakeContextCurrent(window: *mut GLFWwindow);
     pub fn glfwSwapInterval(interval: c_int);
 
     pub fn glfwWindowShouldClose(window: *mut GLFWwindow) -> c_int;
     pub fn glfwPollEvents();
+    pub fn glfwWaitEventsTimeout(timeout: c_double);
     pub fn glfwSwapBuffers(window: *mut GLFWwindow);
 
-    pub fn glfwGetKey(window: *mut GLFWwindow, key: c_int) -> c_int;
     pub fn glfwGetCursorPos(window: *mut GLFWwindow, xpos: *mut c_double, ypos: *mut c_double);
 
     // NOTE: See `https://www.khronos.org/opengl/wiki/OpenGL_Error`.
@@ -80,10 +130,12 @@ unsafe extern "C" {
     pub fn glViewport(x: GLint, y: GLint, width: GLsizei, height: GLsizei);
 
     pub fn glEnable(cap: GLenum);
+    pub fn glDisable(cap: GLenum);
     pub fn glBlendFunc(sfactor: GLenum, dfactor: GLenum);
 
     pub fn glClearColor(red: GLclampf, green: GLclampf, blue: GLclampf, alpha: GLclampf);
     pub fn glClear(mask: GLbitfield);
+    pub fn glFinish();
 
     pub fn glLineWidth(width: GLfloat);
 
@@ -95,12 +147,20 @@ unsafe extern "C" {
         length: *const GLint,
     );
     pub fn glCompileShader(shader: GLuint);
+    pub fn glGetShaderiv(shader: GLuint, pname: GLenum, params: *mut GLint);
+    pub fn glGetShaderInfoLog(
+        shader: GLuint,
+        bufSize: GLsizei,
+        length: *mut GLsizei,
+        infoLog: *mut GLchar,
+    );
     pub fn glDeleteShader(shader: GLuint);
 
     pub fn glCreateProgram() -> GLuint;
     pub fn glDeleteProgram(program: GLuint);
     pub fn glAttachShader(program: GLuint, shader: GLuint);
     pub fn glLinkProgram(program: GLuint);
+    pub fn glGetProgramiv(program: GLuint, pname: GLenum, params: *mut GLint);
     pub fn glUseProgram(program: GLuint);
 
     pub fn glGenVertexArrays(n: GLsizei, arrays: *mut GLuint);
@@ -125,6 +185,27 @@ unsafe extern "C" {
     pub fn glBufferSubData(target: GLenum, offset: GLintptr, size: GLsizeiptr, data: *const c_void);
     pub fn glDeleteBuffers(n: GLsizei, buffers: *const GLuint);
 
+    pub fn glGenFramebuffers(n: GLsizei, framebuffers: *mut GLuint);
+    pub fn glBindFramebuffer(target: GLenum, framebuffer: GLuint);
+    pub fn glDeleteFramebuffers(n: GLsizei, framebuffers: *const GLuint);
+    pub fn glCheckFramebufferStatus(target: GLenum) -> GLenum;
+    pub fn glFramebufferRenderbuffer(
+        target: GLenum,
+        attachment: GLenum,
+        renderbuffertarget: GLenum,
+        renderbuffer: GLuint,
+    );
+
+    pub fn glGenRenderbuffers(n: GLsizei, renderbuffers: *mut GLuint);
+    pub fn glBindRenderbuffer(target: GLenum, renderbuffer: GLuint);
+    pub fn glDeleteRenderbuffers(n: GLsizei, renderbuffers: *const GLuint);
+    pub fn glRenderbufferStorage(
+        target: GLenum,
+        internalformat: GLenum,
+        width: GLsizei,
+        height: GLsizei,
+    );
+
     pub fn glGetUniformLocation(program: GLuint, name: *const GLchar) -> GLint;
     pub fn glUniformMatrix4fv(
         location: GLint,
@@ -133,6 +214,16 @@ unsafe extern "C" {
         value: *const GLfloat,
     );
 
+    pub fn glReadPixels(
+        x: GLint,
+        y: GLint,
+        width: GLsizei,
+        height: GLsizei,
+        format: GLenum,
+        r#type: GLenum,
+        data: *mut c_void,
+    );
+
     // pub fn glDrawArrays(mode: GLenum, first: GLint, count: GLsizei);
     pub fn glDrawArraysInstanced(
         mode: GLenum,
@@ -143,6 +234,7 @@ unsafe extern "C" {
 }
 
 pub const GLFW_RESIZABLE: c_int = 0x0002_0003;
+pub const GLFW_VISIBLE: c_int = 0x0002_0004;
 pub const GLFW_SAMPLES: c_int = 0x0002_100D;
 pub const GLFW_CONTEXT_VERSION_MAJOR: c_int = 0x0002_2002;
 pub const GLFW_CONTEXT_VERSION_MINOR: c_int = 0x0002_2003;
@@ -150,17 +242,61 @@ pub const GLFW_OPENGL_DEBUG_CONTEXT: c_int = 0x0002_2007;
 pub const GLFW_OPENGL_PROFILE: c_int = 0x0002_2008;
 pub const GLFW_OPENGL_CORE_PROFILE: c_int = 0x0003_2001;
 
+pub const GLFW_DONT_CARE: c_int = -1;
+
 pub const GLFW_PRESS: c_int = 1;
+pub const GLFW_REPEAT: c_int = 2;
+
+pub const GLFW_CURSOR: c_int = 0x0003_3001;
+pub const GLFW_RAW_MOUSE_MOTION: c_int = 0x0003_3005;
+pub const GLFW_CURSOR_NORMAL: c_int = 0x0003_4001;
+pub const GLFW_CURSOR_DISABLED: c_int = 0x0003_4003;
 
 pub const GLFW_KEY_ESCAPE: c_int = 256;
+pub const GLFW_KEY_TAB: c_int = 258;
+pub const GLFW_KEY_BACKSPACE: c_int = 259;
+pub const GLFW_KEY_F11: c_int = 300;
+pub const GLFW_KEY_LEFT_SHIFT: c_int = 340;
+pub const GLFW_KEY_LEFT_BRACKET: c_int = 91;
+pub const GLFW_KEY_RIGHT_BRACKET: c_int = 93;
+pub const GLFW_KEY_MINUS: c_int = 45;
+pub const GLFW_KEY_EQUAL: c_int = 61;
 pub const GLFW_KEY_W: c_int = 87;
 pub const GLFW_KEY_S: c_int = 83;
+pub const GLFW_KEY_1: c_int = 49;
+pub const GLFW_KEY_2: c_int = 50;
+pub const GLFW_KEY_3: c_int = 51;
 pub const GLFW_KEY_A: c_int = 65;
+pub const GLFW_KEY_B: c_int = 66;
+pub const GLFW_KEY_C: c_int = 67;
 pub const GLFW_KEY_D: c_int = 68;
+pub const GLFW_KEY_F: c_int = 70;
+pub const GLFW_KEY_G: c_int = 71;
+pub const GLFW_KEY_H: c_int = 72;
+pub const GLFW_KEY_I: c_int = 73;
+pub const GLFW_KEY_J: c_int = 74;
+pub const GLFW_KEY_K: c_int = 75;
+pub const GLFW_KEY_L: c_int = 76;
+pub const GLFW_KEY_M: c_int = 77;
+pub const GLFW_KEY_N: c_int = 78;
+pub const GLFW_KEY_O: c_int = 79;
+pub const GLFW_KEY_P: c_int = 80;
+pub const GLFW_KEY_R: c_int = 82;
+pub const GLFW_KEY_T: c_int = 84;
+pub const GLFW_KEY_V: c_int = 86;
+pub const GLFW_KEY_X: c_int = 88;
+pub const GLFW_KEY_Y: c_int = 89;
+
+pub const GLFW_MOUSE_BUTTON_LEFT: c_int = 0;
+pub const GLFW_MOUSE_BUTTON_RIGHT: c_int = 1;
 
 pub const GL_FALSE: GLboolean = 0;
 
 pub const GL_FLOAT: GLenum = 0x1406;
+pub const GL_UNSIGNED_BYTE: GLenum = 0x1401;
+
+pub const GL_RGB: GLenum = 0x1907;
+pub const GL_RGBA8: GLenum = 0x8058;
 
 pub const GL_LINES: GLenum = 0x0001;
 // pub const GL_TRIANGLES: GLenum = 0x0004;
@@ -170,6 +306,13 @@ pub const GL_LINE_SMOOTH: GLenum = 0x0B20;
 
 pub const GL_VERTEX_SHADER: GLenum = 0x8B31;
 pub const GL_FRAGMENT_SHADER: GLenum = 0x8B30;
+pub const GL_COMPILE_STATUS: GLenum = 0x8B81;
+pub const GL_LINK_STATUS: GLenum = 0x8B82;
+
+pub const GL_FRAMEBUFFER: GLenum = 0x8D40;
+pub const GL_RENDERBUFFER: GLenum = 0x8D41;
+pub const GL_COLOR_ATTACHMENT0: GLenum = 0x8CE0;
+pub const GL_FRAMEBUFFER_COMPLETE: GLenum = 0x8CD5;
 
 pub const GL_ARRAY_BUFFER: GLenum = 0x8892;
 
@@ -0,0 +1,51 @@
+use crate::ffi;
+use crate::governor::Feature;
+use crate::math::{Vec2, Vec4};
+use crate::overlay::{DebugOverlay, DrawList, Frame};
+use crate::pathfinding;
+use std::ffi::c_int;
+
+// NOTE: Each arrow covers this fraction of the edge to the next node downhill.
+const SCALE: f32 = 0.4;
+const COLOR: Vec4<f32> = Vec4 { x: 0.9, y: 0.75, z: 0.3, w: 0.5 };
+
+// NOTE: Points every node along its shortest path to the waypoint under the cursor. The field is
+// only recomputed when the cursor or the graph changes, and not at all while the governor has shed
+// it.
+#[derive(Default)]
+pub struct FlowField {
+    key: Option<(usize, u64)>,
+    arrows: Vec<(Vec2<f32>, Vec2<f32>)>,
+}
+
+impl DebugOverlay for FlowField {
+    fn name(&self) -> &'static str {
+        "flow_field"
+    }
+
+    fn key(&self) -> c_int {
+        ffi::GLFW_KEY_F
+    }
+
+    fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+        if !frame.governor.enabled(Feature::FlowField) {
+            self.key = None;
+            return;
+        }
+        let nodes = &frame.graph.nodes;
+        let key = (frame.cursor, frame.graph.version);
+        if self.key != Some(key) {
+            self.key = Some(key);
+            self.arrows = pathfinding::flow_field(frame.cursor, frame.graph)
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, next)| {
+                    Some((nodes[i], nodes[i] + ((nodes[next?] - nodes[i]) * SCALE.into())))
+                })
+                .collect();
+        }
+        for (from, to) in &self.arrows {
+            draw.line(*from, *to, COLOR);
+        }
+    }
+}
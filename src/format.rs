@@ -0,0 +1,111 @@
+// NOTE: Every file the crate writes starts with `MAGIC`, a four-byte kind tag and a little-endian
+// `u32` version. Readers hand the remaining body through `migrate`, which upgrades it one version
+// at a time and rejects anything it does not recognize instead of guessing.
+pub const MAGIC: &[u8; 4] = b"PTHR";
+
+// NOTE: Upgrades a body from version `k + 1` to `k + 2`, where `k` is the migration's index.
+pub type Migration = fn(&[u8]) -> Option<Vec<u8>>;
+
+pub fn write(kind: [u8; 4], version: u32, body: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(12 + body.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&kind);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(body);
+    bytes
+}
+
+pub fn read(bytes: &[u8], kind: [u8; 4]) -> Option<(u32, &[u8])> {
+    let mut reader = Reader::new(bytes);
+    if (reader.take(4)? != MAGIC) || (reader.take(4)? != kind) {
+        return None;
+    }
+    let version = reader.u32()?;
+    Some((version, reader.rest()))
+}
+
+// NOTE: `legacy` is the magic of files written before headers existed; their bodies follow it
+// directly and are treated as version 1. The current version is `migrations.len() + 1`.
+pub fn migrate(
+    bytes: &[u8],
+    kind: [u8; 4],
+    legacy: Option<[u8; 4]>,
+    migrations: &[Migration],
+) -> Option<Vec<u8>> {
+    let (version, body) = match legacy.and_then(|legacy| bytes.strip_prefix(&legacy)) {
+        Some(body) => (1, body),
+        None => read(bytes, kind)?,
+    };
+    let version = usize::try_from(version).ok()?;
+    if (version == 0) || (migrations.len() + 1 < version) {
+        return None;
+    }
+    let mut body = body.to_vec();
+    for migration in &migrations[(version - 1)..] {
+        body = migration(&body)?;
+    }
+    Some(body)
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+
+    pub fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let chunk = self.bytes.get(self.offset..(self.offset + n))?;
+        self.offset += n;
+        Some(chunk)
+    }
+
+    pub fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.bytes[self.offset..];
+        self.offset = self.bytes.len();
+        rest
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.len() <= self.offset
+    }
+
+    pub fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    pub fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let migrations: [Migration; 2] = [
+            |body| Some([body, b"2"].concat()),
+            |body| Some([body, b"3"].concat()),
+        ];
+        let current = write(*b"TEST", 3, b"x");
+        assert!(migrate(&current, *b"TEST", None, &migrations).unwrap() == b"x");
+        let old = write(*b"TEST", 1, b"x");
+        assert!(migrate(&old, *b"TEST", None, &migrations).unwrap() == b"x23");
+        assert!(migrate(b"NEW!x", *b"TEST", Some(*b"OLD!"), &migrations).is_none());
+        assert!(migrate(b"OLD!x", *b"TEST", Some(*b"OLD!"), &migrations).unwrap() == b"x23");
+
+        assert!(migrate(&write(*b"TEST", 4, b"x"), *b"TEST", None, &migrations).is_none());
+        assert!(migrate(&write(*b"TEST", 0, b"x"), *b"TEST", None, &migrations).is_none());
+        assert!(migrate(&write(*b"ELSE", 3, b"x"), *b"TEST", None, &migrations).is_none());
+        assert!(migrate(&current[..10], *b"TEST", None, &migrations).is_none());
+    }
+}
@@ -1,4 +1,4 @@
-use crate::math::{Vec2, Vec4};
+use crate::math::{Vec2, Vec3, Vec4};
 
 #[repr(C)]
 #[derive(Clone, Copy)]
@@ -30,12 +30,19 @@ impl<T> From<Vec4<T>> for Color<T> {
     }
 }
 
+// NOTE: `x` is the z-offset of the instance and `y` is the z-extent along the vertex `x` axis, so
+// lines can leave the ground plane.
+#[repr(C)]
+#[derive(Clone, Copy, Default)]
+pub struct Depth<T>(pub Vec2<T>);
+
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct Geom<T> {
     pub translate: Translate<T>,
     pub scale: Scale<T>,
     pub color: Color<T>,
+    pub depth: Depth<T>,
 }
 
 #[derive(Clone, Copy)]
@@ -52,3 +59,47 @@ impl From<Line<f32>> for Scale<f32> {
         Self(line.0 - line.1)
     }
 }
+
+#[derive(Clone, Copy)]
+pub struct Line3<T>(pub Vec3<T>, pub Vec3<T>);
+
+impl From<Line3<f32>> for Line<f32> {
+    fn from(line: Line3<f32>) -> Self {
+        Self(Vec2 { x: line.0.x, y: line.0.y }, Vec2 { x: line.1.x, y: line.1.y })
+    }
+}
+
+impl From<Line3<f32>> for Depth<f32> {
+    fn from(line: Line3<f32>) -> Self {
+        Self(Vec2 {
+            x: (line.0.z + line.1.z) * 0.5,
+            y: line.0.z - line.1.z,
+        })
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct GridToWorld {
+    pub scale: Vec2<f32>,
+    pub half_bounds: Vec2<f32>,
+}
+
+impl GridToWorld {
+    pub fn new(bounds: Vec2<u16>, scale: f32) -> Self {
+        Self {
+            scale: Vec2 { x: scale, y: -scale },
+            half_bounds: Vec2 {
+                x: f32::from(bounds.x) * 0.5,
+                y: f32::from(bounds.y) * 0.5,
+            },
+        }
+    }
+
+    pub fn cell_to_world(self, cell: Vec2<f32>) -> Vec2<f32> {
+        ((cell - self.half_bounds) * self.scale) + (self.scale * 0.5.into())
+    }
+
+    pub fn world_to_cell(self, world: Vec2<f32>) -> Vec2<f32> {
+        ((world - (self.scale * 0.5.into())) / self.scale) + self.half_bounds
+    }
+}
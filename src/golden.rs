@@ -0,0 +1,106 @@
+use crate::geom::GridToWorld;
+use crate::level;
+use crate::pathfinding::{self, Heuristic, Options, TieBreak};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+// NOTE: Expected planner outputs, one case per line:
+// `map heuristic tie_break epsilon start goal cost path...`, with cells written as `x,y`. Graphs
+// are built at a scale of one world unit per cell.
+pub const GOLDENS: &str = "planner.goldens";
+
+const QUERIES: usize = 12;
+
+fn options() -> Vec<Options> {
+    let mut options: Vec<Options> = Heuristic::ALL
+        .into_iter()
+        .map(|heuristic| Options { heuristic, ..Options::default() })
+        .collect();
+    options.extend(
+        [TieBreak::Straight, TieBreak::Cross]
+            .map(|tie_break| Options { tie_break, ..Options::default() }),
+    );
+    options.push(Options { epsilon: 10, ..Options::default() });
+    options
+}
+
+// NOTE: Runs every case against the maps (`*.txt`) in `directory`, in a stable order.
+fn record(directory: &Path) -> String {
+    let mut paths: Vec<_> = fs::read_dir(directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+
+    let mut goldens = String::new();
+    for path in paths {
+        let map = path.file_name().unwrap().to_string_lossy().into_owned();
+        let level = level::parse(&fs::read(&path).unwrap(), false).unwrap();
+        let mut graph = level::graph(&level, GridToWorld::new(level.bounds, 1.0), &level::Standard);
+        graph.update_landmarks(pathfinding::LANDMARKS);
+        let n = graph.nodes.len();
+        for options in options() {
+            for k in 0..QUERIES {
+                let (start, end) = ((k * 7919) % n, ((k * 104_729) + (n / 2)) % n);
+                let result = pathfinding::shortest_path(&graph, start, end, options);
+                let cell = |i: usize| format!("{},{}", level.waypoints[i].x, level.waypoints[i].y);
+                write!(
+                    goldens,
+                    "{map} {} {} {} {} {} {:.3}",
+                    options.heuristic.name(),
+                    options.tie_break.name(),
+                    options.epsilon,
+                    cell(start),
+                    cell(end),
+                    result.cost,
+                )
+                .unwrap();
+                for i in &result.nodes {
+                    write!(goldens, " {}", cell(*i)).unwrap();
+                }
+                goldens.push('\n');
+            }
+        }
+    }
+    goldens
+}
+
+pub fn update(directory: &Path) {
+    let goldens = record(directory);
+    fs::write(directory.join(GOLDENS), &goldens).unwrap();
+    println!(
+        "{} cases written to {}",
+        goldens.lines().count(),
+        directory.join(GOLDENS).display()
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: Costs may drift in the last printed digit; everything else must match exactly. Run
+    // `--update-goldens` after a change that is meant to alter results.
+    #[test]
+    fn test_goldens() {
+        let directory = Path::new("assets");
+        let expected = fs::read_to_string(directory.join(GOLDENS)).unwrap();
+        let actual = record(directory);
+        assert!(expected.lines().count() == actual.lines().count());
+        for (expected, actual) in expected.lines().zip(actual.lines()) {
+            let expected: Vec<&str> = expected.split(' ').collect();
+            let actual: Vec<&str> = actual.split(' ').collect();
+            let cost = |fields: &[&str]| fields[6].parse::<f32>().unwrap();
+            assert!(
+                (expected[..6] == actual[..6])
+                    && (expected[7..] == actual[7..])
+                    && ((cost(&expected) - cost(&actual)).abs() < 0.01),
+                "expected {}\n  actual {}",
+                expected.join(" "),
+                actual.join(" "),
+            );
+        }
+    }
+}
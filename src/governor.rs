@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+// NOTE: Optional visuals, in the order they are given up when frames run over budget (and restored
+// in reverse).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Feature {
+    FlowField,
+    Heat,
+    Trail,
+    Multisample,
+}
+
+impl Feature {
+    pub const ALL: [Self; 4] = [Self::FlowField, Self::Heat, Self::Trail, Self::Multisample];
+}
+
+// NOTE: Frame times are smoothed so a single hitch doesn't shed anything; a feature goes after
+// `SHED_FRAMES` consecutive frames over budget and comes back after `RESTORE_FRAMES` frames with
+// `HEADROOM` to spare. The gap between the two keeps it from flickering at the boundary.
+const SMOOTHING: f32 = 0.1;
+const SHED_FRAMES: u32 = 30;
+const RESTORE_FRAMES: u32 = 180;
+const HEADROOM: f32 = 0.6;
+
+pub struct Governor {
+    budget: f32,
+    pub average: f32,
+    over: u32,
+    under: u32,
+    pub shed: usize,
+    // NOTE: Gives up every feature regardless of frame times.
+    pub low_power: bool,
+}
+
+impl Governor {
+    pub const fn new(budget: Duration) -> Self {
+        Self {
+            budget: budget.as_secs_f32(),
+            average: 0.0,
+            over: 0,
+            under: 0,
+            shed: 0,
+            low_power: false,
+        }
+    }
+
+    pub fn enabled(&self, feature: Feature) -> bool {
+        !self.low_power && !Feature::ALL[..self.shed].contains(&feature)
+    }
+
+    // NOTE: `work` should leave out time spent blocked on vsync. Returns the feature that was just
+    // turned off (`false`) or back on (`true`), if any.
+    pub fn update(&mut self, work: Duration) -> Option<(Feature, bool)> {
+        self.average = (work.as_secs_f32() - self.average).mul_add(SMOOTHING, self.average);
+        if self.budget < self.average {
+            self.over += 1;
+            self.under = 0;
+        } else if self.average < (self.budget * HEADROOM) {
+            self.under += 1;
+            self.over = 0;
+        } else {
+            self.over = 0;
+            self.under = 0;
+        }
+
+        if (SHED_FRAMES <= self.over) && (self.shed < Feature::ALL.len()) {
+            self.over = 0;
+            self.shed += 1;
+            return Some((Feature::ALL[self.shed - 1], false));
+        }
+        if (RESTORE_FRAMES <= self.under) && (0 < self.shed) {
+            self.under = 0;
+            self.shed -= 1;
+            return Some((Feature::ALL[self.shed], true));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_governor() {
+        let mut governor = Governor::new(Duration::from_millis(10));
+        let mut changes = vec![];
+        for _ in 0..200 {
+            changes.extend(governor.update(Duration::from_millis(20)));
+        }
+        assert!(changes.len() == Feature::ALL.len());
+        assert!(!governor.enabled(Feature::Multisample));
+
+        // NOTE: Just under budget is not enough headroom to bring anything back.
+        changes.clear();
+        for _ in 0..400 {
+            changes.extend(governor.update(Duration::from_millis(9)));
+        }
+        assert!(changes.is_empty());
+
+        for _ in 0..400 {
+            changes.extend(governor.update(Duration::from_millis(2)));
+        }
+        assert!(changes[0] == (Feature::Multisample, true));
+        assert!(governor.enabled(Feature::Trail) && !governor.enabled(Feature::FlowField));
+
+        governor.low_power = true;
+        assert!(Feature::ALL.iter().all(|feature| !governor.enabled(*feature)));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::math::Vec2;
+use std::collections::HashSet;
+use std::ffi::c_int;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Source {
+    Key(c_int),
+    Button(c_int),
+}
+
+// NOTE: `time` is GLFW's clock (`glfwGetTime`), in seconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Event {
+    pub source: Source,
+    pub pressed: bool,
+    pub time: f64,
+}
+
+// NOTE: GLFW callbacks `push` into the queue while events are polled; `update` then hands the
+// frame everything that happened since the last one, in order. A tap that starts and ends between
+// two frames still counts.
+#[derive(Default)]
+pub struct Input {
+    queue: Vec<Event>,
+    events: Vec<Event>,
+    held: HashSet<Source>,
+    // NOTE: Age of the oldest event handed to this frame, in seconds.
+    pub latency: f64,
+    // NOTE: The framebuffer's latest size, set when the window is resized and taken by the frame
+    // that handles it.
+    pub resized: Option<Vec2<c_int>>,
+}
+
+impl Input {
+    pub fn push(&mut self, event: Event) {
+        self.queue.push(event);
+    }
+
+    pub fn update(&mut self, now: f64) {
+        self.events.clear();
+        self.events.append(&mut self.queue);
+        self.latency = 0.0;
+        for event in &self.events {
+            if event.pressed {
+                self.held.insert(event.source);
+            } else {
+                self.held.remove(&event.source);
+            }
+            self.latency = self.latency.max(now - event.time);
+        }
+    }
+
+    pub fn held(&self, key: c_int) -> bool {
+        self.held.contains(&Source::Key(key))
+    }
+
+    fn pressed(&self, source: Source) -> bool {
+        self.events.iter().any(|event| event.pressed && (event.source == source))
+    }
+
+    pub fn tapped(&self, key: c_int) -> bool {
+        self.pressed(Source::Key(key))
+    }
+
+    pub fn clicked(&self, button: c_int) -> bool {
+        self.pressed(Source::Button(button))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input() {
+        let mut input = Input::default();
+        let event = |source, pressed, time| Event { source, pressed, time };
+        input.push(event(Source::Key(65), true, 1.0));
+        input.push(event(Source::Key(65), false, 1.001));
+        input.push(event(Source::Key(87), true, 1.002));
+        input.push(event(Source::Button(0), true, 1.003));
+        input.update(1.016);
+
+        // NOTE: The tap on `A` was over before the frame began, but isn't lost.
+        assert!(input.tapped(65) && !input.held(65));
+        assert!(input.tapped(87) && input.held(87));
+        assert!(input.clicked(0) && !input.tapped(0));
+        assert!((input.latency - 0.016).abs() < 1e-9);
+
+        input.update(1.032);
+        assert!(!input.tapped(87) && input.held(87) && !input.clicked(0));
+        assert!(input.latency == 0.0);
+    }
+}
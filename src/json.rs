@@ -0,0 +1,261 @@
+use crate::level::Error;
+use std::str::Chars;
+
+// NOTE: Just enough JSON for level files. Every value remembers where it started so callers can
+// point at the offending line and column (both 1-based, columns counted in characters), in the
+// `Error` that `fail` makes.
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+pub struct Node {
+    pub value: Value,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Node {
+    pub fn fail(&self, message: &str) -> Error {
+        fail(message, self.line, self.column)
+    }
+
+    pub fn as_bool(&self) -> Result<bool, Error> {
+        match self.value {
+            Value::Bool(bool) => Ok(bool),
+            _ => Err(self.fail("expected true or false")),
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64, Error> {
+        match self.value {
+            Value::Number(number) => Ok(number),
+            _ => Err(self.fail("expected a number")),
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, Error> {
+        match &self.value {
+            Value::String(string) => Ok(string),
+            _ => Err(self.fail("expected a string")),
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Self], Error> {
+        match &self.value {
+            Value::Array(array) => Ok(array),
+            _ => Err(self.fail("expected an array")),
+        }
+    }
+
+    pub fn as_map(&self) -> Result<&[(String, Self)], Error> {
+        match &self.value {
+            Value::Object(object) => Ok(object),
+            _ => Err(self.fail("expected an object")),
+        }
+    }
+
+    // NOTE: Fails on any key not in `keys`, so typos don't go unnoticed.
+    pub fn as_object(&self, keys: &[&str]) -> Result<&[(String, Self)], Error> {
+        let object = self.as_map()?;
+        for (key, node) in object {
+            if !keys.contains(&key.as_str()) {
+                return Err(node.fail(&format!("unexpected key {key:?}")));
+            }
+        }
+        Ok(object)
+    }
+}
+
+pub fn fail(message: &str, line: usize, column: usize) -> Error {
+    Error::Import {
+        format: "JSON",
+        message: format!("{message} at line {line}, column {column}"),
+    }
+}
+
+struct Parser<'a> {
+    chars: Chars<'a>,
+    line: usize,
+    column: usize,
+}
+
+impl Parser<'_> {
+    fn fail(&self, message: &str) -> Error {
+        fail(message, self.line, self.column)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.clone().next()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let char = self.chars.next()?;
+        if char == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(char)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|char| matches!(char, ' ' | '\t' | '\r' | '\n')) {
+            self.next();
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Error> {
+        if self.peek() != Some(expected) {
+            return Err(self.fail(&format!("expected {expected:?}")));
+        }
+        self.next();
+        Ok(())
+    }
+
+    fn keyword(&mut self, keyword: &str, value: Value) -> Result<Value, Error> {
+        for expected in keyword.chars() {
+            if self.peek() != Some(expected) {
+                return Err(self.fail(&format!("expected {keyword:?}")));
+            }
+            self.next();
+        }
+        Ok(value)
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        self.expect('"')?;
+        let mut string = String::new();
+        loop {
+            match self.next() {
+                Some('"') => return Ok(string),
+                Some('\\') => string.push(match self.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some(char @ ('"' | '\\' | '/')) => char,
+                    Some('u') => {
+                        let digits: String = (0..4).filter_map(|_| self.next()).collect();
+                        u32::from_str_radix(&digits, 16)
+                            .ok()
+                            .and_then(char::from_u32)
+                            .ok_or_else(|| self.fail("invalid unicode escape"))?
+                    }
+                    _ => return Err(self.fail("invalid escape")),
+                }),
+                Some('\n') | None => return Err(self.fail("unterminated string")),
+                Some(char) => string.push(char),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, Error> {
+        let mut number = String::new();
+        while let Some(char) = self
+            .peek()
+            .filter(|char| char.is_ascii_digit() || matches!(char, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            number.push(char);
+            self.next();
+        }
+        number.parse().map_err(|_| self.fail("invalid number"))
+    }
+
+    fn value(&mut self) -> Result<Node, Error> {
+        self.skip_whitespace();
+        let (line, column) = (self.line, self.column);
+        let value = match self.peek() {
+            Some('n') => self.keyword("null", Value::Null)?,
+            Some('t') => self.keyword("true", Value::Bool(true))?,
+            Some('f') => self.keyword("false", Value::Bool(false))?,
+            Some('"') => Value::String(self.string()?),
+            Some('[') => {
+                self.next();
+                let mut array = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.next();
+                } else {
+                    loop {
+                        array.push(self.value()?);
+                        self.skip_whitespace();
+                        match self.next() {
+                            Some(',') => (),
+                            Some(']') => break,
+                            _ => return Err(self.fail("expected ',' or ']'")),
+                        }
+                    }
+                }
+                Value::Array(array)
+            }
+            Some('{') => {
+                self.next();
+                let mut object: Vec<(String, Node)> = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.next();
+                } else {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        if object.iter().any(|(other, _)| *other == key) {
+                            return Err(self.fail(&format!("duplicate key {key:?}")));
+                        }
+                        self.skip_whitespace();
+                        self.expect(':')?;
+                        object.push((key, self.value()?));
+                        self.skip_whitespace();
+                        match self.next() {
+                            Some(',') => (),
+                            Some('}') => break,
+                            _ => return Err(self.fail("expected ',' or '}'")),
+                        }
+                    }
+                }
+                Value::Object(object)
+            }
+            Some(char) if (char == '-') || char.is_ascii_digit() => Value::Number(self.number()?),
+            _ => return Err(self.fail("expected a value")),
+        };
+        Ok(Node { value, line, column })
+    }
+}
+
+pub fn parse(text: &str) -> Result<Node, Error> {
+    let mut parser = Parser {
+        chars: text.chars(),
+        line: 1,
+        column: 1,
+    };
+    let node = parser.value()?;
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.fail("trailing characters"));
+    }
+    Ok(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let node = parse("{\"a\": [1, -2.5e1, \"x\\u00e9\"],\n \"b\": {\"c\": null}}").unwrap();
+        let object = node.as_object(&["a", "b"]).unwrap();
+        let array = object[0].1.as_array().unwrap();
+        assert!((array[1].as_f64().unwrap() + 25.0).abs() < f64::EPSILON);
+        assert!(array[2].as_str().unwrap() == "xé");
+        assert!((object[1].1.line, object[1].1.column) == (2, 7));
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let error = parse("{\"a\": 1,\n\"b\" 2}").err().unwrap();
+        assert!(error.to_string() == "JSON: expected ':' at line 2, column 5");
+    }
+}
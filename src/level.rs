@@ -0,0 +1,1676 @@
+use crate::geom::GridToWorld;
+use crate::mapgen::Rng;
+use crate::math::{Distance, Vec2};
+use crate::pathfinding::Graph;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{self, Write};
+use std::fs;
+use std::path::Path;
+
+pub const WATER_COST: f32 = 3.0;
+pub const MUD_COST: f32 = 2.0;
+
+// NOTE: Speed zones scale how fast things move through a cell without touching edge weights, so
+// heuristics stay admissible.
+pub const MUD_SPEED: f32 = 0.5;
+pub const BOOST_SPEED: f32 = 1.6;
+
+// NOTE: Added to the cost factor of edges off the highways, on plans that have any. Raising the
+// rest of the map rather than discounting the highways keeps weights above the straight-line
+// distance.
+pub const HIGHWAY_PREMIUM: f32 = 0.5;
+
+// NOTE: Edge weights are scaled by up to this fraction when a map asks for jitter.
+pub const JITTER: f32 = 0.05;
+
+// NOTE: How `connect` prices an edge between neighboring waypoints. `terrain` gets the per-cell
+// costs of both ends, `clearance` their distances to the nearest wall (added on top of `terrain`),
+// `door` is added to edges touching a door, and `highway` to edges that leave the highways (see
+// `HIGHWAY_PREMIUM`). Weights must stay at least the straight-line distance for the heuristics to
+// remain admissible.
+pub trait CostModel {
+    fn distance(&self, a: Vec2<f32>, b: Vec2<f32>) -> f32 {
+        a.distance(b)
+    }
+
+    fn terrain(&self, a: f32, b: f32) -> f32 {
+        (a + b) * 0.5
+    }
+
+    fn clearance(&self, _: f32, _: f32) -> f32 {
+        0.0
+    }
+
+    fn door(&self) -> f32 {
+        0.0
+    }
+
+    fn highway(&self) -> f32 {
+        HIGHWAY_PREMIUM
+    }
+}
+
+pub struct Standard;
+
+impl CostModel for Standard {}
+
+// NOTE: Makes edges next to walls dearer, by the given preference over the clearance of the edge's
+// tighter end, so paths keep to the middle of corridors.
+pub struct Clearance(pub f32);
+
+impl CostModel for Clearance {
+    fn clearance(&self, a: f32, b: f32) -> f32 {
+        self.0 / a.min(b)
+    }
+}
+
+// NOTE: What plain terrain costs on a plan, unless `!cost` says otherwise. Costs below `1.0` would
+// let weights drop under the straight-line distance, so `parse` rejects them.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TerrainCosts {
+    pub floor: f32,
+    pub water: f32,
+    pub mud: f32,
+}
+
+impl Default for TerrainCosts {
+    fn default() -> Self {
+        Self {
+            floor: 1.0,
+            water: WATER_COST,
+            mud: MUD_COST,
+        }
+    }
+}
+
+pub struct Level {
+    pub bounds: Vec2<u16>,
+    pub horizontals: Vec<Vec2<u16>>,
+    pub verticals: Vec<Vec2<u16>>,
+    pub waypoints: Vec<Vec2<u16>>,
+    pub doors: Vec<Vec2<u16>>,
+    pub walls: Vec<bool>,
+    pub costs: Vec<f32>,
+    pub speeds: Vec<f32>,
+    pub grades: Vec<u8>,
+    pub clearance: Vec<f32>,
+    pub waypoint_indices: HashMap<Vec2<u16>, usize>,
+    pub floors: Vec<u16>,
+    pub stairs: HashMap<Vec2<u16>, Vec2<u16>>,
+    pub jitter: Option<u64>,
+    pub tags: Vec<String>,
+    pub waypoint_tags: Vec<u32>,
+    pub spawn: Option<Vec2<u16>>,
+    pub goal: Option<Vec2<u16>>,
+    pub exit_tiles: Vec<Vec2<u16>>,
+    // NOTE: Per cell, and empty on plans without highways.
+    pub highways: Vec<bool>,
+    // NOTE: The one way each one-way tile may be crossed in, as a unit step.
+    pub one_ways: HashMap<Vec2<u16>, Vec2<i8>>,
+    // NOTE: World units per cell, when the plan asks for its own.
+    pub scale: Option<f32>,
+    pub diagonals: bool,
+    pub terrain_costs: TerrainCosts,
+}
+
+impl Level {
+    pub fn index(&self, cell: Vec2<u16>) -> usize {
+        (usize::from(cell.y) * usize::from(self.bounds.x)) + usize::from(cell.x)
+    }
+
+    pub fn cell(&self, i: usize) -> Vec2<u16> {
+        Vec2 {
+            x: (i % usize::from(self.bounds.x)).try_into().unwrap(),
+            y: (i / usize::from(self.bounds.x)).try_into().unwrap(),
+        }
+    }
+
+    pub fn floor(&self, cell: Vec2<u16>) -> usize {
+        self.floors.partition_point(|first_row| *first_row <= cell.y) - 1
+    }
+
+    // NOTE: The bit `Options::avoid` uses for `label`.
+    pub fn tag(&self, label: &str) -> Option<u32> {
+        self.tags.iter().position(|tag| tag == label).map(|i| 1 << i)
+    }
+
+    // NOTE: `cell` is in (fractional) grid space, as returned by `GridToWorld::world_to_cell`.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn nearest(&self, cell: Vec2<f32>) -> Option<Vec2<u16>> {
+        let x = cell.x.round();
+        let y = cell.y.round();
+        if (x < 0.0)
+            || (y < 0.0)
+            || (f32::from(self.bounds.x) <= x)
+            || (f32::from(self.bounds.y) <= y)
+        {
+            return None;
+        }
+        Some(Vec2 { x: x as u16, y: y as u16 })
+    }
+
+    pub fn speed(&self, cell: Vec2<f32>) -> f32 {
+        self.nearest(cell).map_or(1.0, |cell| self.speeds[self.index(cell)])
+    }
+
+    // NOTE: Cell costs are copied from `terrain_costs` rather than computed, so they compare
+    // exactly. Water no dearer than the floor around it is just floor.
+    #[allow(clippy::float_cmp)]
+    pub fn water(&self, i: usize) -> bool {
+        (self.costs[i] == self.terrain_costs.water) && (self.costs[i] != self.terrain_costs.floor)
+    }
+
+    pub fn on_exit(&self, cell: Vec2<f32>) -> bool {
+        self.nearest(cell).is_some_and(|cell| self.exit_tiles.contains(&cell))
+    }
+
+    // NOTE: Whether stepping from `from` to the neighboring `to` runs against the arrow of a
+    // one-way tile at either end, on the way in or out; steps across the arrow are fine.
+    pub fn against_one_way(&self, from: Vec2<u16>, to: Vec2<u16>) -> bool {
+        let dx = i32::from(to.x) - i32::from(from.x);
+        let dy = i32::from(to.y) - i32::from(from.y);
+        [from, to].iter().any(|cell| {
+            self.one_ways
+                .get(cell)
+                .is_some_and(|arrow| ((i32::from(arrow.x) * dx) + (i32::from(arrow.y) * dy)) < 0)
+        })
+    }
+}
+
+// NOTE: `.`, `~`, `#` and `*` are waypoints, ` ` is open floor that only becomes a waypoint with
+// `auto_waypoints`, and `D` is a door waypoint. `@` marks the player's spawn and `X` a goal to head
+// for from the start (one of each at most), and `E` waypoints lead on to the next level; all three
+// are plain waypoints otherwise. `H` waypoints are highways, which long trips go out of their way
+// to use (see `HIGHWAY_PREMIUM`). `>`, `<`, `↑` and `↓` (or `→` and `←`) are one-way waypoints
+// that may only be entered, crossed and left heading the way the arrow points. `#` (mud) and `*`
+// (boost pad) are speed zones, and the digits `1` to `9` are waypoints graded with that cost
+// multiplier (`0` in `grades` elsewhere).
+// Leading lines starting with `!` are directives: `!auto-waypoints` and `!jitter <seed>`,
+// `!tag <label> <x0> <y0> <x1> <y1>` which labels the waypoints inside the (inclusive) rectangle,
+// `!warp <x0> <y0> <x1> <y1>` which links two waypoints both ways like a flight of stairs,
+// `!fill <glyph>` which pads out short rows in place of the default open floor ` `,
+// `!scale <units>` for the world size of a cell, `!connectivity 4` to drop diagonal edges (`8` is
+// the default), and `!cost <floor|water|mud> <cost>` (see `TerrainCosts`). Lines starting with `;`
+// are comments, wherever they appear.
+// Plans may be up to 65535 cells on a side. A `=== floor N ===` line starts another floor (see
+// `stack_floors`); a `^` stair leads to the `v` at the same spot on the floor after it. Plans are
+// UTF-8, and box-drawing characters may stand in for walls and `·` for `.` (see `ascii`). Plans
+// that fail `validate` or `build` come back as its `Error`.
+pub fn parse(floor_plan: &[u8], auto_waypoints: bool) -> Result<Level, Error> {
+    validate(floor_plan, auto_waypoints)?;
+    let (header, grid, floors) = stack(floor_plan);
+    build(header, &grid, floors, auto_waypoints)
+}
+
+struct Directives {
+    auto_waypoints: bool,
+    jitter: Option<u64>,
+    tags: Vec<String>,
+    regions: Vec<(u32, Vec2<u16>, Vec2<u16>)>,
+    // NOTE: With the line each was given on, since its ends are only checked by `build`.
+    warps: Vec<(usize, Vec2<u16>, Vec2<u16>)>,
+    fill: u8,
+    scale: Option<f32>,
+    diagonals: bool,
+    terrain_costs: TerrainCosts,
+}
+
+// NOTE: `n` numbers, or `None` if there are more or fewer words or any doesn't parse.
+fn numbers<T: std::str::FromStr>(words: &str, n: usize) -> Option<Vec<T>> {
+    let numbers: Vec<T> =
+        words.split_whitespace().map(|word| word.parse().ok()).collect::<Option<_>>()?;
+    (numbers.len() == n).then_some(numbers)
+}
+
+impl Directives {
+    fn new(auto_waypoints: bool) -> Self {
+        Self {
+            auto_waypoints,
+            jitter: None,
+            tags: vec![],
+            regions: vec![],
+            warps: vec![],
+            fill: b' ',
+            scale: None,
+            diagonals: true,
+            terrain_costs: TerrainCosts::default(),
+        }
+    }
+
+    // NOTE: `directive` is the text of directive line `line`, after the `!`.
+    fn apply(&mut self, line: usize, directive: &str) -> Result<(), Error> {
+        let bad = || Error::BadDirective {
+            line,
+            directive: directive.to_owned(),
+        };
+        match directive.split_once(' ') {
+            None if directive == "auto-waypoints" => self.auto_waypoints = true,
+            Some(("jitter", seed)) => self.jitter = Some(seed.parse().map_err(|_| bad())?),
+            Some(("scale", value)) => {
+                let value: f32 = value.parse().map_err(|_| bad())?;
+                if value.is_nan() || (value <= 0.0) {
+                    return Err(bad());
+                }
+                self.scale = Some(value);
+            }
+            Some(("connectivity", "4")) => self.diagonals = false,
+            Some(("connectivity", "8")) => self.diagonals = true,
+            Some(("cost", cost)) => {
+                let (terrain, value) = cost.split_once(' ').ok_or_else(bad)?;
+                let value: f32 = value.trim().parse().map_err(|_| bad())?;
+                if value.is_nan() || (value < 1.0) {
+                    return Err(bad());
+                }
+                match terrain {
+                    "floor" => self.terrain_costs.floor = value,
+                    "water" => self.terrain_costs.water = value,
+                    "mud" => self.terrain_costs.mud = value,
+                    _ => return Err(bad()),
+                }
+            }
+            Some(("fill", cell)) => {
+                let mut chars = cell.chars();
+                self.fill = chars
+                    .next()
+                    .and_then(glyph)
+                    .filter(|_| chars.next().is_none())
+                    .ok_or_else(bad)?;
+            }
+            Some(("warp", cells)) => {
+                let cells: Vec<u16> = numbers(cells, 4).ok_or_else(bad)?;
+                self.warps.push((
+                    line,
+                    Vec2 { x: cells[0], y: cells[1] },
+                    Vec2 { x: cells[2], y: cells[3] },
+                ));
+            }
+            Some(("tag", region)) => {
+                let (label, bounds) = region.split_once(' ').ok_or_else(bad)?;
+                let bounds: Vec<u16> = numbers(bounds, 4).ok_or_else(bad)?;
+                let i = self.tags.iter().position(|tag| tag == label).unwrap_or(self.tags.len());
+                if 32 <= i {
+                    return Err(bad());
+                }
+                if i == self.tags.len() {
+                    self.tags.push(label.to_owned());
+                }
+                self.regions.push((
+                    1_u32 << i,
+                    Vec2 { x: bounds[0], y: bounds[1] },
+                    Vec2 { x: bounds[2], y: bounds[3] },
+                ));
+            }
+            Some(("connectivity", _)) => return Err(bad()),
+            _ => {
+                return Err(Error::UnknownDirective {
+                    line,
+                    directive: directive.to_owned(),
+                })
+            }
+        }
+        Ok(())
+    }
+}
+
+// NOTE: Reads the leading directive (and comment) lines, returning what they ask for and the rest
+// of the plan.
+fn directives(floor_plan: &[u8], auto_waypoints: bool) -> Result<(Directives, &[u8]), Error> {
+    let mut floor_plan = floor_plan;
+    let mut directives = Directives::new(auto_waypoints);
+    let mut line = 1;
+    while let Some(b'!' | b';') = floor_plan.first() {
+        let n = floor_plan.iter().position(|byte| *byte == b'\n').unwrap();
+        if floor_plan[0] == b'!' {
+            let directive =
+                std::str::from_utf8(&floor_plan[1..n]).map_err(|_| Error::NotUtf8 { line })?;
+            directives.apply(line, directive)?;
+        }
+        floor_plan = &floor_plan[(n + 1)..];
+        line += 1;
+    }
+    Ok((directives, floor_plan))
+}
+
+// NOTE: The two halves of `parse`, for loaders that keep plans in another form (see `compiled`).
+// `stack` splits off the leading directive lines and stacks the floors after them into one grid of
+// ASCII rows (see `stack_floors`), assuming the plan passed `validate`; `build` makes the level
+// from the two, checking only what `validate` can't see before the level is built.
+pub fn stack(floor_plan: &[u8]) -> (&[u8], Vec<u8>, Vec<u16>) {
+    let (directives, body) = directives(floor_plan, false).unwrap();
+    let header = &floor_plan[..(floor_plan.len() - body.len())];
+    let (grid, floors) = stack_floors(&ascii(body), directives.fill);
+    (header, grid, floors)
+}
+
+#[allow(clippy::many_single_char_names)]
+pub fn build(
+    header: &[u8],
+    grid: &[u8],
+    floors: Vec<u16>,
+    auto_waypoints: bool,
+) -> Result<Level, Error> {
+    let (directives, body) = directives(header, auto_waypoints)?;
+    assert!(body.is_empty());
+    let Directives {
+        auto_waypoints,
+        jitter,
+        tags,
+        regions,
+        warps,
+        scale,
+        diagonals,
+        terrain_costs,
+        ..
+    } = directives;
+
+    let mut horizontals = vec![];
+    let mut verticals = vec![];
+    let mut waypoints = vec![];
+    let mut doors = vec![];
+    let mut steps = vec![];
+    let mut spawn = None;
+    let mut goal = None;
+    let mut exit_tiles = vec![];
+    let mut one_ways = HashMap::new();
+
+    let mut x: u16 = 0;
+    let mut y: u16 = 0;
+    let mut w: u16 = 0;
+    let mut h: u16 = 0;
+    for byte in grid {
+        match byte {
+            b'\n' => {
+                x = 0;
+                y += 1;
+            }
+            _ => x += 1,
+        }
+        w = w.max(x);
+        h = h.max(y);
+    }
+
+    let mut walls = vec![false; usize::from(w) * usize::from(h)];
+    let mut costs = vec![terrain_costs.floor; usize::from(w) * usize::from(h)];
+    let mut speeds = vec![1.0; usize::from(w) * usize::from(h)];
+    let mut grades = vec![0; usize::from(w) * usize::from(h)];
+    let mut highways = vec![false; usize::from(w) * usize::from(h)];
+
+    x = 0;
+    y = 0;
+    for byte in grid {
+        let i = (usize::from(y) * usize::from(w)) + usize::from(x);
+        match byte {
+            b'\n' => {
+                assert!(x == w);
+                x = 0;
+                y += 1;
+            }
+            b'+' => {
+                horizontals.push(Vec2 { x, y });
+                verticals.push(Vec2 { x, y });
+                walls[i] = true;
+                x += 1;
+            }
+            b'-' => {
+                horizontals.push(Vec2 { x, y });
+                walls[i] = true;
+                x += 1;
+            }
+            b'|' => {
+                verticals.push(Vec2 { x, y });
+                walls[i] = true;
+                x += 1;
+            }
+            b'.' => {
+                waypoints.push(Vec2 { x, y });
+                x += 1;
+            }
+            b'D' => {
+                waypoints.push(Vec2 { x, y });
+                doors.push(Vec2 { x, y });
+                x += 1;
+            }
+            b'@' => {
+                if spawn.is_some() {
+                    return Err(Error::MoreThanOne { what: "spawn" });
+                }
+                waypoints.push(Vec2 { x, y });
+                spawn = Some(Vec2 { x, y });
+                x += 1;
+            }
+            b'X' => {
+                if goal.is_some() {
+                    return Err(Error::MoreThanOne { what: "goal" });
+                }
+                waypoints.push(Vec2 { x, y });
+                goal = Some(Vec2 { x, y });
+                x += 1;
+            }
+            b'E' => {
+                waypoints.push(Vec2 { x, y });
+                exit_tiles.push(Vec2 { x, y });
+                x += 1;
+            }
+            b'H' => {
+                waypoints.push(Vec2 { x, y });
+                highways[i] = true;
+                x += 1;
+            }
+            b'>' | b'<' | b'N' | b'S' => {
+                waypoints.push(Vec2 { x, y });
+                let arrow = match byte {
+                    b'>' => Vec2 { x: 1, y: 0 },
+                    b'<' => Vec2 { x: -1, y: 0 },
+                    b'N' => Vec2 { x: 0, y: -1 },
+                    _ => Vec2 { x: 0, y: 1 },
+                };
+                one_ways.insert(Vec2 { x, y }, arrow);
+                x += 1;
+            }
+            b' ' => {
+                if auto_waypoints {
+                    waypoints.push(Vec2 { x, y });
+                }
+                x += 1;
+            }
+            b'~' => {
+                waypoints.push(Vec2 { x, y });
+                costs[i] = terrain_costs.water;
+                x += 1;
+            }
+            b'#' => {
+                waypoints.push(Vec2 { x, y });
+                costs[i] = terrain_costs.mud;
+                speeds[i] = MUD_SPEED;
+                x += 1;
+            }
+            b'*' => {
+                waypoints.push(Vec2 { x, y });
+                speeds[i] = BOOST_SPEED;
+                x += 1;
+            }
+            b'^' | b'v' => {
+                waypoints.push(Vec2 { x, y });
+                steps.push((Vec2 { x, y }, *byte == b'^'));
+                x += 1;
+            }
+            b'1'..=b'9' => {
+                waypoints.push(Vec2 { x, y });
+                grades[i] = byte - b'0';
+                costs[i] = f32::from(grades[i]);
+                x += 1;
+            }
+            _ => unreachable!(),
+        }
+    }
+    assert!(y == h);
+    if !highways.contains(&true) {
+        highways.clear();
+    }
+
+    verticals.sort_unstable();
+    let waypoint_tags = waypoints
+        .iter()
+        .map(|waypoint| {
+            regions
+                .iter()
+                .filter(|(_, min, max)| {
+                    (min.x..=max.x).contains(&waypoint.x) && (min.y..=max.y).contains(&waypoint.y)
+                })
+                .fold(0, |mask, (bit, _, _)| mask | bit)
+        })
+        .collect();
+    let waypoint_indices: HashMap<Vec2<u16>, usize> =
+        waypoints.iter().enumerate().map(|(i, waypoint)| (*waypoint, i)).collect();
+    let clearance = clearance(&walls, Vec2 { x: w, y: h });
+    let floor = |y: u16| floors.partition_point(|first_row| *first_row <= y) - 1;
+    let mut stairs: HashMap<Vec2<u16>, Vec2<u16>> = HashMap::new();
+    for (cell, up) in &steps {
+        let from = floor(cell.y);
+        let to = if *up { from + 1 } else { from.wrapping_sub(1) };
+        let landing = floors.get(to).map(|first_row| Vec2 {
+            x: cell.x,
+            y: (cell.y - floors[from]) + first_row,
+        });
+        match landing.filter(|landing| steps.contains(&(*landing, !up))) {
+            Some(landing) => stairs.insert(*cell, landing),
+            None => return Err(Error::UnmatchedStair { x: cell.x, y: cell.y }),
+        };
+    }
+    for (line, a, b) in warps {
+        if !(waypoint_indices.contains_key(&a) && waypoint_indices.contains_key(&b)) {
+            return Err(Error::BadDirective {
+                line,
+                directive: format!("warp {} {} {} {}", a.x, a.y, b.x, b.y),
+            });
+        }
+        stairs.insert(a, b);
+        stairs.insert(b, a);
+    }
+    Ok(Level {
+        bounds: Vec2 { x: w, y: h },
+        horizontals,
+        verticals,
+        waypoints,
+        doors,
+        walls,
+        costs,
+        speeds,
+        grades,
+        clearance,
+        waypoint_indices,
+        floors,
+        stairs,
+        jitter,
+        tags,
+        waypoint_tags,
+        spawn,
+        goal,
+        exit_tiles,
+        highways,
+        one_ways,
+        scale,
+        diagonals,
+        terrain_costs,
+    })
+}
+
+// NOTE: Writes `level` back out in the grammar `parse` reads, walls set with `set_wall` included
+// (as `+`). Waypoints come out in their own glyphs (so `!auto-waypoints` isn't needed), stairs that
+// don't line up across floors as `!warp`s, and tags as one `!tag` per run of tagged waypoints in a
+// row. Costs the active cost model adds on top of the plan aren't part of it and aren't written.
+#[allow(clippy::float_cmp)]
+pub fn floor_plan(level: &Level) -> Vec<u8> {
+    let horizontals: HashSet<Vec2<u16>> = level.horizontals.iter().copied().collect();
+    let verticals: HashSet<Vec2<u16>> = level.verticals.iter().copied().collect();
+    let step = |a: Vec2<u16>, b: Vec2<u16>| {
+        let (from, to) = (level.floor(a), level.floor(b));
+        ((to == from + 1) || (to + 1 == from))
+            && (a.x == b.x)
+            && ((a.y - level.floors[from]) == (b.y - level.floors[to]))
+    };
+
+    let mut floor_plan = String::new();
+    if let Some(seed) = level.jitter {
+        writeln!(floor_plan, "!jitter {seed}").unwrap();
+    }
+    for (k, label) in level.tags.iter().enumerate() {
+        for y in 0..level.bounds.y {
+            let mut run: Option<u16> = None;
+            for x in 0..=level.bounds.x {
+                let tagged = level
+                    .waypoint_indices
+                    .get(&Vec2 { x, y })
+                    .is_some_and(|i| level.waypoint_tags[*i] & (1 << k) != 0);
+                match (run, tagged) {
+                    (None, true) => run = Some(x),
+                    (Some(x0), false) => {
+                        writeln!(floor_plan, "!tag {label} {x0} {y} {} {y}", x - 1).unwrap();
+                        run = None;
+                    }
+                    _ => (),
+                }
+            }
+        }
+    }
+    let mut warps: Vec<(Vec2<u16>, Vec2<u16>)> = level
+        .stairs
+        .iter()
+        .filter(|(a, b)| (a < b) && !step(**a, **b))
+        .map(|(a, b)| (*a, *b))
+        .collect();
+    warps.sort_unstable();
+    for (a, b) in warps {
+        writeln!(floor_plan, "!warp {} {} {} {}", a.x, a.y, b.x, b.y).unwrap();
+    }
+    if let Some(scale) = level.scale {
+        writeln!(floor_plan, "!scale {scale}").unwrap();
+    }
+    if !level.diagonals {
+        writeln!(floor_plan, "!connectivity 4").unwrap();
+    }
+    let defaults = TerrainCosts::default();
+    for (terrain, cost, default) in [
+        ("floor", level.terrain_costs.floor, defaults.floor),
+        ("water", level.terrain_costs.water, defaults.water),
+        ("mud", level.terrain_costs.mud, defaults.mud),
+    ] {
+        if cost != default {
+            writeln!(floor_plan, "!cost {terrain} {cost}").unwrap();
+        }
+    }
+
+    let mut floor_plan = floor_plan.into_bytes();
+    for y in 0..level.bounds.y {
+        if let Some(k) = level.floors.iter().position(|first_row| *first_row == y + 1) {
+            // NOTE: The row above each floor after the first is the wall `stack_floors` adds.
+            floor_plan.extend_from_slice(format!("=== floor {} ===\n", k + 1).as_bytes());
+            continue;
+        }
+        if (y == 0) && (1 < level.floors.len()) {
+            floor_plan.extend_from_slice(b"=== floor 1 ===\n");
+        }
+        for x in 0..level.bounds.x {
+            let cell = Vec2 { x, y };
+            let i = level.index(cell);
+            let byte = match (horizontals.contains(&cell), verticals.contains(&cell)) {
+                (true, false) => b'-',
+                (false, true) => b'|',
+                _ if level.walls[i] => b'+',
+                _ if !level.waypoint_indices.contains_key(&cell) => b' ',
+                _ if level.doors.contains(&cell) => b'D',
+                _ if level.spawn == Some(cell) => b'@',
+                _ if level.goal == Some(cell) => b'X',
+                _ if level.exit_tiles.contains(&cell) => b'E',
+                _ if level.highways.get(i) == Some(&true) => b'H',
+                _ if level.one_ways.contains_key(&cell) => match level.one_ways[&cell] {
+                    Vec2 { x: 1, .. } => b'>',
+                    Vec2 { x: -1, .. } => b'<',
+                    Vec2 { y: -1, .. } => b'N',
+                    _ => b'S',
+                },
+                _ => match level.stairs.get(&cell) {
+                    Some(landing) if step(cell, *landing) => {
+                        if level.floor(cell) < level.floor(*landing) {
+                            b'^'
+                        } else {
+                            b'v'
+                        }
+                    }
+                    _ if 0 < level.grades[i] => b'0' + level.grades[i],
+                    _ if level.speeds[i] == MUD_SPEED => b'#',
+                    _ if level.speeds[i] == BOOST_SPEED => b'*',
+                    _ if level.water(i) => b'~',
+                    _ => b'.',
+                },
+            };
+            match byte {
+                b'N' => floor_plan.extend_from_slice("↑".as_bytes()),
+                b'S' => floor_plan.extend_from_slice("↓".as_bytes()),
+                _ => floor_plan.push(byte),
+            }
+        }
+        floor_plan.push(b'\n');
+    }
+    floor_plan
+}
+
+// NOTE: Returns the byte `parse` reads for a cell drawn with `glyph`; box-drawing characters stand
+// in for the ASCII glyphs they look like.
+pub fn glyph(glyph: char) -> Option<u8> {
+    match glyph {
+        '─' | '━' => Some(b'-'),
+        '│' | '┃' => Some(b'|'),
+        '┼' | '┌' | '┐' | '└' | '┘' | '├' | '┤' | '┬' | '┴' => Some(b'+'),
+        '·' => Some(b'.'),
+        '→' => Some(b'>'),
+        '←' => Some(b'<'),
+        // NOTE: `^` and `v` are taken by stairs, and these bytes never appear in a plan.
+        '↑' => Some(b'N'),
+        '↓' => Some(b'S'),
+        '+'
+        | '-'
+        | '|'
+        | '.'
+        | 'D'
+        | '@'
+        | 'X'
+        | 'E'
+        | 'H'
+        | '>'
+        | '<'
+        | ' '
+        | '~'
+        | '#'
+        | '*'
+        | '^'
+        | 'v'
+        | '1'..='9' => u8::try_from(glyph).ok(),
+        _ => None,
+    }
+}
+
+// NOTE: What `validate` (or `build`) found wrong with a floor plan. Lines and columns count from
+// 1, directives included. `Import` is a level file in another format that didn't get as far as a
+// plan (see `loader`, `tmx`, `png`, `world` and `compiled`), with its position in `message` where
+// the format has one.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    NotUtf8 {
+        line: usize,
+    },
+    UnknownDirective {
+        line: usize,
+        directive: String,
+    },
+    BadDirective {
+        line: usize,
+        directive: String,
+    },
+    CrLf {
+        line: usize,
+    },
+    UnknownGlyph {
+        line: usize,
+        column: usize,
+        glyph: char,
+    },
+    NoWaypoints,
+    MoreThanOne {
+        what: &'static str,
+    },
+    UnmatchedStair {
+        x: u16,
+        y: u16,
+    },
+    Import {
+        format: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotUtf8 { line } => write!(f, "floor plan is not UTF-8 at line {line}"),
+            Self::UnknownDirective { line, directive } => {
+                write!(f, "unknown directive {directive:?} at line {line}")
+            }
+            Self::BadDirective { line, directive } => {
+                write!(f, "bad arguments in directive {directive:?} at line {line}")
+            }
+            Self::CrLf { line } => {
+                write!(f, "CRLF line ending at line {line} (save the plan with LF endings)")
+            }
+            Self::UnknownGlyph { line, column, glyph } => write!(
+                f,
+                "unsupported codepoint U+{:04X} {glyph:?} at line {line}, column {column}",
+                u32::from(*glyph),
+            ),
+            Self::NoWaypoints => write!(f, "floor plan has no waypoints"),
+            Self::MoreThanOne { what } => write!(f, "more than one {what}"),
+            Self::UnmatchedStair { x, y } => {
+                write!(f, "stair at {x} {y} has no landing on the floor it leads to")
+            }
+            Self::Import { format, message } => write!(f, "{format}: {message}"),
+        }
+    }
+}
+
+// NOTE: Checks everything `parse` needs of a plan's text, directive arguments included, stopping at
+// the first problem. What only shows once the level is built (markers, stairs and warp ends) is
+// left to `build`.
+pub fn validate(floor_plan: &[u8], auto_waypoints: bool) -> Result<(), Error> {
+    let text = std::str::from_utf8(floor_plan).map_err(|error| Error::NotUtf8 {
+        line: floor_plan[..error.valid_up_to()].split(|byte| *byte == b'\n').count(),
+    })?;
+    let mut header = Directives::new(auto_waypoints);
+    let mut directives = true;
+    let mut waypoints = false;
+    for (n, row) in text.split_terminator('\n').enumerate() {
+        let line = n + 1;
+        if row.ends_with('\r') {
+            return Err(Error::CrLf { line });
+        }
+        if row.starts_with(';') {
+            continue;
+        }
+        if directives && row.starts_with('!') {
+            header.apply(line, &row[1..])?;
+            continue;
+        }
+        directives = false;
+        if row.is_empty() || row.as_bytes().starts_with(FLOOR_MARKER) {
+            continue;
+        }
+        for (column, char) in row.chars().enumerate() {
+            let Some(byte) = glyph(char) else {
+                return Err(Error::UnknownGlyph {
+                    line,
+                    column: column + 1,
+                    glyph: char,
+                });
+            };
+            waypoints |=
+                !matches!(byte, b'+' | b'-' | b'|') && ((byte != b' ') || header.auto_waypoints);
+        }
+    }
+    if !waypoints {
+        return Err(Error::NoWaypoints);
+    }
+    Ok(())
+}
+
+// NOTE: Assumes the plan passed `validate`.
+fn ascii(floor_plan: &[u8]) -> Vec<u8> {
+    let text = std::str::from_utf8(floor_plan).unwrap();
+    let mut ascii = Vec::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        if line.starts_with(';') {
+            continue;
+        }
+        if line.as_bytes().starts_with(FLOOR_MARKER) {
+            ascii.extend_from_slice(line.as_bytes());
+            continue;
+        }
+        for char in line.chars() {
+            ascii.push(match char {
+                '\n' => b'\n',
+                _ => glyph(char).unwrap(),
+            });
+        }
+    }
+    ascii
+}
+
+const FLOOR_MARKER: &[u8] = b"===";
+
+// NOTE: Floors share one grid, laid out top to bottom in file order with a row of wall between
+// each, so nothing connects across floors except stairs. Rows shorter than the widest are padded
+// out with `fill`, and blank lines (a trailing newline, say) are dropped. Also returns the first
+// row of each floor.
+fn stack_floors(floor_plan: &[u8], fill: u8) -> (Vec<u8>, Vec<u16>) {
+    let lines = || floor_plan.split(|byte| *byte == b'\n').filter(|line| !line.is_empty());
+    let width = lines().filter(|line| !line.starts_with(FLOOR_MARKER)).map(<[u8]>::len).max();
+
+    let mut stacked = Vec::with_capacity(floor_plan.len());
+    let mut floors = vec![0];
+    let mut rows: u16 = 0;
+    for line in lines() {
+        if line.starts_with(FLOOR_MARKER) {
+            if 0 < rows {
+                stacked.extend(std::iter::repeat_n(b'-', width.unwrap()));
+                stacked.push(b'\n');
+                rows += 1;
+                floors.push(rows);
+            }
+            continue;
+        }
+        stacked.extend_from_slice(line);
+        stacked.extend(std::iter::repeat_n(fill, width.unwrap() - line.len()));
+        stacked.push(b'\n');
+        rows += 1;
+    }
+    (stacked, floors)
+}
+
+// NOTE: Chebyshev distance (in cells) from each cell to the nearest wall, counting the space past
+// the plan's edge as wall. Walls themselves are 0.
+fn clearance(walls: &[bool], bounds: Vec2<u16>) -> Vec<f32> {
+    let (width, height) = (usize::from(bounds.x), usize::from(bounds.y));
+    let mut distances = vec![f32::INFINITY; walls.len()];
+    let mut queue = VecDeque::new();
+    // NOTE: Walls are queued ahead of the edge cells so the queue stays sorted by distance.
+    for (i, wall) in walls.iter().enumerate() {
+        if *wall {
+            distances[i] = 0.0;
+            queue.push_back(i);
+        }
+    }
+    for i in 0..walls.len() {
+        let (x, y) = (i % width, i / width);
+        if !walls[i] && ((x == 0) || (y == 0) || (x == (width - 1)) || (y == (height - 1))) {
+            distances[i] = 1.0;
+            queue.push_back(i);
+        }
+    }
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = (i % width, i / width);
+        for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                let j = (ny * width) + nx;
+                if distances[j].is_infinite() {
+                    distances[j] = distances[i] + 1.0;
+                    queue.push_back(j);
+                }
+            }
+        }
+    }
+    distances
+}
+
+pub fn graph<M: CostModel>(level: &Level, grid: GridToWorld, model: &M) -> Graph<Vec2<f32>> {
+    let mut graph = Graph::new(
+        level
+            .waypoints
+            .iter()
+            .map(|waypoint| {
+                grid.cell_to_world(Vec2 {
+                    x: f32::from(waypoint.x),
+                    y: f32::from(waypoint.y),
+                })
+            })
+            .collect(),
+    );
+    for i in 0..graph.nodes.len() {
+        connect(&mut graph, level, model, i);
+    }
+    graph.tags.clone_from(&level.waypoint_tags);
+    graph
+}
+
+// NOTE: Bumped whenever `connect` changes which edges it builds, so stale sidecars are rebuilt.
+const EDGE_RULES: u8 = 1;
+
+// NOTE: FNV-1a, so keys stay stable across toolchains (unlike `DefaultHasher`).
+pub fn key(floor_plan: &[u8], level: &Level, grid: GridToWorld) -> u64 {
+    floor_plan
+        .iter()
+        .copied()
+        .chain([EDGE_RULES])
+        .chain(level.waypoints.iter().flat_map(|waypoint| {
+            waypoint.x.to_le_bytes().into_iter().chain(waypoint.y.to_le_bytes())
+        }))
+        // NOTE: Walls that aren't in the plan (see `scatter`) change the edges too.
+        .chain(level.walls.iter().map(|wall| u8::from(*wall)))
+        .chain(grid.scale.x.to_le_bytes())
+        .chain(grid.scale.y.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+// NOTE: The derived graph is cached in a sidecar file next to the floor plan; a stale or corrupt
+// sidecar is rebuilt and overwritten.
+pub fn load_graph<M: CostModel>(
+    path: &Path,
+    floor_plan: &[u8],
+    level: &Level,
+    grid: GridToWorld,
+    model: &M,
+) -> (Graph<Vec2<f32>>, bool) {
+    let key = key(floor_plan, level, grid);
+    let sidecar = path.with_extension("graph");
+    if let Some(graph) = fs::read(&sidecar).ok().and_then(|bytes| Graph::deserialize(&bytes, key)) {
+        if graph.nodes.len() == level.waypoints.len() {
+            // NOTE: Tags come from the floor plan's directives and are not stored in the sidecar.
+            let mut graph = graph;
+            graph.tags.clone_from(&level.waypoint_tags);
+            return (graph, true);
+        }
+    }
+    let graph = graph(level, grid, model);
+    // NOTE: The sidecar is only a cache, so a plan without a directory to write it to (an embedded
+    // one, see `assets`) just rebuilds its graph every time.
+    fs::write(&sidecar, graph.serialize(key)).ok();
+    (graph, false)
+}
+
+// NOTE: SplitMix64 finalizer over the seed and the (unordered) edge, mapped into `[0, 1)`; both
+// directions of an edge get the same noise.
+#[allow(clippy::cast_precision_loss)]
+fn noise(seed: u64, i: usize, j: usize) -> f32 {
+    let mut z = seed ^ ((i as u64) << 32) ^ (j as u64);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1_u64 << 24) as f32
+}
+
+// NOTE: Returns the nodes whose edges changed, the door's own node first.
+pub fn set_door(
+    graph: &mut Graph<Vec2<f32>>,
+    level: &Level,
+    door: Vec2<u16>,
+    open: bool,
+) -> Vec<usize> {
+    let i = level.waypoint_indices[&door];
+    let mut changed = vec![i];
+    for j in 0..graph.nodes.len() {
+        let version = graph.version;
+        if open {
+            graph.enable_edge(i, j);
+            graph.enable_edge(j, i);
+        } else {
+            graph.disable_edge(i, j);
+            graph.disable_edge(j, i);
+        }
+        if graph.version != version {
+            changed.push(j);
+        }
+    }
+    changed
+}
+
+// NOTE: Walls off the waypoint at `cell` (or clears it again) and reconnects it and its neighbors
+// right away. Clearance is recomputed, but edges further out that depend on it are left to the
+// caller. Returns the nodes that were reconnected.
+pub fn set_wall<M: CostModel>(
+    graph: &mut Graph<Vec2<f32>>,
+    level: &mut Level,
+    model: &M,
+    cell: Vec2<u16>,
+    wall: bool,
+) -> Vec<usize> {
+    assert!(level.waypoint_indices.contains_key(&cell));
+    let i = level.index(cell);
+    level.walls[i] = wall;
+    level.clearance = clearance(&level.walls, level.bounds);
+    let mut changed = vec![];
+    for y in cell.y.saturating_sub(1)..=(cell.y + 1).min(level.bounds.y - 1) {
+        for x in cell.x.saturating_sub(1)..=(cell.x + 1).min(level.bounds.x - 1) {
+            if let Some(j) = level.waypoint_indices.get(&Vec2 { x, y }) {
+                connect(graph, level, model, *j);
+                changed.push(*j);
+            }
+        }
+    }
+    changed
+}
+
+// NOTE: Waypoints walled off by `set_wall` keep their node but get no edges.
+pub fn connect<M: CostModel>(graph: &mut Graph<Vec2<f32>>, level: &Level, model: &M, i: usize) {
+    graph.clear_edges(i);
+
+    let waypoint = level.waypoints[i];
+    if level.walls[level.index(waypoint)] {
+        return;
+    }
+    let min_x = waypoint.x.saturating_sub(1);
+    let min_y = waypoint.y.saturating_sub(1);
+    let max_x = (waypoint.x + 1).min(level.bounds.x - 1);
+    let max_y = (waypoint.y + 1).min(level.bounds.y - 1);
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if ((x == waypoint.x) && (y == waypoint.y))
+                || (!level.diagonals && (x != waypoint.x) && (y != waypoint.y))
+            {
+                continue;
+            }
+            let Some(j) = level.waypoint_indices.get(&Vec2 { x, y }) else {
+                continue;
+            };
+            assert!(i != *j);
+            if level.walls[level.index(Vec2 { x, y })] {
+                continue;
+            }
+            // NOTE: A diagonal step may not squeeze past a wall on either side, or paths would
+            // clip wall corners.
+            if (x != waypoint.x)
+                && (y != waypoint.y)
+                && (level.walls[level.index(Vec2 { x, y: waypoint.y })]
+                    || level.walls[level.index(Vec2 { x: waypoint.x, y })])
+            {
+                continue;
+            }
+            if level.against_one_way(waypoint, Vec2 { x, y }) {
+                continue;
+            }
+            let neighbor = level.waypoints[*j];
+            let (a, b) = (level.index(waypoint), level.index(neighbor));
+            let mut factor = model.terrain(level.costs[a], level.costs[b])
+                + model.clearance(level.clearance[a], level.clearance[b]);
+            let exempt = level.highways.is_empty() || (level.highways[a] && level.highways[b]);
+            if !exempt {
+                factor += model.highway();
+            }
+            let factor = level.jitter.map_or(factor, |seed| {
+                JITTER.mul_add(noise(seed, i.min(*j), i.max(*j)), 1.0) * factor
+            });
+            let mut weight = model.distance(graph.nodes[i], graph.nodes[*j]) * factor;
+            if level.doors.contains(&waypoint) || level.doors.contains(&neighbor) {
+                weight += model.door();
+            }
+            assert!(weight.is_sign_positive());
+            graph.set_weight(i, *j, weight);
+        }
+    }
+
+    // NOTE: A flight of stairs costs its length as the crow flies, which keeps every heuristic
+    // admissible even though the floors' rows are stacked apart.
+    if let Some(landing) = level.stairs.get(&waypoint) {
+        let j = level.waypoint_indices[landing];
+        graph.set_weight(i, j, model.distance(graph.nodes[i], graph.nodes[j]));
+    }
+}
+
+// NOTE: Walls off `percent` of the open waypoints, picked at random from `seed`, as if they had
+// been `+` in the plan. The cells along one path from `start` to `goal` (when there is one) are
+// spared so the two stay connected, and so are doors and stairs. Returns how many cells were
+// walled.
+pub fn scatter(
+    level: &mut Level,
+    percent: usize,
+    seed: u64,
+    start: Vec2<u16>,
+    goal: Option<Vec2<u16>>,
+) -> usize {
+    assert!(percent <= 100);
+    let open: Vec<Vec2<u16>> = level
+        .waypoints
+        .iter()
+        .copied()
+        .filter(|waypoint| !level.walls[level.index(*waypoint)])
+        .collect();
+
+    let mut keep: HashSet<Vec2<u16>> = std::iter::once(start).chain(goal).collect();
+    if let Some(goal) = goal {
+        let mut previous: HashMap<Vec2<u16>, Vec2<u16>> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(cell) = queue.pop_front() {
+            if cell == goal {
+                let mut cell = goal;
+                while let Some(before) = previous.get(&cell) {
+                    keep.insert(*before);
+                    cell = *before;
+                }
+                break;
+            }
+            let mut neighbors = vec![];
+            if 0 < cell.x {
+                neighbors.push(Vec2 { x: cell.x - 1, ..cell });
+            }
+            if cell.x + 1 < level.bounds.x {
+                neighbors.push(Vec2 { x: cell.x + 1, ..cell });
+            }
+            if 0 < cell.y {
+                neighbors.push(Vec2 { y: cell.y - 1, ..cell });
+            }
+            if cell.y + 1 < level.bounds.y {
+                neighbors.push(Vec2 { y: cell.y + 1, ..cell });
+            }
+            neighbors.retain(|neighbor| !level.against_one_way(cell, *neighbor));
+            neighbors.extend(level.stairs.get(&cell));
+            for neighbor in neighbors {
+                if level.waypoint_indices.contains_key(&neighbor)
+                    && !level.walls[level.index(neighbor)]
+                    && (neighbor != start)
+                    && !previous.contains_key(&neighbor)
+                {
+                    previous.insert(neighbor, cell);
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+    }
+
+    let mut candidates: Vec<Vec2<u16>> = open
+        .iter()
+        .copied()
+        .filter(|cell| {
+            !keep.contains(cell) && !level.doors.contains(cell) && !level.stairs.contains_key(cell)
+        })
+        .collect();
+    let n = ((open.len() * percent) / 100).min(candidates.len());
+    let mut rng = Rng(seed);
+    for k in 0..n {
+        let pick = rng.range(k, candidates.len());
+        candidates.swap(k, pick);
+        let cell = candidates[k];
+        let i = level.index(cell);
+        level.walls[i] = true;
+        level.horizontals.push(cell);
+        level.verticals.push(cell);
+    }
+    level.horizontals.sort_unstable_by_key(|cell| (cell.y, cell.x));
+    level.verticals.sort_unstable();
+    level.clearance = clearance(&level.walls, level.bounds);
+    n
+}
+
+// NOTE: Rooms are 4-connected regions of floor cells; walls (and cells outside the plan) separate
+// them.
+pub fn rooms(level: &Level) -> (Vec<Option<usize>>, usize) {
+    let mut labels = vec![None; level.walls.len()];
+    let mut n = 0;
+
+    let mut queue = VecDeque::new();
+    for i in 0..level.walls.len() {
+        if level.walls[i] || labels[i].is_some() {
+            continue;
+        }
+        labels[i] = Some(n);
+        queue.push_back(i);
+        while let Some(j) = queue.pop_front() {
+            let x = j % usize::from(level.bounds.x);
+            let y = j / usize::from(level.bounds.x);
+            let mut neighbors = Vec::with_capacity(4);
+            if 0 < x {
+                neighbors.push(j - 1);
+            }
+            if x + 1 < usize::from(level.bounds.x) {
+                neighbors.push(j + 1);
+            }
+            if 0 < y {
+                neighbors.push(j - usize::from(level.bounds.x));
+            }
+            if y + 1 < usize::from(level.bounds.y) {
+                neighbors.push(j + usize::from(level.bounds.x));
+            }
+            for k in neighbors {
+                if level.walls[k] || labels[k].is_some() {
+                    continue;
+                }
+                labels[k] = Some(n);
+                queue.push_back(k);
+            }
+        }
+        n += 1;
+    }
+
+    (labels, n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding;
+
+    #[test]
+    fn test_terrain_costs() {
+        let level = parse(b".~#\n", false).unwrap();
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!((graph.weight(0, 1) - 2.0).abs() < 0.001);
+        assert!((graph.weight(1, 2) - 2.5).abs() < 0.001);
+        assert!(graph.weight(0, 2).is_infinite());
+    }
+
+    #[test]
+    fn test_graded_costs() {
+        let level = parse(b"1.9\n", false).unwrap();
+        assert!(level.grades == [1, 0, 9]);
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!((graph.weight(0, 1) - 1.0).abs() < 0.001);
+        assert!((graph.weight(1, 2) - 5.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_speed_zones() {
+        let level = parse(b".#*\n", false).unwrap();
+        assert!(level.waypoints.len() == 3);
+        assert!((level.speed(Vec2 { x: 0.2, y: 0.0 }) - 1.0).abs() < f32::EPSILON);
+        assert!((level.speed(Vec2 { x: 0.9, y: -0.3 }) - MUD_SPEED).abs() < f32::EPSILON);
+        assert!((level.speed(Vec2 { x: 2.4, y: 0.1 }) - BOOST_SPEED).abs() < f32::EPSILON);
+        assert!((level.speed(Vec2 { x: 5.0, y: 0.0 }) - 1.0).abs() < f32::EPSILON);
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!((graph.weight(1, 2) - 1.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_auto_waypoints() {
+        assert!(parse(b". .\n", false).unwrap().waypoints.len() == 2);
+        assert!(parse(b". .\n", true).unwrap().waypoints.len() == 3);
+        let level = parse(b"!auto-waypoints\n . \n", false).unwrap();
+        assert!(level.bounds == Vec2 { x: 3, y: 1 });
+        assert!(level.waypoints.len() == 3);
+    }
+
+    #[test]
+    fn test_jitter() {
+        let level = parse(b"!jitter 7\n...\n", false).unwrap();
+        assert!(level.jitter == Some(7));
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!((graph.weight(0, 1) - graph.weight(1, 0)).abs() < f32::EPSILON);
+        assert!((1.0..(1.0 + JITTER)).contains(&graph.weight(0, 1)));
+        assert!((graph.weight(0, 1) - graph.weight(1, 2)).abs() > 0.0);
+    }
+
+    #[test]
+    fn test_tags() {
+        let level = parse(
+            b"!tag indoor 1 0 2 1\n!tag road 0 1 3 1\n!tag indoor 3 0 3 0\n....\n....\n",
+            false,
+        )
+        .unwrap();
+        assert!(level.tags == ["indoor", "road"]);
+        assert!(
+            (level.tag("indoor"), level.tag("road"), level.tag("roof")) == (Some(1), Some(2), None)
+        );
+        assert!(level.waypoint_tags == [0, 1, 1, 1, 2, 3, 3, 2]);
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!(graph.tags == level.waypoint_tags);
+    }
+
+    #[test]
+    fn test_corner_cutting() {
+        let build = |floor_plan: &[u8]| {
+            let level = parse(floor_plan, false).unwrap();
+            graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard)
+        };
+        let open = build(b"..\n..\n");
+        assert!(open.weight(1, 2).is_finite() && open.weight(0, 3).is_finite());
+
+        // NOTE: One wall beside the diagonal is enough to block it.
+        let corner = build(b"..\n.-\n");
+        assert!(corner.weight(1, 2).is_infinite() && corner.weight(2, 1).is_infinite());
+        assert!(corner.weight(0, 1).is_finite() && corner.weight(0, 2).is_finite());
+
+        let pinched = build(b".|\n-.\n");
+        assert!(pinched.weight(0, 1).is_infinite());
+    }
+
+    #[test]
+    fn test_markers() {
+        let level = parse(b".XE\n@.E\n", false).unwrap();
+        assert!(level.waypoints.len() == 6);
+        assert!(
+            level.spawn == Some(Vec2 { x: 0, y: 1 }) && level.goal == Some(Vec2 { x: 1, y: 0 })
+        );
+        assert!(level.exit_tiles == [Vec2 { x: 2, y: 0 }, Vec2 { x: 2, y: 1 }]);
+        assert!(parse(b"...\n", false).unwrap().spawn.is_none());
+    }
+
+    #[test]
+    fn test_build_errors() {
+        assert!(parse(b"@.@\n", false).err() == Some(Error::MoreThanOne { what: "spawn" }));
+        assert!(
+            parse(b"=== floor 1 ===\n.^\n", false).err()
+                == Some(Error::UnmatchedStair { x: 1, y: 0 })
+        );
+        assert!(
+            parse(b";\n!warp 0 0 1 0\n.+\n", false).err()
+                == Some(Error::BadDirective {
+                    line: 2,
+                    directive: "warp 0 0 1 0".to_owned(),
+                })
+        );
+    }
+
+    #[test]
+    fn test_doors() {
+        let level = parse(b".D.\n", false).unwrap();
+        assert!(level.doors == [Vec2 { x: 1, y: 0 }]);
+        let mut graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!(set_door(&mut graph, &level, level.doors[0], false) == [1, 0, 2]);
+        assert!(graph.weight(0, 1).is_infinite() && graph.weight(1, 2).is_infinite());
+        set_door(&mut graph, &level, level.doors[0], true);
+        assert!(graph.weight(0, 1).is_finite() && graph.weight(2, 1).is_finite());
+    }
+
+    #[test]
+    fn test_set_wall() {
+        let mut level = parse(b"...\n...\n", false).unwrap();
+        let mut graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        let original = graph.weights.clone();
+        let cell = Vec2 { x: 1, y: 0 };
+        assert!(set_wall(&mut graph, &mut level, &Standard, cell, true).len() == 6);
+        assert!(
+            (0..6).all(|i| graph.weight(1, i).is_infinite() && graph.weight(i, 1).is_infinite())
+        );
+        // NOTE: The diagonal under the new wall would clip its corner.
+        assert!(graph.weight(0, 5).is_infinite() && graph.weight(3, 4).is_finite());
+        assert!((level.clearance[level.index(Vec2 { x: 1, y: 1 })] - 1.0).abs() < f32::EPSILON);
+        set_wall(&mut graph, &mut level, &Standard, cell, false);
+        assert!(graph.weights == original);
+    }
+
+    #[test]
+    fn test_box_drawing() {
+        let boxed = parse("┌─┐\n│·D\n└─┘\n".as_bytes(), false).unwrap();
+        let plain = parse(b"+-+\n|.D\n+-+\n", false).unwrap();
+        assert!(boxed.waypoints == plain.waypoints);
+        assert!(boxed.horizontals == plain.horizontals && boxed.verticals == plain.verticals);
+    }
+
+    #[test]
+    fn test_unsupported_codepoint() {
+        let error = parse("!jitter 1\n+.é\n".as_bytes(), false).err().unwrap();
+        assert!(error.to_string() == "unsupported codepoint U+00E9 'é' at line 2, column 3");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(b"!auto-waypoints\n+-+\n| |\n", false) == Ok(()));
+        for (floor_plan, error) in [
+            (&b"+-+\n|.\xFF\n"[..], Error::NotUtf8 { line: 2 }),
+            (
+                b"!seed 1\n...\n",
+                Error::UnknownDirective {
+                    line: 1,
+                    directive: "seed 1".to_owned(),
+                },
+            ),
+            (b"...\r\n...\r\n", Error::CrLf { line: 1 }),
+            (b"+-+\n|x|\n", Error::UnknownGlyph { line: 2, column: 2, glyph: 'x' }),
+            (b"+-+\n| |\n+-+\n", Error::NoWaypoints),
+        ] {
+            assert!(validate(floor_plan, false) == Err(error));
+        }
+        for directive in [
+            "scale abc",
+            "scale -1",
+            "cost water x",
+            "cost lava 2",
+            "tag a 1 2",
+            "warp 1 2",
+        ] {
+            let floor_plan = format!("; Bad.\n!{directive}\n...\n");
+            assert!(
+                validate(floor_plan.as_bytes(), false)
+                    == Err(Error::BadDirective {
+                        line: 2,
+                        directive: directive.to_owned(),
+                    })
+            );
+        }
+    }
+
+    #[test]
+    fn test_options() {
+        let level = parse(
+            b"; A small yard.\n\
+              !scale 20\n\
+              !connectivity 4\n\
+              ; Deep water.\n\
+              !cost water 5\n\
+              ..\n\
+              ; Row two.\n\
+              .~\n",
+            false,
+        )
+        .unwrap();
+        assert!(level.bounds == Vec2 { x: 2, y: 2 } && level.scale == Some(20.0));
+        assert!(
+            !level.diagonals
+                && (level.costs[level.index(Vec2 { x: 1, y: 1 })] - 5.0).abs() < f32::EPSILON
+        );
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!(graph.weight(0, 3) == f32::INFINITY && graph.weight(0, 1).is_finite());
+
+        let written = parse(&floor_plan(&level), false).unwrap();
+        assert!((written.scale, written.diagonals) == (level.scale, level.diagonals));
+        assert!(written.terrain_costs == level.terrain_costs && written.costs == level.costs);
+    }
+
+    #[test]
+    fn test_trailing_newline() {
+        let plan = parse(b"+-+\n|.|\n+-+\n", false).unwrap();
+        for floor_plan in [&b"+-+\n|.|\n+-+"[..], b"+-+\n|.|\n+-+\n\n\n"] {
+            let level = parse(floor_plan, false).unwrap();
+            assert!(level.bounds == plan.bounds && level.waypoints == plan.waypoints);
+        }
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let level = parse(b"+--+\n|.\n+--+\n", false).unwrap();
+        assert!(level.bounds == Vec2 { x: 4, y: 3 });
+        assert!(level.waypoints == [Vec2 { x: 1, y: 1 }]);
+        assert!(!level.walls[level.index(Vec2 { x: 3, y: 1 })]);
+
+        let level = parse(b"!fill .\n+--+\n|.\n+--+\n", false).unwrap();
+        assert!(level.waypoints.len() == 3);
+        assert!(level.waypoint_indices.contains_key(&Vec2 { x: 3, y: 1 }));
+    }
+
+    #[test]
+    fn test_floors() {
+        let level = parse(b"=== floor 1 ===\n.^\n=== floor 2 ===\n.v\n", false).unwrap();
+        assert!(level.floors == [0, 2]);
+        assert!(level.floor(Vec2 { x: 0, y: 2 }) == 1);
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        assert!((graph.weight(1, 3) - 2.0).abs() < f32::EPSILON);
+        assert!(graph.weight(3, 1).is_finite());
+        assert!(graph.weight(0, 2).is_infinite());
+    }
+
+    #[test]
+    fn test_floor_plan() {
+        let text = "!jitter 3\n!tag road 1 5 3 5\n!warp 0 4 3 5\n\
+                    === floor 1 ===\n+--+\n|.^~\n|#*D\n\
+                    === floor 2 ===\n1 @|\n.HvX\n9.E+\n";
+        let mut level = parse(text.as_bytes(), false).unwrap();
+        assert!(floor_plan(&level) == text.as_bytes());
+
+        let mut graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        set_wall(&mut graph, &mut level, &Standard, Vec2 { x: 1, y: 1 }, true);
+        let edited = parse(&floor_plan(&level), false).unwrap();
+        assert!(edited.walls == level.walls && edited.waypoints.len() == level.waypoints.len() - 1);
+    }
+
+    #[test]
+    fn test_cost_model() {
+        struct Cautious;
+
+        impl CostModel for Cautious {
+            fn terrain(&self, a: f32, b: f32) -> f32 {
+                a.max(b)
+            }
+
+            fn door(&self) -> f32 {
+                10.0
+            }
+        }
+
+        let level = parse(b".D.~\n", false).unwrap();
+        let grid = GridToWorld::new(level.bounds, 1.0);
+        let standard = graph(&level, grid, &Standard);
+        let cautious = graph(&level, grid, &Cautious);
+        assert!((standard.weight(0, 1) - 1.0).abs() < f32::EPSILON);
+        assert!((cautious.weight(0, 1) - 11.0).abs() < f32::EPSILON);
+        assert!(standard.weight(2, 3) < cautious.weight(2, 3));
+    }
+
+    #[test]
+    fn test_clearance() {
+        let level = parse(b"-----\n.....\n.....\n.....\n.....\n", false).unwrap();
+        let column: Vec<f32> =
+            (0..5).map(|y| level.clearance[level.index(Vec2 { x: 2, y })]).collect();
+        assert!(column == [0.0, 1.0, 2.0, 2.0, 1.0]);
+
+        // NOTE: Hugging the wall costs more than the same step a row further in.
+        let grid = GridToWorld::new(level.bounds, 1.0);
+        let graph = graph(&level, grid, &Clearance(1.0));
+        let (i, j) = (
+            level.waypoint_indices[&Vec2 { x: 1, y: 1 }],
+            level.waypoint_indices[&Vec2 { x: 2, y: 1 }],
+        );
+        let (k, l) = (
+            level.waypoint_indices[&Vec2 { x: 1, y: 2 }],
+            level.waypoint_indices[&Vec2 { x: 2, y: 2 }],
+        );
+        assert!((graph.weight(i, j) - 2.0).abs() < 0.001);
+        assert!((graph.weight(k, l) - 1.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_wide_plan() {
+        let mut floor_plan = vec![];
+        for y in 0..3 {
+            floor_plan.extend((0..1000).map(|x| if (x == 500) && (y < 2) { b'|' } else { b'.' }));
+            floor_plan.push(b'\n');
+        }
+        let level = parse(&floor_plan, false).unwrap();
+        assert!(level.bounds == Vec2 { x: 1000, y: 3 });
+        let far = Vec2 { x: 999, y: 2 };
+        assert!(level.cell(level.index(far)) == far);
+        assert!(level.waypoint_indices[&far] == level.waypoints.len() - 1);
+
+        let result = pathfinding::grid_path(
+            &level.walls,
+            &level.costs,
+            usize::from(level.bounds.x),
+            0,
+            level.index(Vec2 { x: 999, y: 0 }),
+            pathfinding::Options::default(),
+        );
+        assert!(result.nodes.iter().any(|i| level.cell(*i) == Vec2 { x: 500, y: 2 }));
+    }
+
+    #[test]
+    fn test_highways() {
+        let highways = b"HHHHHHHHHHHH\nH..........H\nH..........H\n";
+        for (floor_plan, detour) in [
+            (&highways[..], true),
+            (&highways.map(|byte| if byte == b'H' { b'.' } else { byte })[..], false),
+        ] {
+            let level = parse(floor_plan, false).unwrap();
+            assert!(level.highways.is_empty() != detour);
+            let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+            let result = pathfinding::shortest_path(
+                &graph,
+                level.waypoint_indices[&Vec2 { x: 0, y: 2 }],
+                level.waypoint_indices[&Vec2 { x: 11, y: 2 }],
+                pathfinding::Options::default(),
+            );
+            let along_highway = result.nodes.iter().any(|i| level.waypoints[*i].y == 0);
+            assert!(along_highway == detour);
+        }
+    }
+
+    #[test]
+    fn test_one_ways() {
+        let level = parse(".>.\n.↑.\n...\n".as_bytes(), false).unwrap();
+        let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+        let edge = |a: (u16, u16), b: (u16, u16)| {
+            let i = level.waypoint_indices[&Vec2 { x: a.0, y: a.1 }];
+            let j = level.waypoint_indices[&Vec2 { x: b.0, y: b.1 }];
+            graph.weight(i, j).is_finite()
+        };
+        assert!(edge((0, 0), (1, 0)) && edge((1, 0), (2, 0)));
+        assert!(!edge((1, 0), (0, 0)) && !edge((2, 0), (1, 0)));
+        assert!(edge((1, 2), (1, 1)) && edge((1, 1), (1, 0)));
+        assert!(!edge((1, 0), (1, 1)) && !edge((1, 1), (1, 2)));
+        assert!(edge((0, 1), (1, 1)) && edge((1, 1), (0, 1)));
+        assert!(edge((1, 1), (2, 0)) && !edge((2, 0), (1, 1)));
+
+        let result = pathfinding::shortest_path(
+            &graph,
+            level.waypoint_indices[&Vec2 { x: 2, y: 0 }],
+            level.waypoint_indices[&Vec2 { x: 0, y: 0 }],
+            pathfinding::Options::default(),
+        );
+        assert!(!result.nodes.iter().any(|i| level.waypoints[*i] == Vec2 { x: 1, y: 0 }));
+        assert!(floor_plan(&level) == ".>.\n.↑.\n...\n".as_bytes());
+        assert!(parse("→←↓\n".as_bytes(), false).unwrap().one_ways.len() == 3);
+    }
+
+    #[test]
+    fn test_scatter() {
+        let open = "@.........\n..........\n..........\n..........\n.........X\n".as_bytes();
+        let scattered = |seed| {
+            let mut level = parse(open, false).unwrap();
+            let (start, goal) = (level.spawn.unwrap(), level.goal);
+            let n = scatter(&mut level, 40, seed, start, goal);
+            (level, n)
+        };
+        for seed in 0..8 {
+            let (level, n) = scattered(seed);
+            assert!(n == 20);
+            assert!(level.walls.iter().filter(|wall| **wall).count() == 20);
+            assert!(level.horizontals.len() == 20);
+            let graph = graph(&level, GridToWorld::new(level.bounds, 1.0), &Standard);
+            let result = pathfinding::shortest_path(
+                &graph,
+                level.waypoint_indices[&level.spawn.unwrap()],
+                level.waypoint_indices[&level.goal.unwrap()],
+                pathfinding::Options::default(),
+            );
+            assert!(!result.nodes.is_empty());
+        }
+        assert!(scattered(1).0.walls == scattered(1).0.walls);
+        assert!(scattered(1).0.walls != scattered(2).0.walls);
+        assert!(scatter(&mut parse(open, false).unwrap(), 100, 0, Vec2 { x: 0, y: 0 }, None) == 49);
+    }
+
+    #[test]
+    fn test_rooms() {
+        let level = parse(b"..|..\n..|..\n--+--\n.....\n", false).unwrap();
+        let (labels, n) = rooms(&level);
+        assert!(n == 3);
+        assert!(labels[level.index(Vec2 { x: 0, y: 0 })] == Some(0));
+        assert!(labels[level.index(Vec2 { x: 4, y: 1 })] == Some(1));
+        assert!(labels[level.index(Vec2 { x: 2, y: 1 })].is_none());
+        assert!(labels[level.index(Vec2 { x: 4, y: 3 })] == Some(2));
+    }
+}
@@ -0,0 +1,221 @@
+use crate::json::{self, Node};
+use crate::level::{self, Error, Level};
+use crate::math::{Vec2, Vec3, Vec4};
+use std::fmt::Write;
+
+// NOTE: A JSON level file wraps an ordinary floor plan with metadata, e.g.
+//
+//     {
+//         "auto_waypoints": false,
+//         "directives": ["jitter 7"],
+//         "grid": ["+---+", "|.~.|", "+---+"],
+//         "spawn": [1, 1],
+//         "camera": [0, -30, 350],
+//         "palette": {
+//             "background": [0, 0, 0, 1],
+//             "floor": [0.2, 0.2, 0.2, 1],
+//             "wall": [1, 1, 1, 1]
+//         },
+//         "costs": {"~": 5}
+//     }
+//
+// Only `grid` is required; `floors` (an array of grids) may be given instead for multi-floor plans.
+// `directives` are the floor plan's `!` lines without the `!`, `costs` overrides the movement cost
+// of every cell drawn with the given glyph, and `spawn` wins over an `@` in the grid.
+const KEYS: [&str; 8] = [
+    "auto_waypoints",
+    "directives",
+    "grid",
+    "floors",
+    "spawn",
+    "camera",
+    "palette",
+    "costs",
+];
+
+#[derive(Default)]
+pub struct Palette {
+    pub background: Option<Vec4<f32>>,
+    pub floor: Option<Vec4<f32>>,
+    pub wall: Option<Vec4<f32>>,
+}
+
+pub struct LevelFile {
+    pub level: Level,
+    pub spawn: Option<Vec2<u16>>,
+    pub camera: Option<Vec3<f32>>,
+    pub palette: Palette,
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn numbers<const N: usize>(node: &Node) -> Result<[f32; N], Error> {
+    let mut numbers = [0.0; N];
+    let array = node.as_array()?;
+    if array.len() != N {
+        return Err(node.fail(&format!("expected {N} numbers")));
+    }
+    for (number, node) in numbers.iter_mut().zip(array) {
+        *number = node.as_f64()? as f32;
+    }
+    Ok(numbers)
+}
+
+fn cell(node: &Node) -> Result<Vec2<u16>, Error> {
+    let [x, y] = numbers(node)?;
+    if !(0.0..=f32::from(u16::MAX)).contains(&x)
+        || !(0.0..=f32::from(u16::MAX)).contains(&y)
+        || (x.fract() != 0.0)
+        || (y.fract() != 0.0)
+    {
+        return Err(node.fail("expected a cell"));
+    }
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    Ok(Vec2 { x: x as u16, y: y as u16 })
+}
+
+fn color(node: &Node) -> Result<Vec4<f32>, Error> {
+    let [x, y, z, w] = numbers(node)?;
+    Ok(Vec4 { x, y, z, w })
+}
+
+// NOTE: Glyphs are checked here, where their position in the file is known. Columns assume rows
+// hold no escapes.
+fn grid(node: &Node) -> Result<Vec<&str>, Error> {
+    node.as_array()?
+        .iter()
+        .map(|row| {
+            let text = row.as_str()?;
+            if let Some(column) = text.chars().position(|glyph| level::glyph(glyph).is_none()) {
+                return Err(json::fail("unsupported glyph", row.line, row.column + 1 + column));
+            }
+            Ok(text)
+        })
+        .collect()
+}
+
+impl From<Level> for LevelFile {
+    fn from(level: Level) -> Self {
+        Self {
+            spawn: level.spawn,
+            level,
+            camera: None,
+            palette: Palette::default(),
+        }
+    }
+}
+
+pub fn load(text: &str, auto_waypoints: bool) -> Result<LevelFile, Error> {
+    let root = json::parse(text)?;
+    let object = root.as_object(&KEYS)?;
+    let field = |key: &str| object.iter().find(|(other, _)| other == key).map(|(_, node)| node);
+
+    let floors: Vec<Vec<&str>> = match (field("grid"), field("floors")) {
+        (Some(node), None) => vec![grid(node)?],
+        (None, Some(node)) => node.as_array()?.iter().map(grid).collect::<Result<_, _>>()?,
+        (Some(_), Some(node)) => return Err(node.fail("expected either \"grid\" or \"floors\"")),
+        (None, None) => return Err(root.fail("expected \"grid\" or \"floors\"")),
+    };
+    let mut floor_plan = String::new();
+    for directive in field("directives").map_or(Ok(&[][..]), Node::as_array)? {
+        floor_plan.push('!');
+        floor_plan.push_str(directive.as_str()?);
+        floor_plan.push('\n');
+    }
+    for (k, rows) in floors.iter().enumerate() {
+        if 1 < floors.len() {
+            writeln!(floor_plan, "=== floor {} ===", k + 1).unwrap();
+        }
+        for row in rows {
+            floor_plan.push_str(row);
+            floor_plan.push('\n');
+        }
+    }
+    let auto_waypoints =
+        auto_waypoints || field("auto_waypoints").map_or(Ok(false), Node::as_bool)?;
+    let mut level = level::parse(floor_plan.as_bytes(), auto_waypoints)?;
+
+    for (glyph, node) in field("costs").map_or(Ok(&[][..]), Node::as_map)? {
+        let mut chars = glyph.chars();
+        let (Some(glyph), None) = (chars.next(), chars.next()) else {
+            return Err(node.fail("expected a single glyph"));
+        };
+        #[allow(clippy::cast_possible_truncation)]
+        let cost = node.as_f64()? as f32;
+        // NOTE: Like `!cost`, nothing under `1.0` (see `level::TerrainCosts`).
+        if !(cost.is_finite() && (1.0 <= cost)) {
+            return Err(node.fail("expected a cost of at least 1"));
+        }
+        for (k, rows) in floors.iter().enumerate() {
+            for (y, row) in (level.floors[k]..).zip(rows) {
+                for (x, _) in (0..).zip(row.chars()).filter(|(_, other)| *other == glyph) {
+                    let i = level.index(Vec2 { x, y });
+                    level.costs[i] = cost;
+                }
+            }
+        }
+    }
+
+    let spawn = field("spawn")
+        .map(|node| {
+            let spawn = cell(node)?;
+            if !level.waypoint_indices.contains_key(&spawn) {
+                return Err(node.fail("spawn is not a waypoint"));
+            }
+            Ok(spawn)
+        })
+        .transpose()?
+        .or(level.spawn);
+    let camera = field("camera")
+        .map(|node| {
+            let [x, y, z] = numbers(node)?;
+            Ok(Vec3 { x, y, z })
+        })
+        .transpose()?;
+    let mut palette = Palette::default();
+    if let Some(node) = field("palette") {
+        for (key, node) in node.as_object(&["background", "floor", "wall"])? {
+            let color = Some(color(node)?);
+            match key.as_str() {
+                "background" => palette.background = color,
+                "floor" => palette.floor = color,
+                _ => palette.wall = color,
+            }
+        }
+    }
+    Ok(LevelFile { level, spawn, camera, palette })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let level_file = load(
+            r#"{
+                "grid": ["+---+", "|.~.|", "+---+"],
+                "spawn": [3, 1],
+                "palette": {"wall": [1, 0, 0, 1]},
+                "costs": {"~": 5, ".": 1.5}
+            }"#,
+            false,
+        )
+        .unwrap();
+        let level = &level_file.level;
+        assert!(level.waypoints.len() == 3);
+        assert!(level_file.spawn == Some(Vec2 { x: 3, y: 1 }));
+        assert!(level_file.palette.wall.is_some_and(|wall| wall.y.abs() < f32::EPSILON));
+        let cost = |x| level.costs[level.index(Vec2 { x, y: 1 })];
+        assert!((1..4).map(cost).collect::<Vec<_>>() == [1.5, 5.0, 1.5]);
+    }
+
+    #[test]
+    fn test_load_error() {
+        let error = load("{\n    \"grid\": [\"+-+\", \"|x|\"]\n}", false).err().unwrap();
+        assert!(error.to_string() == "JSON: unsupported glyph at line 2, column 23");
+        let error = load("{\"directives\": [\"scale x\"], \"grid\": [\"..\"]}", false)
+            .err()
+            .unwrap();
+        assert!(error.to_string() == "bad arguments in directive \"scale x\" at line 1");
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Severity {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Info => "info",
+            Self::Warn => "warn",
+            Self::Error => "error",
+        }
+    }
+
+    // NOTE: See `https://en.wikipedia.org/wiki/ANSI_escape_code#Colors`.
+    const fn color(self) -> &'static str {
+        match self {
+            Self::Info => "\x1B[0m",
+            Self::Warn => "\x1B[33m",
+            Self::Error => "\x1B[31m",
+        }
+    }
+}
+
+// NOTE: Keeps the most recent `capacity` messages, each stamped with the frame it was logged on.
+pub struct Log {
+    capacity: usize,
+    entries: VecDeque<(u64, Severity, String)>,
+}
+
+impl Log {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, frame: u64, severity: Severity, message: String) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((frame, severity, message));
+    }
+
+    // NOTE: Writes the last `n` messages at or above `min`, oldest first, colored by severity.
+    pub fn panel(&self, min: Severity, n: usize, out: &mut String) {
+        let shown = |entry: &&(u64, Severity, String)| min <= entry.1;
+        let skip = self.entries.iter().filter(shown).count().saturating_sub(n);
+        for (frame, severity, message) in self.entries.iter().filter(shown).skip(skip) {
+            writeln!(
+                out,
+                "{}{frame:12} {:<5} {message}\x1B[0m",
+                severity.color(),
+                severity.name(),
+            )
+            .unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log() {
+        let mut log = Log::new(3);
+        log.push(0, Severity::Info, "a".to_owned());
+        log.push(1, Severity::Warn, "b".to_owned());
+        log.push(2, Severity::Info, "c".to_owned());
+        log.push(3, Severity::Error, "d".to_owned());
+
+        let mut out = String::new();
+        log.panel(Severity::Info, 2, &mut out);
+        assert!(out.lines().count() == 2);
+        assert!(out.contains(" c") && out.contains(" d") && !out.contains(" b"));
+
+        // NOTE: The oldest message was dropped to stay within capacity.
+        out.clear();
+        log.panel(Severity::Warn, 8, &mut out);
+        assert!(out.lines().count() == 2);
+        assert!(out.find("warn").unwrap() < out.find("error").unwrap());
+    }
+}
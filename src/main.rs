@@ -1,22 +1,58 @@
+mod agent;
+mod assets;
+mod bench;
+mod camera;
+mod compiled;
 mod defer;
+mod distance_field;
+mod explored;
 mod ffi;
+mod flow_field;
+mod format;
 mod geom;
+mod golden;
+mod governor;
+mod input;
+mod json;
+mod level;
+mod loader;
+mod log;
+mod mapgen;
 mod math;
+mod overlay;
 mod pathfinding;
+mod picking;
+mod png;
+mod pool;
+mod power;
 mod prelude;
+mod recovery;
+mod scenario;
+mod self_test;
+mod soak;
+mod stats;
+mod stress;
+mod tmx;
+mod tracked;
+mod tween;
+mod visibility;
+mod worker;
+mod world;
 
 use crate::defer::Defer;
-use crate::geom::{Geom, Line, Scale, Translate};
+use crate::geom::{Depth, Geom, GridToWorld, Line, Line3, Scale, Translate};
 use crate::math::{Distance, Dot, Mat4, Normalize, Vec2, Vec3, Vec4};
-use std::collections::HashMap;
+use std::collections::{HashSet, VecDeque};
 use std::convert::TryInto;
 use std::ffi::{c_char, c_int, c_void, CStr, CString};
+use std::fmt::Write;
 use std::fs;
 use std::mem;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::ptr;
 use std::slice::from_raw_parts;
 use std::str::from_utf8_unchecked;
+use std::thread;
 use std::time;
 
 const QUAD_VERTICES: [Vec2<f32>; 4] = [
@@ -39,16 +75,90 @@ const VIEW_DISTANCE: f32 = 350.0;
 const VIEW_TO_OFFSET: f32 = 25.0;
 const VIEW_UP: Vec3<f32> = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
 
+// NOTE: The spectator's projection keeps the whole level in range from any vantage point.
+const SPECTATOR_NEAR: f32 = 1.0;
+const SPECTATOR_FAR: f32 = 2000.0;
+const SPECTATOR_SPEED: f32 = 4.0;
+const SPECTATOR_SENSITIVITY: f32 = 0.003;
+
+// NOTE: Line width is in framebuffer pixels, so it is multiplied by the window's content scale.
+// Point-like markers are in world units and are scaled with the camera's distance to the ground
+// (within these bounds) so they keep roughly the same size on screen.
 const LINE_WIDTH: f32 = 4.0;
+const MIN_POINT_SCALE: f32 = 0.25;
+const MAX_POINT_SCALE: f32 = 4.0;
 
 const PLAYER_ACCEL: f32 = 0.6975;
 const PLAYER_DRAG: f32 = 0.825;
+// NOTE: Fraction of a grid cell within which a waypoint counts as reached.
+const PLAYER_REACH: f32 = 0.25;
 
 const PLAYER_QUAD_SCALE: f32 = 16.5;
 const PLAYER_LINE_SCALE: f32 = 6.75;
 const FLOOR_SCALE: f32 = 35.0;
 const WAYPOINT_SCALE: f32 = 4.5;
 const WAYPOINT_HIGHLIGHT_SCALE: f32 = 5.75;
+const WAYPOINT_PREVIEW_SCALE: f32 = 5.0;
+const RAY_MARKER_SCALE: f32 = 6.0;
+const DESTINATION_MARKER_SCALE: f32 = 9.0;
+const DESTINATION_MARKER_CAPACITY: usize = 64;
+// NOTE: Breadcrumbs are dropped every `TRAIL_SPACING` world units along the player's path, up to
+// `TRAIL_RANGE` units ahead.
+const TRAIL_DOTS: usize = 48;
+const TRAIL_SPACING: f32 = 14.0;
+const TRAIL_RANGE: f32 = 560.0;
+const TRAIL_DOT_SCALE: f32 = 2.25;
+
+const AGENTS: usize = 4;
+const AGENT_QUAD_SCALE: f32 = 11.0;
+// NOTE: Agents plan this many timesteps ahead and advance one timestep every `AGENT_STEP_FRAMES`.
+const AGENT_WINDOW: usize = 8;
+const AGENT_STEP_FRAMES: u32 = 20;
+const AGENT_FOLLOW: f32 = 0.2;
+// NOTE: Every other crowd agent is a vehicle, which plans for long straight runs by paying this
+// much (in cells) per right angle turned, and stops short of slots walkers reserved.
+const VEHICLE_TURN_PENALTY: f32 = 2.0;
+
+// NOTE: A node's heat flares up when its edges change, then fades out over `HEAT_FRAMES`.
+const HEAT_FRAMES: u32 = 100;
+const MARKER_POP_FRAMES: u32 = 12;
+const MARKER_FADE_FRAMES: u32 = 20;
+
+// NOTE: Graphs with at most this many nodes (overridable with `--all-pairs-max`) answer queries
+// from a precomputed next-hop table instead of searching.
+const ALL_PAIRS_MAX_NODES: usize = 128;
+
+// NOTE: In cells.
+const DOOR_REACH: f32 = 1.5;
+
+// NOTE: `-` and `=` step how strongly paths keep away from walls (see `level::Clearance`); the
+// graph loads at zero, where weights match `level::Standard`.
+const CLEARANCE_STEP: f32 = 0.25;
+const MAX_CLEARANCE_PREFERENCE: f32 = 2.0;
+
+// NOTE: CPU time a frame may take before the governor starts shedding optional visuals; it leaves
+// room under a 60Hz refresh for the driver's share.
+const FRAME_BUDGET: time::Duration = time::Duration::from_millis(12);
+
+// NOTE: `L` cycles the log panel under the HUD through all messages, warnings and up, errors only,
+// and hidden.
+const LOG_CAPACITY: usize = 256;
+const LOG_PANEL_LINES: usize = 8;
+
+const AUTOSAVE_INTERVAL: time::Duration = time::Duration::from_secs(5);
+const RELOAD_INTERVAL: time::Duration = time::Duration::from_millis(500);
+
+// NOTE: Low power (`B`, or `--low-power <on|off|auto>`) caps frames at `LOW_POWER_FRAME`, gives up
+// every optional visual (see `governor::Feature`), and while nothing moves sleeps in
+// `glfwWaitEventsTimeout` (up to `LOW_POWER_IDLE_WAIT`) until input arrives. `auto` keeps it on
+// while the machine runs on battery, checked every `POWER_POLL_INTERVAL`.
+const LOW_POWER_FRAME: time::Duration = time::Duration::from_micros(33_333);
+const LOW_POWER_IDLE_WAIT: time::Duration = time::Duration::from_millis(500);
+const POWER_POLL_INTERVAL: time::Duration = time::Duration::from_secs(10);
+const IDLE_SPEED: f32 = 0.01;
+
+const REBUILD_BUDGET: usize = 16;
+const SEARCH_BUDGET: usize = 200;
 
 const BACKGROUND_COLOR: Vec4<f32> = Vec4 { x: 0.1, y: 0.09, z: 0.11, w: 1.0 };
 const FLOOR_COLOR: Vec4<f32> = Vec4 {
@@ -58,11 +168,86 @@ const FLOOR_COLOR: Vec4<f32> = Vec4 {
     w: 0.25,
 };
 const WALL_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 1.0, z: 1.0, w: 0.9 };
+const CONTACT_SHADOW_COLOR: Vec4<f32> = Vec4 { x: 0.0, y: 0.0, z: 0.0, w: 0.12 };
+const CONTACT_SHADOW_BANDS: u8 = 3;
+// NOTE: How far into a floor cell (as a fraction of it) the widest band reaches.
+const CONTACT_SHADOW_SPREAD: f32 = 0.45;
 const PLAYER_QUAD_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.5, z: 0.75, w: 1.0 };
 const PLAYER_LINE_COLOR: Vec4<f32> = Vec4 { w: 0.375, ..PLAYER_QUAD_COLOR };
 const CURSOR_LINE_COLOR: Vec4<f32> = Vec4 { w: 0.15, ..PLAYER_QUAD_COLOR };
 const WAYPOINT_COLOR: Vec4<f32> = Vec4 { x: 0.4, y: 0.875, z: 0.9, w: 0.1 };
 const WAYPOINT_HIGHLIGHT_COLOR: Vec4<f32> = Vec4 { y: 1.0, w: 0.675, ..WAYPOINT_COLOR };
+const WAYPOINT_PREVIEW_COLOR: Vec4<f32> = Vec4 { w: 0.3, ..WAYPOINT_HIGHLIGHT_COLOR };
+const WAYPOINT_UNREACHABLE_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.25, z: 0.2, w: 0.9 };
+const OVERLAY_POINTS: usize = 4096;
+const OVERLAY_LINES: usize = 1024;
+const GENERATED_SIZE: &str = "64x48";
+
+const DESTINATION_MARKER_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.85, z: 0.2, w: 0.6 };
+const DESTINATION_MARKER_ACTIVE_COLOR: Vec4<f32> = Vec4 { w: 0.9, ..DESTINATION_MARKER_COLOR };
+const AGENT_COLOR: Vec4<f32> = Vec4 { x: 0.65, y: 0.55, z: 1.0, w: 0.9 };
+const VEHICLE_COLOR: Vec4<f32> = Vec4 {
+    x: 0.35,
+    y: 0.8,
+    z: 1.0,
+    ..AGENT_COLOR
+};
+const TRAIL_COLOR: Vec4<f32> = Vec4 { w: 0.5, ..PLAYER_QUAD_COLOR };
+const HEAT_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.45, z: 0.1, w: 0.45 };
+const RAY_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.3, z: 0.2, w: 0.8 };
+const RULER_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.95, z: 0.5, w: 0.8 };
+const WATER_COLOR: Vec4<f32> = Vec4 { x: 0.2, y: 0.45, z: 0.9, w: 0.3 };
+const DOOR_OPEN_COLOR: Vec4<f32> = Vec4 { x: 0.55, y: 0.85, z: 0.4, w: 0.5 };
+const DOOR_CLOSED_COLOR: Vec4<f32> = Vec4 { x: 0.85, y: 0.3, z: 0.25, w: 0.9 };
+const MUD_COLOR: Vec4<f32> = Vec4 { x: 0.45, y: 0.3, z: 0.15, w: 0.4 };
+const BOOST_COLOR: Vec4<f32> = Vec4 { x: 0.3, y: 0.9, z: 0.55, w: 0.35 };
+const EXIT_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.6, z: 1.0, w: 0.45 };
+const HIGHWAY_COLOR: Vec4<f32> = Vec4 { x: 0.6, y: 0.6, z: 0.65, w: 0.3 };
+const ONE_WAY_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.95, z: 0.95, w: 0.5 };
+// NOTE: Half the length of a one-way tile's arrow, in cells.
+const ONE_WAY_ARROW: f32 = 0.3;
+// NOTE: Graded tiles are tinted along a ramp from the cheapest (`1`) to the dearest (`9`) grade.
+const GRADE_LOW_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.9, z: 0.45, w: 0.2 };
+const GRADE_HIGH_COLOR: Vec4<f32> = Vec4 { x: 0.8, y: 0.15, z: 0.2, w: 0.5 };
+const ROOM_TINT_ALPHA: f32 = 0.125;
+const ROOM_COLORS: [Vec4<f32>; 6] = [
+    Vec4 {
+        x: 0.9,
+        y: 0.35,
+        z: 0.35,
+        w: ROOM_TINT_ALPHA,
+    },
+    Vec4 {
+        x: 0.35,
+        y: 0.9,
+        z: 0.35,
+        w: ROOM_TINT_ALPHA,
+    },
+    Vec4 {
+        x: 0.35,
+        y: 0.35,
+        z: 0.9,
+        w: ROOM_TINT_ALPHA,
+    },
+    Vec4 {
+        x: 0.9,
+        y: 0.9,
+        z: 0.35,
+        w: ROOM_TINT_ALPHA,
+    },
+    Vec4 {
+        x: 0.9,
+        y: 0.35,
+        z: 0.9,
+        w: ROOM_TINT_ALPHA,
+    },
+    Vec4 {
+        x: 0.35,
+        y: 0.9,
+        z: 0.9,
+        w: ROOM_TINT_ALPHA,
+    },
+];
 
 extern "C" fn callback_glfw_error(error_code: c_int, description: *const c_char) {
     let mut message = error_code.to_string();
@@ -73,6 +258,22 @@ extern "C" fn callback_glfw_error(error_code: c_int, description: *const c_char)
     panic!("{}", message);
 }
 
+// NOTE: The window's user pointer is the main loop's `input::Input`. Key repeats are dropped.
+fn push_input(window: *mut ffi::GLFWwindow, source: input::Source, action: c_int) {
+    if action == ffi::GLFW_REPEAT {
+        return;
+    }
+    unsafe {
+        let input = ffi::glfwGetWindowUserPointer(window).cast::<input::Input>();
+        assert!(!input.is_null());
+        (*input).push(input::Event {
+            source,
+            pressed: action == ffi::GLFW_PRESS,
+            time: ffi::glfwGetTime(),
+        });
+    }
+}
+
 extern "C" fn callback_glfw_key(
     window: *mut ffi::GLFWwindow,
     key: c_int,
@@ -80,14 +281,33 @@ extern "C" fn callback_glfw_key(
     action: c_int,
     _mods: c_int,
 ) {
-    if action != ffi::GLFW_PRESS {
-        return;
-    }
-    if key == ffi::GLFW_KEY_ESCAPE {
+    if (action == ffi::GLFW_PRESS) && (key == ffi::GLFW_KEY_ESCAPE) {
         unsafe {
             ffi::glfwSetWindowShouldClose(window, 1);
         }
     }
+    push_input(window, input::Source::Key(key), action);
+}
+
+extern "C" fn callback_glfw_mouse_button(
+    window: *mut ffi::GLFWwindow,
+    button: c_int,
+    action: c_int,
+    _mods: c_int,
+) {
+    push_input(window, input::Source::Button(button), action);
+}
+
+extern "C" fn callback_glfw_framebuffer_size(
+    window: *mut ffi::GLFWwindow,
+    width: c_int,
+    height: c_int,
+) {
+    unsafe {
+        let input = ffi::glfwGetWindowUserPointer(window).cast::<input::Input>();
+        assert!(!input.is_null());
+        (*input).resized = Some(Vec2 { x: width, y: height });
+    }
 }
 
 extern "C" fn callback_gl_debug(
@@ -106,6 +326,43 @@ extern "C" fn callback_gl_debug(
     panic!("{}", message);
 }
 
+#[derive(Clone, Copy, Default)]
+struct InstanceStats {
+    submitted: usize,
+    culled: usize,
+    visible: usize,
+}
+
+// NOTE: Counts what a frustum cull would drop: an instance is culled when every corner of its
+// bounds lies outside the same clip plane, and visible when it survives with non-zero extent and
+// alpha.
+fn instance_stats(geoms: &[Geom<f32>], clip: &Mat4<f32>, stats: &mut InstanceStats) {
+    for geom in geoms {
+        let corners = [(-0.5, -0.5), (0.5, -0.5), (-0.5, 0.5), (0.5, 0.5)].map(|(x, y)| {
+            Vec4 {
+                x: geom.scale.0.x.mul_add(x, geom.translate.0.x),
+                y: geom.scale.0.y.mul_add(y, geom.translate.0.y),
+                z: geom.depth.0.y.mul_add(x, geom.depth.0.x),
+                w: 1.0,
+            }
+            .dot(clip)
+        });
+        let outside = |f: fn(&Vec4<f32>) -> bool| corners.iter().all(f);
+        let culled = outside(|p| p.x < -p.w)
+            || outside(|p| p.w < p.x)
+            || outside(|p| p.y < -p.w)
+            || outside(|p| p.w < p.y)
+            || outside(|p| p.z < -p.w)
+            || outside(|p| p.w < p.z);
+        stats.submitted += 1;
+        if culled {
+            stats.culled += 1;
+        } else if (0.0 < geom.color.0.w) && (geom.scale.0 != Vec2::default()) {
+            stats.visible += 1;
+        }
+    }
+}
+
 fn compile_shader(r#type: ffi::GLenum, source: &str) -> ffi::GLuint {
     unsafe {
         let shader = ffi::glCreateShader(r#type);
@@ -120,9 +377,82 @@ fn compile_shader(r#type: ffi::GLenum, source: &str) -> ffi::GLuint {
     }
 }
 
-fn buffer<T>(target: ffi::GLuint, data: &[T], usage: ffi::GLenum) {
+// NOTE: Objects made once per window and reused by every `run`.
+#[derive(Clone, Copy)]
+struct Gl {
+    program: ffi::GLuint,
+    vao: [ffi::GLuint; 3],
+    vbo: [ffi::GLuint; 3],
+    instance_vbo: [ffi::GLuint; 3],
+}
+
+// NOTE: Shadows the bits of GL state we touch so redundant binds never reach the driver.
+#[derive(Default)]
+struct GlState {
+    program: Option<ffi::GLuint>,
+    vao: Option<ffi::GLuint>,
+    buffer: Option<ffi::GLuint>,
+    blend: Option<bool>,
+    changes: u64,
+    skipped: u64,
+}
+
+impl GlState {
+    fn track<T: PartialEq + Copy>(
+        current: &mut Option<T>,
+        next: T,
+        changes: &mut u64,
+        skipped: &mut u64,
+    ) -> bool {
+        if *current == Some(next) {
+            *skipped += 1;
+            return false;
+        }
+        *current = Some(next);
+        *changes += 1;
+        true
+    }
+
+    fn use_program(&mut self, program: ffi::GLuint) {
+        if Self::track(&mut self.program, program, &mut self.changes, &mut self.skipped) {
+            unsafe {
+                ffi::glUseProgram(program);
+            }
+        }
+    }
+
+    fn bind_vertex_array(&mut self, vao: ffi::GLuint) {
+        if Self::track(&mut self.vao, vao, &mut self.changes, &mut self.skipped) {
+            unsafe {
+                ffi::glBindVertexArray(vao);
+            }
+        }
+    }
+
+    fn bind_buffer(&mut self, buffer: ffi::GLuint) {
+        if Self::track(&mut self.buffer, buffer, &mut self.changes, &mut self.skipped) {
+            unsafe {
+                ffi::glBindBuffer(ffi::GL_ARRAY_BUFFER, buffer);
+            }
+        }
+    }
+
+    fn blend(&mut self, enabled: bool) {
+        if Self::track(&mut self.blend, enabled, &mut self.changes, &mut self.skipped) {
+            unsafe {
+                if enabled {
+                    ffi::glEnable(ffi::GL_BLEND);
+                } else {
+                    ffi::glDisable(ffi::GL_BLEND);
+                }
+            }
+        }
+    }
+}
+
+fn buffer<T>(gl_state: &mut GlState, target: ffi::GLuint, data: &[T], usage: ffi::GLenum) {
+    gl_state.bind_buffer(target);
     unsafe {
-        ffi::glBindBuffer(ffi::GL_ARRAY_BUFFER, target);
         ffi::glBufferData(
             ffi::GL_ARRAY_BUFFER,
             mem::size_of_val(data).try_into().unwrap(),
@@ -194,6 +524,7 @@ macro_rules! uniform {
 }
 
 fn buffers_and_attributes(
+    gl_state: &mut GlState,
     program: ffi::GLuint,
     vao: ffi::GLuint,
     vbo: ffi::GLuint,
@@ -201,33 +532,33 @@ fn buffers_and_attributes(
     geoms: &[Geom<ffi::GLfloat>],
     vertices: &[Vec2<ffi::GLfloat>],
 ) {
-    unsafe {
-        ffi::glBindVertexArray(vao);
-    }
+    gl_state.bind_vertex_array(vao);
 
-    buffer(vbo, vertices, ffi::GL_STATIC_DRAW);
+    buffer(gl_state, vbo, vertices, ffi::GL_STATIC_DRAW);
     unsafe {
         attribute!(program, Vec2<ffi::GLfloat>, position);
     }
 
-    buffer(instance_vbo, geoms, ffi::GL_DYNAMIC_DRAW);
+    buffer(gl_state, instance_vbo, geoms, ffi::GL_DYNAMIC_DRAW);
     unsafe {
         attribute!(program, Geom<ffi::GLfloat>, translate, 1);
         attribute!(program, Geom<ffi::GLfloat>, scale, 1);
         attribute!(program, Geom<ffi::GLfloat>, color, 1);
+        attribute!(program, Geom<ffi::GLfloat>, depth, 1);
     }
 }
 
 fn bind_and_draw(
+    gl_state: &mut GlState,
     vao: ffi::GLuint,
     instance_vbo: ffi::GLuint,
     geoms: &[Geom<ffi::GLfloat>],
     vertices: &[Vec2<ffi::GLfloat>],
     mode: ffi::GLenum,
 ) {
+    gl_state.bind_vertex_array(vao);
+    gl_state.bind_buffer(instance_vbo);
     unsafe {
-        ffi::glBindVertexArray(vao);
-        ffi::glBindBuffer(ffi::GL_ARRAY_BUFFER, instance_vbo);
         ffi::glBufferSubData(
             ffi::GL_ARRAY_BUFFER,
             0,
@@ -243,256 +574,285 @@ fn bind_and_draw(
     }
 }
 
-fn pressed(window: *mut ffi::GLFWwindow, key: c_int) -> bool {
-    unsafe { ffi::glfwGetKey(window, key) == ffi::GLFW_PRESS }
+struct CrowdAgent {
+    quad_idx: usize,
+    node: usize,
+    // NOTE: Agents without a goal hold their node.
+    goal: Option<usize>,
+    plan: VecDeque<usize>,
+    turn_penalty: Option<f32>,
+    velocity: Vec2<f32>,
+    replanned: u64,
 }
 
-#[allow(clippy::cognitive_complexity)]
-fn main() {
-    #[allow(clippy::cast_precision_loss)]
-    let projection = math::perspective(
-        45.0,
-        (WINDOW_WIDTH as f32) / (WINDOW_HEIGHT as f32),
-        VIEW_DISTANCE - 100.0,
-        VIEW_DISTANCE + 100.0,
-    );
-    let inverse_projection: Mat4<f32> = math::invert(&projection);
-
-    let mut camera = Vec3 {
-        x: 0.0,
-        y: CAMERA_OFFSET,
-        z: VIEW_DISTANCE,
-    };
-
-    let mut player_speed: Vec2<f32> = Vec2::default();
-    let mut camera_speed: Vec2<f32> = Vec2::default();
-
-    let mut world_cursor = Vec3::default();
-
-    let mut quads = vec![Geom {
-        translate: Vec2::default().into(),
-        scale: Vec2::<f32>::from(PLAYER_QUAD_SCALE).into(),
-        color: PLAYER_QUAD_COLOR.into(),
-    }];
-    let player_quad_idx = 0;
-
-    let mut lines = vec![
-        Geom {
-            translate: Vec2::default().into(),
-            scale: Vec2::default().into(),
-            color: PLAYER_LINE_COLOR.into(),
-        },
-        Geom {
-            translate: Vec2::default().into(),
-            scale: Vec2::default().into(),
-            color: CURSOR_LINE_COLOR.into(),
-        },
-    ];
-    let player_line_idx = 0;
-    let cursor_line_idx = 1;
+// NOTE: Writes the back buffer as a binary PPM, flipped so the first row is the top of the window.
+fn write_screenshot(path: &Path, framebuffer: Vec2<c_int>) {
+    let width = usize::try_from(framebuffer.x).unwrap();
+    let height = usize::try_from(framebuffer.y).unwrap();
+    let mut pixels = vec![0_u8; width * height * 3];
+    unsafe {
+        ffi::glReadPixels(
+            0,
+            0,
+            framebuffer.x,
+            framebuffer.y,
+            ffi::GL_RGB,
+            ffi::GL_UNSIGNED_BYTE,
+            pixels.as_mut_ptr().cast::<c_void>(),
+        );
+    }
+    let mut bytes = format!("P6\n{width} {height}\n255\n").into_bytes();
+    for row in pixels.chunks_exact(width * 3).rev() {
+        bytes.extend_from_slice(row);
+    }
+    fs::write(path, bytes).unwrap();
+}
 
-    let (bounds, horizontals, verticals, waypoints) = {
-        let floor_plan = fs::read(Path::new("assets").join("floor-plan.txt")).unwrap();
+fn heat(tweens: &mut tween::Tweens<usize>, quad_idx: usize) {
+    tweens.start(
+        quad_idx,
+        tween::Channel::Alpha,
+        HEAT_COLOR.w,
+        0.0,
+        HEAT_FRAMES,
+        tween::Easing::EaseOut,
+    );
+}
 
-        let mut horizontals = vec![];
-        let mut verticals = vec![];
-        let mut waypoints = vec![];
+// NOTE: Markers fade out before they are despawned, which happens once the fade finishes.
+fn fade_out(tweens: &mut tween::Tweens<pool::Handle>, marker: pool::Handle, geom: &Geom<f32>) {
+    tweens.cancel(marker);
+    tweens.start(
+        marker,
+        tween::Channel::Alpha,
+        geom.color.0.w,
+        0.0,
+        MARKER_FADE_FRAMES,
+        tween::Easing::Linear,
+    );
+}
 
-        let mut x: u8 = 0;
-        let mut y: u8 = 0;
-        let mut w: u8 = 0;
-        let mut h: u8 = 0;
-        for byte in &floor_plan {
-            match byte {
-                b'\n' => {
-                    x = 0;
-                    y += 1;
-                }
-                _ => x += 1,
-            }
-            w = w.max(x);
-            h = h.max(y);
-        }
+#[derive(Clone, Copy)]
+struct Trip {
+    traveled: f32,
+    planned: f32,
+    straight: f32,
+}
 
-        x = 0;
-        y = 0;
-        for byte in floor_plan {
-            match byte {
-                b'\n' => {
-                    assert!(x == w);
-                    x = 0;
-                    y += 1;
-                }
-                b'+' => {
-                    horizontals.push(Vec2 { x, y });
-                    verticals.push(Vec2 { x, y });
-                    x += 1;
-                }
-                b'-' => {
-                    horizontals.push(Vec2 { x, y });
-                    x += 1;
-                }
-                b'|' => {
-                    verticals.push(Vec2 { x, y });
-                    x += 1;
-                }
-                b'.' => {
-                    waypoints.push(Vec2 { x, y });
-                    x += 1;
-                }
-                _ => panic!(),
+fn line_of_sight(wall_bounds: &[(Vec2<f32>, Vec2<f32>)], a: Vec2<f32>, b: Vec2<f32>) -> bool {
+    for (min, max) in wall_bounds {
+        let corners = [
+            *min,
+            Vec2 { x: max.x, y: min.y },
+            *max,
+            Vec2 { x: min.x, y: max.y },
+        ];
+        for k in 0..corners.len() {
+            if math::segments_intersect(a, b, corners[k], corners[(k + 1) % corners.len()]) {
+                return false;
             }
         }
-        assert!(y == h);
+    }
+    true
+}
 
-        verticals.sort_unstable();
-        (Vec2 { x: w, y: h }, horizontals, verticals, waypoints)
-    };
+// NOTE: The box `line_of_sight` checks against for a single walled-off cell, padded like the
+// level's own walls.
+fn cell_bounds(grid: GridToWorld, cell: Vec2<u16>) -> (Vec2<f32>, Vec2<f32>) {
+    let center = grid.cell_to_world(Vec2 {
+        x: f32::from(cell.x),
+        y: f32::from(cell.y),
+    });
+    let extent = Vec2 {
+        x: grid.scale.x.abs(),
+        y: grid.scale.y.abs(),
+    } * 0.5.into();
+    let margin: Vec2<f32> = (PLAYER_QUAD_SCALE / 2.0).into();
+    (center - extent - margin, center + extent + margin)
+}
 
-    let walls = {
-        let mut walls = vec![];
+// NOTE: Where the camera centers on `floor`; floors are stacked down the grid.
+fn floor_camera_y(level: &level::Level, grid: GridToWorld, floor: usize) -> f32 {
+    let first_row = f32::from(level.floors[floor]);
+    let last_row = level.floors.get(floor + 1).map_or(level.bounds.y, |row| *row) - 1;
+    let middle = (first_row + f32::from(last_row)) * 0.5;
+    grid.cell_to_world(Vec2 { x: 0.0, y: middle }).y + CAMERA_OFFSET
+}
 
-        walls.push((Line(horizontals[0], horizontals[0]), true));
-        for horizontal in horizontals.into_iter().skip(1) {
-            let n = walls.len() - 1;
-            if (walls[n].0 .0.y != horizontal.y) || (walls[n].0 .1.x != (horizontal.x - 1)) {
-                walls.push((Line(horizontal, horizontal), true));
-                continue;
-            }
-            walls[n].0 .1.x = horizontal.x;
+fn load_level_file(
+    path: &Path,
+    floor_plan: &[u8],
+    auto_waypoints: bool,
+) -> Result<loader::LevelFile, level::Error> {
+    let text = |format| {
+        std::str::from_utf8(floor_plan).map_err(|_| level::Error::Import {
+            format,
+            message: "not UTF-8".to_owned(),
+        })
+    };
+    Ok(match path.extension().and_then(|extension| extension.to_str()) {
+        Some("json") => loader::load(text("JSON")?, auto_waypoints)?,
+        Some("tmx") => {
+            loader::LevelFile::from(level::parse(&tmx::import(text("TMX")?)?, auto_waypoints)?)
         }
-
-        walls.push((Line(verticals[0], verticals[0]), false));
-        for vertical in verticals.into_iter().skip(1) {
-            let n = walls.len() - 1;
-            if (walls[n].0 .0.x != vertical.x) || (walls[n].0 .1.y != (vertical.y - 1)) {
-                walls.push((Line(vertical, vertical), false));
-                continue;
+        Some("bin") => loader::LevelFile::from(compiled::load(floor_plan)?),
+        Some("png") => {
+            let (plan, spawn) = png::import(floor_plan)?;
+            loader::LevelFile {
+                spawn,
+                ..loader::LevelFile::from(level::parse(&plan, auto_waypoints)?)
             }
-            walls[n].0 .1.y = vertical.y;
-        }
-
-        walls
-    };
-
-    let k = Vec2 { x: FLOOR_SCALE, y: -FLOOR_SCALE };
-    let half_k = k * 0.5.into();
-    let half_bounds = Vec2 {
-        x: f32::from(bounds.x) * 0.5,
-        y: f32::from(bounds.y) * 0.5,
-    };
-
-    quads.push(Geom {
-        translate: Vec2::default().into(),
-        scale: Vec2 {
-            x: f32::from(bounds.x) * FLOOR_SCALE,
-            y: f32::from(bounds.y) * FLOOR_SCALE,
         }
-        .into(),
-        color: FLOOR_COLOR.into(),
-    });
-
-    for (wall, horizontal) in walls {
-        let wall = Line(
-            Vec2 {
-                x: f32::from(wall.0.x),
-                y: f32::from(wall.0.y),
-            },
-            Vec2 {
-                x: f32::from(wall.1.x),
-                y: f32::from(wall.1.y),
-            },
-        );
-
-        let mut translate: Translate<f32> = wall.into();
-        translate.0 -= half_bounds;
-        translate.0 *= k;
-        translate.0 += half_k;
+        _ => loader::LevelFile::from(level::parse(floor_plan, auto_waypoints)?),
+    })
+}
 
-        let mut scale: Scale<f32> = wall.into();
-        scale.0.x = scale.0.x.abs();
-        scale.0.y = scale.0.y.abs();
-        scale.0 += 1.0.into();
+// NOTE: A level that doesn't load at startup leaves nothing to run.
+fn or_exit<T>(path: &Path, result: Result<T, level::Error>) -> T {
+    result.unwrap_or_else(|error| {
+        eprintln!("{}: {error}", path.display());
+        std::process::exit(1);
+    })
+}
 
-        if horizontal {
-            scale.0.x *= k.x;
+// NOTE: Switches between the window and fullscreen on the primary monitor, at the monitor's current
+// video mode. `windowed` keeps the window's position and size for the way back. Either way the
+// framebuffer size callback follows, so the projections take the new aspect ratio instead of
+// stretching the old one.
+fn toggle_fullscreen(
+    window: *mut ffi::GLFWwindow,
+    windowed: &mut Option<(Vec2<c_int>, Vec2<c_int>)>,
+) {
+    unsafe {
+        if ffi::glfwGetWindowMonitor(window).is_null() {
+            let monitor = ffi::glfwGetPrimaryMonitor();
+            if monitor.is_null() {
+                return;
+            }
+            let mode = &*ffi::glfwGetVideoMode(monitor);
+            let mut position: Vec2<c_int> = Vec2::default();
+            let mut size: Vec2<c_int> = Vec2::default();
+            ffi::glfwGetWindowPos(window, &mut position.x, &mut position.y);
+            ffi::glfwGetWindowSize(window, &mut size.x, &mut size.y);
+            *windowed = Some((position, size));
+            ffi::glfwSetWindowMonitor(
+                window,
+                monitor,
+                0,
+                0,
+                mode.width,
+                mode.height,
+                mode.refresh_rate,
+            );
         } else {
-            scale.0.y *= k.y;
+            let (position, size) = windowed.take().unwrap();
+            ffi::glfwSetWindowMonitor(
+                window,
+                ptr::null_mut::<ffi::GLFWmonitor>(),
+                position.x,
+                position.y,
+                size.x,
+                size.y,
+                ffi::GLFW_DONT_CARE,
+            );
         }
-
-        quads.push(Geom {
-            translate,
-            scale,
-            color: WALL_COLOR.into(),
-        });
     }
+}
 
-    let first_waypoint_idx = quads.len();
-
-    let (nodes, map) = {
-        let mut nodes = Vec::with_capacity(waypoints.len());
-        let mut map = HashMap::with_capacity(waypoints.len());
+// NOTE: The main camera's projection and the spectator's, for a framebuffer of the given size.
+#[allow(clippy::cast_precision_loss)]
+fn projections(framebuffer: Vec2<c_int>) -> (Mat4<f32>, Mat4<f32>) {
+    let aspect_ratio = (framebuffer.x as f32) / (framebuffer.y as f32);
+    (
+        math::perspective(45.0, aspect_ratio, VIEW_DISTANCE - 100.0, VIEW_DISTANCE + 100.0),
+        math::perspective(45.0, aspect_ratio, SPECTATOR_NEAR, SPECTATOR_FAR),
+    )
+}
 
-        for (i, waypoint) in waypoints.iter().enumerate() {
-            let mut translate: Translate<f32> = Vec2 {
-                x: f32::from(waypoint.x),
-                y: f32::from(waypoint.y),
-            }
-            .into();
-            translate.0 -= half_bounds;
-            translate.0 *= k;
-            translate.0 += half_k;
+fn point_scale(distance: f32) -> f32 {
+    (distance.abs() / VIEW_DISTANCE).clamp(MIN_POINT_SCALE, MAX_POINT_SCALE)
+}
 
-            quads.push(Geom {
-                translate,
-                scale: Vec2::<f32>::from(WAYPOINT_SCALE).into(),
-                color: WAYPOINT_COLOR.into(),
-            });
-            nodes.push(translate.0);
-            map.insert(waypoint, i);
-        }
-        (nodes, map)
-    };
+// NOTE: Queries asked this frame and last frame. Route legs and the ruler are asked again every
+// frame; only the first ask (or a fresh search) counts as a query.
+type Asked = [HashSet<(usize, usize, pathfinding::Options)>; 2];
+
+// NOTE: Counts the lookup against its heuristic, as a hit if the cache already had the answer.
+fn cached_path(
+    path_cache: &mut pathfinding::PathCache,
+    stats: &mut stats::Stats,
+    asked: &mut Asked,
+    graph: &pathfinding::Graph<Vec2<f32>>,
+    i: usize,
+    j: usize,
+    options: pathfinding::Options,
+) -> pathfinding::PathResult {
+    let hits = path_cache.hits;
+    let result = path_cache.shortest_path(graph, i, j, options);
+    let hit = hits < path_cache.hits;
+    if !(hit && asked[1].contains(&(i, j, options))) {
+        stats.record(options.heuristic.name(), hit, result.expanded, result.cost);
+    }
+    asked[0].insert((i, j, options));
+    result
+}
 
-    let mut player_waypoint_idx = first_waypoint_idx;
-    quads[player_quad_idx].translate = quads[first_waypoint_idx].translate;
-
-    let edges = {
-        let mut edges = Vec::with_capacity(waypoints.len());
-        for (i, waypoint) in waypoints.iter().enumerate() {
-            let min_x = waypoint.x.saturating_sub(1);
-            let min_y = waypoint.y.saturating_sub(1);
-            let max_x = (waypoint.x + 1).min(bounds.x - 1);
-            let max_y = (waypoint.y + 1).min(bounds.y - 1);
-            for y in min_y..=max_y {
-                for x in min_x..=max_x {
-                    if (x == waypoint.x) && (y == waypoint.y) {
-                        continue;
-                    }
-                    let Some(j) = map.get(&Vec2 { x, y }) else {
-                        continue;
-                    };
-                    assert!(i != *j);
-                    edges.push((i, *j));
-                }
-            }
-        }
-        edges
-    };
+// NOTE: The values following flag `name`, one per placeholder in `usage`, or `None` if the flag
+// wasn't passed. Exits with a usage message if it was passed without them.
+fn flag_values<'a>(args: &'a [String], name: &str, usage: &[&str]) -> Option<&'a [String]> {
+    let i = args.iter().position(|arg| arg == name)?;
+    if let Some(values) = args.get((i + 1)..=(i + usage.len())) {
+        return Some(values);
+    }
+    eprintln!("usage: {name} {}", usage.join(" "));
+    std::process::exit(2);
+}
 
-    let weights = {
-        let mut weights = vec![f32::INFINITY; nodes.len() * nodes.len()];
-        for (i, j) in edges {
-            assert!(weights[(i * nodes.len()) + j].is_infinite());
-            let weight = nodes[i].distance(nodes[j]);
-            assert!(weight.is_sign_positive());
-            weights[(i * nodes.len()) + j] = weight;
-        }
-        weights
-    };
+fn flag_value<'a>(args: &'a [String], name: &str) -> Option<&'a str> {
+    flag_values(args, name, &["<value>"]).map(|values| values[0].as_str())
+}
 
-    println!("{}", unsafe { CStr::from_ptr(ffi::glfwGetVersionString()) }.to_str().unwrap());
+#[allow(clippy::cognitive_complexity)]
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.iter().any(|arg| arg == "--bench-maps") {
+        bench::run(Path::new("assets"), FLOOR_SCALE);
+        return;
+    }
+    if args.iter().any(|arg| arg == "--update-goldens") {
+        golden::update(Path::new("assets"));
+        return;
+    }
+    if args.iter().any(|arg| arg == "--self-test") {
+        let passed = self_test::run(&Path::new("assets").join("floor-plan.txt"), FLOOR_SCALE);
+        std::process::exit(i32::from(!passed));
+    }
+    // NOTE: `--compile-level <in> <out.bin>` writes any map the viewer reads as a compiled level
+    // (see `compiled`), which loads faster than its source.
+    if let Some([from, to]) = flag_values(&args, "--compile-level", &["<in>", "<out.bin>"]) {
+        let from = Path::new(from);
+        let auto_waypoints = args.iter().any(|arg| arg == "--auto-waypoints");
+        let level_file =
+            or_exit(from, load_level_file(from, &fs::read(from).unwrap(), auto_waypoints));
+        let bytes =
+            or_exit(from, compiled::compile(&level::floor_plan(&level_file.level), FLOOR_SCALE));
+        fs::write(to, bytes).unwrap();
+        return;
+    }
+    if let Some(minutes) = flag_value(&args, "--soak") {
+        let passed = soak::run(minutes.parse().unwrap());
+        std::process::exit(i32::from(!passed));
+    }
 
+    let mut log = log::Log::new(LOG_CAPACITY);
+    log.push(
+        0,
+        log::Severity::Info,
+        unsafe { CStr::from_ptr(ffi::glfwGetVersionString()) }
+            .to_str()
+            .unwrap()
+            .to_owned(),
+    );
     unsafe {
         ffi::glfwSetErrorCallback(callback_glfw_error);
         assert!(ffi::glfwInit() == 1);
@@ -506,7 +866,7 @@ fn main() {
         ffi::glfwWindowHint(ffi::GLFW_CONTEXT_VERSION_MAJOR, 3);
         ffi::glfwWindowHint(ffi::GLFW_CONTEXT_VERSION_MINOR, 3);
         ffi::glfwWindowHint(ffi::GLFW_OPENGL_PROFILE, ffi::GLFW_OPENGL_CORE_PROFILE);
-        ffi::glfwWindowHint(ffi::GLFW_RESIZABLE, 0);
+        ffi::glfwWindowHint(ffi::GLFW_RESIZABLE, 1);
         ffi::glfwWindowHint(ffi::GLFW_SAMPLES, 16);
     }
 
@@ -530,65 +890,53 @@ fn main() {
         ffi::glfwDestroyWindow(window);
     });
 
+    // NOTE: Must stay put while the window lives, since GLFW callbacks write through a pointer to
+    // it.
+    let mut input = input::Input::default();
+
+    let mut framebuffer: Vec2<c_int> = Vec2::default();
+    let mut content_scale: Vec2<f32> = Vec2::default();
+    unsafe {
+        ffi::glfwGetFramebufferSize(window, &mut framebuffer.x, &mut framebuffer.y);
+        ffi::glfwGetWindowContentScale(window, &mut content_scale.x, &mut content_scale.y);
+    }
+
     unsafe {
         ffi::glfwMakeContextCurrent(window);
         ffi::glfwSwapInterval(1);
+        ffi::glfwSetWindowUserPointer(window, ptr::addr_of_mut!(input).cast::<c_void>());
         ffi::glfwSetKeyCallback(window, callback_glfw_key);
+        ffi::glfwSetMouseButtonCallback(window, callback_glfw_mouse_button);
+        ffi::glfwSetFramebufferSizeCallback(window, callback_glfw_framebuffer_size);
 
         ffi::glEnable(ffi::GL_DEBUG_OUTPUT);
         ffi::glEnable(ffi::GL_DEBUG_OUTPUT_SYNCHRONOUS);
         ffi::glDebugMessageCallback(callback_gl_debug, ptr::null::<c_void>());
 
-        ffi::glEnable(ffi::GL_BLEND);
         ffi::glBlendFunc(ffi::GL_SRC_ALPHA, ffi::GL_ONE_MINUS_SRC_ALPHA);
-        ffi::glClearColor(
-            BACKGROUND_COLOR.x,
-            BACKGROUND_COLOR.y,
-            BACKGROUND_COLOR.z,
-            BACKGROUND_COLOR.w,
-        );
         ffi::glEnable(ffi::GL_MULTISAMPLE);
-        ffi::glViewport(0, 0, WINDOW_WIDTH, WINDOW_HEIGHT);
+        ffi::glViewport(0, 0, framebuffer.x, framebuffer.y);
+        ffi::glLineWidth(LINE_WIDTH * content_scale.x);
+        ffi::glEnable(ffi::GL_LINE_SMOOTH);
     }
 
-    let vao = {
-        let mut vao: [ffi::GLuint; 2] = [0; 2];
-        unsafe {
-            ffi::glGenVertexArrays(vao.len().try_into().unwrap(), vao.as_mut_ptr());
-        }
-        vao
-    };
-    defer!(unsafe {
-        ffi::glDeleteVertexArrays(vao.len().try_into().unwrap(), vao.as_ptr());
-    });
+    let mut vao: [ffi::GLuint; 3] = [0; 3];
+    tracked::gen_vertex_arrays(&mut vao);
+    defer!(tracked::delete_vertex_arrays(&vao));
 
-    let vbo = {
-        let mut vbo: [ffi::GLuint; 2] = [0; 2];
-        unsafe {
-            ffi::glGenBuffers(vbo.len().try_into().unwrap(), vbo.as_mut_ptr());
-        }
-        vbo
-    };
-    defer!(unsafe {
-        ffi::glDeleteBuffers(vbo.len().try_into().unwrap(), vbo.as_ptr());
-    });
+    let mut vbo: [ffi::GLuint; 3] = [0; 3];
+    tracked::gen_buffers(&mut vbo);
+    defer!(tracked::delete_buffers(&vbo));
 
-    let instance_vbo = {
-        let mut instance_vbo: [ffi::GLuint; 2] = [0; 2];
-        unsafe {
-            ffi::glGenBuffers(instance_vbo.len().try_into().unwrap(), instance_vbo.as_mut_ptr());
-        }
-        instance_vbo
-    };
-    defer!(unsafe {
-        ffi::glDeleteBuffers(instance_vbo.len().try_into().unwrap(), instance_vbo.as_ptr());
-    });
+    let mut instance_vbo: [ffi::GLuint; 3] = [0; 3];
+    tracked::gen_buffers(&mut instance_vbo);
+    defer!(tracked::delete_buffers(&instance_vbo));
 
     let program = unsafe { ffi::glCreateProgram() };
     {
         let vert_shader = compile_shader(
             ffi::GL_VERTEX_SHADER,
-            &fs::read_to_string(Path::new("src").join("vert.glsl")).unwrap(),
+            &assets::read_to_string(&Path::new("src").join("vert.glsl")),
         );
         defer!(unsafe {
             ffi::glDeleteShader(vert_shader);
@@ -596,7 +944,7 @@ fn main() {
 
         let frag_shader = compile_shader(
             ffi::GL_FRAGMENT_SHADER,
-            &fs::read_to_string(Path::new("src").join("frag.glsl")).unwrap(),
+            &assets::read_to_string(&Path::new("src").join("frag.glsl")),
         );
         defer!(unsafe {
             ffi::glDeleteShader(frag_shader);
@@ -613,71 +961,1121 @@ fn main() {
         ffi::glDeleteProgram(program);
     });
 
-    unsafe {
-        ffi::glUseProgram(program);
+    let gl = Gl { program, vao, vbo, instance_vbo };
+    // NOTE: `--stress` swaps the level for a synthetic scene that measures instancing throughput
+    // (see `stress::run`).
+    if args.iter().any(|arg| arg == "--stress") {
+        stress::run(window, &mut input, gl);
+        return;
+    }
+    // NOTE: `--levels <a>,<b>,...` plays floor plans in turn; stepping onto an `E` tile moves on to
+    // the next one, and after the last back to the first.
+    let levels: Vec<PathBuf> = flag_value(&args, "--levels")
+        .map(|levels| levels.split(',').map(PathBuf::from).collect())
+        .unwrap_or_default();
+    let mut k = 0;
+    let mut windowed = None;
+    let mut reloaded = None;
+    loop {
+        let level_path = levels.get(k).map(PathBuf::as_path);
+        let outcome = run(
+            &args,
+            level_path,
+            reloaded.take(),
+            window,
+            &mut windowed,
+            &mut input,
+            &mut log,
+            gl,
+        );
+        match outcome {
+            Outcome::Closed => break,
+            Outcome::Changed(loaded) => reloaded = Some(*loaded),
+            Outcome::Exited => k = (k + 1) % levels.len(),
+        }
+    }
+}
 
-        ffi::glLineWidth(LINE_WIDTH);
-        ffi::glEnable(ffi::GL_LINE_SMOOTH);
+// NOTE: A plan as loaded from its file: the plan itself (stacked, for a world), the world it came
+// from, if any, and the level file built from it.
+struct Loaded {
+    floor_plan: Vec<u8>,
+    world: Option<world::World>,
+    level_file: loader::LevelFile,
+}
 
-        uniform!(program, projection);
+// NOTE: Why `run` returned: the window was closed, the plan changed on disk (so the same plan is
+// run again, from what the reload already loaded), or the player took an exit to the next level.
+enum Outcome {
+    Closed,
+    Changed(Box<Loaded>),
+    Exited,
+}
+
+// NOTE: Builds everything that depends on the floor plan and runs frames until there's reason to
+// stop (see `Outcome`). `level_path`, when given, wins over any other plan, and `reloaded` over
+// loading it again.
+#[allow(clippy::too_many_arguments)]
+fn run(
+    args: &[String],
+    level_path: Option<&Path>,
+    reloaded: Option<Loaded>,
+    window: *mut ffi::GLFWwindow,
+    windowed: &mut Option<(Vec2<c_int>, Vec2<c_int>)>,
+    input: &mut input::Input,
+    log: &mut log::Log,
+    gl: Gl,
+) -> Outcome {
+    let Gl { program, vao, vbo, instance_vbo } = gl;
+    let arg = |name: &str| flag_value(args, name);
+    let mut options = pathfinding::Options {
+        heuristic: arg("--heuristic").map_or(pathfinding::Heuristic::Euclidean, |name| {
+            pathfinding::Heuristic::from_name(name).unwrap()
+        }),
+        tie_break: arg("--tie-break").map_or(pathfinding::TieBreak::None, |name| {
+            pathfinding::TieBreak::from_name(name).unwrap()
+        }),
+        epsilon: 0,
+        // NOTE: A blocked-off destination still draws the player as close as it can get.
+        partial: true,
+        avoid: 0,
+    };
+
+    let mut framebuffer: Vec2<c_int> = Vec2::default();
+    unsafe {
+        ffi::glfwGetFramebufferSize(window, &mut framebuffer.x, &mut framebuffer.y);
     }
+    let (mut projection, mut spectator_projection) = projections(framebuffer);
 
-    buffers_and_attributes(program, vao[0], vbo[0], instance_vbo[0], &quads, &QUAD_VERTICES);
-    buffers_and_attributes(program, vao[1], vbo[1], instance_vbo[1], &lines, &LINE_VERTICES);
+    let mut camera = Vec3 {
+        x: 0.0,
+        y: CAMERA_OFFSET,
+        z: VIEW_DISTANCE,
+    };
 
-    let mut now = time::Instant::now();
-    let mut frames = 0;
-    let mut path_counter = 0;
+    let mut camera_speed: Vec2<f32> = Vec2::default();
 
-    println!("\n\n\n\n\n\n");
-    while unsafe { ffi::glfwWindowShouldClose(window) } != 1 {
-        let elapsed = now.elapsed();
-        if 0 < elapsed.as_secs() {
-            println!(
-                "\x1B[7A\
-                 {:12.2} elapsed ns\n\
-                 {frames:12} frames\n\
-                 {:12} ns / frame\n\
+    let mut spectator: Option<camera::Spectator> = None;
+    let mut spectator_cursor: Vec2<f64> = Vec2::default();
+
+    let mut world_cursor = Vec3::default();
+
+    let mut quads = vec![Geom {
+        translate: Vec2::default().into(),
+        scale: Vec2::<f32>::from(PLAYER_QUAD_SCALE).into(),
+        color: PLAYER_QUAD_COLOR.into(),
+        depth: Depth::default(),
+    }];
+    let player_quad_idx = 0;
+
+    let mut lines = vec![
+        Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: PLAYER_LINE_COLOR.into(),
+            depth: Depth::default(),
+        },
+        Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: CURSOR_LINE_COLOR.into(),
+            depth: Depth::default(),
+        },
+    ];
+    let player_line_idx = 0;
+    let cursor_line_idx = 1;
+
+    let mut scenario =
+        arg("--scenario").map(|path| scenario::parse(&fs::read_to_string(path).unwrap()));
+    // NOTE: `--generate <rooms|caves>` (with `--seed <n>` and `--size <w>x<h>`) stands in for a map
+    // file; a map given as the first argument wins over the scenario's.
+    let generator = arg("--generate").map(|name| mapgen::Generator::from_name(name).unwrap());
+    let seed: u64 = arg("--seed").map_or(0, |seed| seed.parse().unwrap());
+    let floor_plan_path = generator.map_or_else(
+        || {
+            level_path
+                .map(Path::to_path_buf)
+                .or_else(|| args.get(1).filter(|arg| !arg.starts_with("--")).map(PathBuf::from))
+                .or_else(|| scenario.as_ref().and_then(scenario::Scenario::map).cloned())
+                .unwrap_or_else(|| Path::new("assets").join("floor-plan.txt"))
+        },
+        |generator| Path::new("assets").join(format!("{}-{seed}.txt", generator.name())),
+    );
+    let n_agents = scenario.as_ref().and_then(scenario::Scenario::agents).unwrap_or(AGENTS);
+    let auto_waypoints = args.iter().any(|arg| arg == "--auto-waypoints");
+    // NOTE: A `.world` file stacks its maps into one plan, one map per floor. Walking onto an exit
+    // warps the player to the linked entry; `--plan-across-maps` lets paths go through exits too.
+    let plan_across = args.iter().any(|arg| arg == "--plan-across-maps");
+    let load_world = |text: &str| {
+        let directory = floor_plan_path.parent().unwrap();
+        world::load(text, plan_across, auto_waypoints, |path| fs::read(directory.join(path)).ok())
+    };
+    let is_world = floor_plan_path.extension().is_some_and(|extension| extension == "world");
+    let load = |contents: &[u8]| -> Result<Loaded, level::Error> {
+        let world = if is_world {
+            let text = std::str::from_utf8(contents).map_err(|_| level::Error::Import {
+                format: "world",
+                message: "not UTF-8".to_owned(),
+            })?;
+            Some(load_world(text)?)
+        } else {
+            None
+        };
+        let floor_plan = world
+            .as_ref()
+            .map_or_else(|| contents.to_vec(), |world| world.floor_plan.clone());
+        let level_file = load_level_file(&floor_plan_path, &floor_plan, auto_waypoints)?;
+        Ok(Loaded { floor_plan, world, level_file })
+    };
+    let generate = |generator| {
+        let (w, h) = arg("--size").unwrap_or(GENERATED_SIZE).split_once('x').unwrap();
+        let size = Vec2 {
+            x: w.parse().unwrap(),
+            y: h.parse().unwrap(),
+        };
+        mapgen::generate(generator, size, seed)
+    };
+    let Loaded { floor_plan, world, level_file } = reloaded.unwrap_or_else(|| {
+        let contents = generator.map_or_else(|| assets::read(&floor_plan_path), generate);
+        or_exit(&floor_plan_path, load(&contents))
+    });
+    // NOTE: A plan read from disk is polled every `RELOAD_INTERVAL`. Once it changes and the new
+    // contents load, `run` returns them so the level is rebuilt from scratch on the same window;
+    // contents that don't load (say, a save caught halfway) are logged and skipped until the next
+    // change.
+    let mut watched = generator.is_none().then(|| {
+        (
+            assets::read(&floor_plan_path),
+            fs::metadata(&floor_plan_path).and_then(|metadata| metadata.modified()).ok(),
+        )
+    });
+    let mut polled = time::Instant::now();
+    let mut outcome = Outcome::Closed;
+    let (maps, exits) = world.map(|world| (world.maps, world.exits)).unwrap_or_default();
+    let mut level = level_file.level;
+    if let Some(camera_start) = level_file.camera {
+        camera = camera_start;
+    }
+    if let Some(label) = arg("--avoid") {
+        options.avoid = level.tag(label).unwrap();
+    }
+    let bounds = level.bounds;
+    let (rooms, n_rooms) = level::rooms(&level);
+    // NOTE: `--scatter <percent>` clutters the map with walls for profiling, seeded by `--seed` so
+    // a run can be repeated; the spawn and goal stay connected (see `level::scatter`).
+    // Like walls drawn in edit mode, they don't split rooms.
+    if let Some(percent) = arg("--scatter") {
+        let start = level_file.spawn.unwrap_or(level.waypoints[0]);
+        let goal = level.goal;
+        level::scatter(&mut level, percent.parse().unwrap(), seed, start, goal);
+    }
+
+    // NOTE: A plan can have no walls at all (an imported image with no dark pixels, say).
+    let walls = {
+        let mut walls = vec![];
+
+        if let Some(first) = level.horizontals.first() {
+            walls.push((Line(*first, *first), true));
+            for horizontal in level.horizontals.iter().copied().skip(1) {
+                let n = walls.len() - 1;
+                if (walls[n].0 .0.y != horizontal.y) || (walls[n].0 .1.x != (horizontal.x - 1)) {
+                    walls.push((Line(horizontal, horizontal), true));
+                    continue;
+                }
+                walls[n].0 .1.x = horizontal.x;
+            }
+        }
+
+        if let Some(first) = level.verticals.first() {
+            walls.push((Line(*first, *first), false));
+            for vertical in level.verticals.iter().copied().skip(1) {
+                let n = walls.len() - 1;
+                if (walls[n].0 .0.x != vertical.x) || (walls[n].0 .1.y != (vertical.y - 1)) {
+                    walls.push((Line(vertical, vertical), false));
+                    continue;
+                }
+                walls[n].0 .1.y = vertical.y;
+            }
+        }
+
+        walls
+    };
+
+    let grid = GridToWorld::new(bounds, level.scale.unwrap_or(FLOOR_SCALE));
+
+    quads.push(Geom {
+        translate: Vec2::default().into(),
+        scale: (Vec2 {
+            x: f32::from(bounds.x),
+            y: f32::from(bounds.y),
+        } * grid.scale)
+            .into(),
+        color: level_file.palette.floor.unwrap_or(FLOOR_COLOR).into(),
+        depth: Depth::default(),
+    });
+
+    // NOTE: Every floor edge against a wall gets overlapping strips anchored at the wall, so the
+    // darkening builds up towards the wall (and doubles up in inside corners) without a gradient in
+    // the shader.
+    for i in (0..level.walls.len()).filter(|i| !level.walls[*i]) {
+        let cell = level.cell(i);
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let (Some(x), Some(y)) = (cell.x.checked_add_signed(dx), cell.y.checked_add_signed(dy))
+            else {
+                continue;
+            };
+            if (bounds.x <= x) || (bounds.y <= y) || !level.walls[level.index(Vec2 { x, y })] {
+                continue;
+            }
+            let direction = Vec2 { x: f32::from(dx), y: f32::from(dy) };
+            for k in 1..=CONTACT_SHADOW_BANDS {
+                let width = CONTACT_SHADOW_SPREAD * f32::from(k) / f32::from(CONTACT_SHADOW_BANDS);
+                let center = Vec2 {
+                    x: f32::from(cell.x),
+                    y: f32::from(cell.y),
+                } + (direction * (0.5 - (width / 2.0)).into());
+                let extent = Vec2 {
+                    x: if dx == 0 { 1.0 } else { width },
+                    y: if dy == 0 { 1.0 } else { width },
+                };
+                quads.push(Geom {
+                    translate: grid.cell_to_world(center).into(),
+                    scale: (grid.scale * extent).into(),
+                    color: CONTACT_SHADOW_COLOR.into(),
+                    depth: Depth::default(),
+                });
+            }
+        }
+    }
+
+    let wall_color = level_file.palette.wall.unwrap_or(WALL_COLOR);
+    let mut wall_bounds = Vec::with_capacity(walls.len());
+    for (wall, horizontal) in walls {
+        let wall = Line(
+            Vec2 {
+                x: f32::from(wall.0.x),
+                y: f32::from(wall.0.y),
+            },
+            Vec2 {
+                x: f32::from(wall.1.x),
+                y: f32::from(wall.1.y),
+            },
+        );
+
+        let translate: Translate<f32> = grid.cell_to_world(Translate::from(wall).0).into();
+
+        let mut scale: Scale<f32> = wall.into();
+        scale.0.x = scale.0.x.abs();
+        scale.0.y = scale.0.y.abs();
+        scale.0 += 1.0.into();
+
+        if horizontal {
+            scale.0.x *= grid.scale.x;
+        } else {
+            scale.0.y *= grid.scale.y;
+        }
+
+        let extent = Vec2 {
+            x: scale.0.x.abs(),
+            y: scale.0.y.abs(),
+        } * 0.5.into();
+        let margin: Vec2<f32> = (PLAYER_QUAD_SCALE / 2.0).into();
+        wall_bounds.push((translate.0 - extent - margin, translate.0 + extent + margin));
+
+        quads.push(Geom {
+            translate,
+            scale,
+            color: wall_color.into(),
+            depth: Depth::default(),
+        });
+    }
+
+    let first_room_tint_idx = quads.len();
+    for (i, room) in rooms.iter().enumerate() {
+        let Some(room) = room else {
+            continue;
+        };
+        let cell = level.cell(i);
+        let cell = Vec2 {
+            x: f32::from(cell.x),
+            y: f32::from(cell.y),
+        };
+        quads.push(Geom {
+            translate: grid.cell_to_world(cell).into(),
+            scale: grid.scale.into(),
+            color: Vec4 {
+                w: 0.0,
+                ..ROOM_COLORS[room % ROOM_COLORS.len()]
+            }
+            .into(),
+            depth: Depth::default(),
+        });
+    }
+    let last_room_tint_idx = quads.len();
+
+    for (i, speed) in level.speeds.iter().enumerate() {
+        let color = if level.exit_tiles.contains(&level.cell(i)) {
+            EXIT_COLOR
+        } else if level.highways.get(i) == Some(&true) {
+            HIGHWAY_COLOR
+        } else if 0 < level.grades[i] {
+            let t = f32::from(level.grades[i] - 1) / 8.0;
+            GRADE_LOW_COLOR + ((GRADE_HIGH_COLOR - GRADE_LOW_COLOR) * t.into())
+        } else if *speed == level::MUD_SPEED {
+            MUD_COLOR
+        } else if level.water(i) {
+            WATER_COLOR
+        } else if *speed == level::BOOST_SPEED {
+            BOOST_COLOR
+        } else {
+            continue;
+        };
+        let cell = level.cell(i);
+        quads.push(Geom {
+            translate: grid
+                .cell_to_world(Vec2 {
+                    x: f32::from(cell.x),
+                    y: f32::from(cell.y),
+                })
+                .into(),
+            scale: grid.scale.into(),
+            color: color.into(),
+            depth: Depth::default(),
+        });
+    }
+
+    for (cell, arrow) in &level.one_ways {
+        let center = grid.cell_to_world(Vec2 {
+            x: f32::from(cell.x),
+            y: f32::from(cell.y),
+        });
+        let d = Vec2 {
+            x: f32::from(arrow.x) * grid.scale.x,
+            y: f32::from(arrow.y) * grid.scale.y,
+        } * ONE_WAY_ARROW.into();
+        let p = Vec2 { x: -d.y, y: d.x } * 0.5.into();
+        let tip = center + d;
+        let back = tip - (d * 0.5.into());
+        for line in [
+            Line(center - d, tip),
+            Line(tip, back + p),
+            Line(tip, back - p),
+        ] {
+            lines.push(Geom {
+                translate: line.into(),
+                scale: line.into(),
+                color: ONE_WAY_COLOR.into(),
+                depth: Depth::default(),
+            });
+        }
+    }
+
+    // NOTE: Tab toggles edit mode, where clicks wall off (left) or reopen (right) the hovered
+    // waypoint. Each waypoint has a wall quad, hidden until it is walled off.
+    let first_edited_wall_idx = quads.len();
+    for waypoint in &level.waypoints {
+        quads.push(Geom {
+            translate: grid
+                .cell_to_world(Vec2 {
+                    x: f32::from(waypoint.x),
+                    y: f32::from(waypoint.y),
+                })
+                .into(),
+            scale: Vec2::default().into(),
+            color: wall_color.into(),
+            depth: Depth::default(),
+        });
+    }
+    let mut edit_mode = false;
+
+    let first_door_idx = quads.len();
+    for door in &level.doors {
+        quads.push(Geom {
+            translate: grid
+                .cell_to_world(Vec2 {
+                    x: f32::from(door.x),
+                    y: f32::from(door.y),
+                })
+                .into(),
+            scale: grid.scale.into(),
+            color: DOOR_OPEN_COLOR.into(),
+            depth: Depth::default(),
+        });
+    }
+    let mut doors_open = vec![true; level.doors.len()];
+
+    let mut room_tints = false;
+
+    let first_ray_marker_idx = quads.len();
+    for _ in 0..3 {
+        quads.push(Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: RAY_COLOR.into(),
+            depth: Depth::default(),
+        });
+    }
+    let ray_line_idx = lines.len();
+    lines.push(Geom {
+        translate: Vec2::default().into(),
+        scale: Vec2::default().into(),
+        color: RAY_COLOR.into(),
+        depth: Depth::default(),
+    });
+    let mut ray = None;
+
+    // NOTE: In measure mode (`T`) left clicks drop the ends of a ruler instead of destinations; the
+    // HUD reads out its length and the path cost between the waypoints nearest its ends.
+    let ruler_line_idx = lines.len();
+    lines.push(Geom {
+        translate: Vec2::default().into(),
+        scale: Vec2::default().into(),
+        color: RULER_COLOR.into(),
+        depth: Depth::default(),
+    });
+    let mut measure = false;
+    let mut ruler: Vec<(Vec2<f32>, usize)> = vec![];
+    let mut measurement: Option<(f32, f32, f32)> = None;
+
+    // NOTE: Floors are stacked down the grid; `N` moves the camera to the middle of the next one.
+    let mut focused_floor = 0;
+
+    let first_agent_idx = quads.len();
+    for _ in 0..n_agents {
+        quads.push(Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::<f32>::from(AGENT_QUAD_SCALE).into(),
+            color: AGENT_COLOR.into(),
+            depth: Depth::default(),
+        });
+    }
+
+    let first_heat_idx = quads.len();
+    for waypoint in &level.waypoints {
+        quads.push(Geom {
+            translate: grid
+                .cell_to_world(Vec2 {
+                    x: f32::from(waypoint.x),
+                    y: f32::from(waypoint.y),
+                })
+                .into(),
+            scale: grid.scale.into(),
+            color: Vec4 { w: 0.0, ..HEAT_COLOR }.into(),
+            depth: Depth::default(),
+        });
+    }
+    let mut quad_tweens: tween::Tweens<usize> = tween::Tweens::default();
+
+    let first_trail_idx = quads.len();
+    for _ in 0..TRAIL_DOTS {
+        quads.push(Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: TRAIL_COLOR.into(),
+            depth: Depth::default(),
+        });
+    }
+
+    // NOTE: Debug overlays draw into a fixed pool of quads (and of lines, further down); anything
+    // past the pool is dropped.
+    let first_overlay_point_idx = quads.len();
+    for _ in 0..OVERLAY_POINTS {
+        quads.push(Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: Vec4::from(0.0).into(),
+            depth: Depth::default(),
+        });
+    }
+    let mut overlays = overlay::Registry::default();
+    overlays.register(flow_field::FlowField::default());
+    overlays.register(explored::Explored);
+    overlays.register(distance_field::DistanceField);
+    overlays.register(visibility::Overlay::default());
+    let mut draw_list = overlay::DrawList::default();
+
+    let first_waypoint_idx = quads.len();
+
+    // NOTE: Compiled levels carry their own graph in place of a sidecar.
+    let (mut graph, graph_loaded) =
+        if floor_plan_path.extension().is_some_and(|extension| extension == "bin") {
+            compiled::graph(&floor_plan, &level, grid).map_or_else(
+                || (level::graph(&level, grid, &level::Standard), false),
+                |graph| (graph, true),
+            )
+        } else {
+            level::load_graph(&floor_plan_path, &floor_plan, &level, grid, &level::Standard)
+        };
+    if !graph_loaded {
+        log.push(
+            0,
+            log::Severity::Warn,
+            format!("graph sidecar for {} missing or stale; rebuilt", floor_plan_path.display()),
+        );
+    }
+
+    // NOTE: Door and wall edits are autosaved every `AUTOSAVE_INTERVAL`; after an unclean exit `Y`
+    // closes the doors and places the walls that were there at the last autosave.
+    let recovery_path = recovery::path(&floor_plan_path);
+    let mut recovered = recovery::load(&recovery_path)
+        .map(|recovery| recovery::Recovery {
+            closed_doors: recovery
+                .closed_doors
+                .into_iter()
+                .filter(|door| level.doors.contains(door))
+                .collect(),
+            walls: recovery
+                .walls
+                .into_iter()
+                .filter(|cell| level.waypoint_indices.contains_key(cell))
+                .collect(),
+        })
+        .filter(|recovery| !(recovery.closed_doors.is_empty() && recovery.walls.is_empty()));
+    if let Some(recovery) = &recovered {
+        log.push(
+            0,
+            log::Severity::Warn,
+            format!(
+                "last session did not exit cleanly; press Y to close its {} door(s) and place its \
+                 {} wall(s) again",
+                recovery.closed_doors.len(),
+                recovery.walls.len(),
+            ),
+        );
+    }
+    let mut edited = false;
+    let mut autosaved = time::Instant::now();
+    for node in &graph.nodes {
+        quads.push(Geom {
+            translate: (*node).into(),
+            scale: Vec2::<f32>::from(WAYPOINT_SCALE).into(),
+            color: WAYPOINT_COLOR.into(),
+            depth: Depth::default(),
+        });
+    }
+
+    let waypoint_rooms: Vec<usize> = level
+        .waypoints
+        .iter()
+        .map(|waypoint| rooms[level.index(*waypoint)].unwrap())
+        .collect();
+
+    let spawn = level_file.spawn.map_or(0, |cell| level.waypoint_indices[&cell]);
+    let mut player = agent::Agent::new(
+        quads[first_waypoint_idx + spawn].translate.0,
+        spawn,
+        PLAYER_ACCEL,
+        PLAYER_DRAG,
+        grid.scale.x.abs() * PLAYER_REACH,
+    );
+    let mut player_room = waypoint_rooms[spawn];
+    // NOTE: Exits only warp when stepped onto, so arriving on the linked entry doesn't warp back.
+    let mut warp_node = spawn;
+    // NOTE: Spawning on an exit only counts once the player has stepped off and back on.
+    let mut on_exit = level.exit_tiles.contains(&level.waypoints[spawn]);
+    // NOTE: A plan's `X` becomes the first destination, as if clicked on the first frame.
+    let mut goal = level.goal;
+    let mut rooms_entered = 0;
+    quads[player_quad_idx].translate = player.position.into();
+
+    let mut rebuild = pathfinding::Rebuild::new(graph.nodes.len());
+    let mut cost_model = level::Clearance(0.0);
+    let mut path_cache = pathfinding::PathCache::default();
+    let mut path_service = worker::Service::new();
+    let mut all_pairs = (graph.nodes.len()
+        <= arg("--all-pairs-max").map_or(ALL_PAIRS_MAX_NODES, |n| n.parse().unwrap()))
+    .then(|| pathfinding::AllPairs::new(&graph, options.avoid));
+    if all_pairs.is_none() {
+        log.push(
+            0,
+            log::Severity::Info,
+            format!("all-pairs table off for {} nodes; searching instead", graph.nodes.len()),
+        );
+    }
+
+    let mut agents: Vec<CrowdAgent> = (0..n_agents)
+        .map(|k| {
+            let node = ((k + 1) * graph.nodes.len()) / (n_agents + 1);
+            let vehicle = k % 2 == 1;
+            quads[first_agent_idx + k].translate = graph.nodes[node].into();
+            if vehicle {
+                quads[first_agent_idx + k].color = VEHICLE_COLOR.into();
+            }
+            CrowdAgent {
+                quad_idx: first_agent_idx + k,
+                node,
+                goal: Some((node + (graph.nodes.len() / 2)) % graph.nodes.len()),
+                plan: VecDeque::new(),
+                turn_penalty: vehicle.then_some(VEHICLE_TURN_PENALTY * grid.scale.x.abs()),
+                velocity: Vec2::default(),
+                replanned: 0,
+            }
+        })
+        .collect();
+    let mut reservations = pathfinding::Reservations::default();
+    let mut agent_frames = 0;
+    // NOTE: Clicking an agent inspects it in the HUD; while one is inspected, `X` clears its goal
+    // and `J` teleports it to the waypoint under the cursor. Clicking it again lets it go.
+    let mut inspected: Option<usize> = None;
+    let mut path_response: Option<worker::Response> = None;
+
+    // NOTE: Leaves room for a flow field arrow per node on top of `OVERLAY_LINES`.
+    let first_overlay_line_idx = lines.len();
+    for _ in 0..(graph.nodes.len() + OVERLAY_LINES) {
+        lines.push(Geom {
+            translate: Vec2::default().into(),
+            scale: Vec2::default().into(),
+            color: Vec4::from(0.0).into(),
+            depth: Depth::default(),
+        });
+    }
+
+    let mut destination_marker = Geom {
+        translate: Vec2::default().into(),
+        scale: Vec2::<f32>::from(DESTINATION_MARKER_SCALE).into(),
+        color: DESTINATION_MARKER_COLOR.into(),
+        depth: Depth::default(),
+    };
+    let mut marker_pool = pool::Pool::with_capacity(DESTINATION_MARKER_CAPACITY);
+    let mut marker_tweens: tween::Tweens<pool::Handle> = tween::Tweens::default();
+    let mut points = 1.0;
+
+    unsafe {
+        ffi::glfwSetWindowTitle(
+            window,
+            CString::new(format!(
+                "{} - {}",
+                std::module_path!(),
+                floor_plan_path.file_stem().unwrap().to_string_lossy(),
+            ))
+            .unwrap()
+            .as_bytes_with_nul()
+            .as_ptr()
+            .cast::<c_char>(),
+        );
+        let background = level_file.palette.background.unwrap_or(BACKGROUND_COLOR);
+        ffi::glClearColor(background.x, background.y, background.z, background.w);
+    }
+
+    let mut gl_state = GlState::default();
+    gl_state.use_program(program);
+    gl_state.blend(true);
+
+    unsafe {
+        uniform!(program, projection);
+    }
+
+    buffers_and_attributes(
+        &mut gl_state,
+        program,
+        vao[0],
+        vbo[0],
+        instance_vbo[0],
+        &quads,
+        &QUAD_VERTICES,
+    );
+    buffers_and_attributes(
+        &mut gl_state,
+        program,
+        vao[1],
+        vbo[1],
+        instance_vbo[1],
+        &lines,
+        &LINE_VERTICES,
+    );
+    // NOTE: The instance buffer is allocated at full capacity once; each frame only the live prefix
+    // of the pool is uploaded.
+    buffers_and_attributes(
+        &mut gl_state,
+        program,
+        vao[2],
+        vbo[2],
+        instance_vbo[2],
+        &vec![destination_marker; DESTINATION_MARKER_CAPACITY],
+        &QUAD_VERTICES,
+    );
+
+    let mut now = time::Instant::now();
+    let mut frames = 0;
+    let mut frame: u64 = 0;
+    let mut path_expanded = 0;
+    let mut path_stale = 0;
+    let mut path_relaxed = 0;
+    let mut path_rooms = 0;
+    let mut path_cost = 0.0;
+    let mut path_length = 0.0;
+    let mut path_deviation = 0.0;
+    let mut preview_search: Option<pathfinding::IncrementalSearch> = None;
+    let mut preview = pathfinding::PathResult::default();
+    // NOTE: Component labels, refreshed whenever the graph version moves.
+    let mut components = (graph.version, pathfinding::components(&graph));
+    // NOTE: `--visibility-graph` routes the player over wall corners instead (see
+    // `visibility::Visibility`), falling back to the waypoint graph where that finds no path.
+    let mut visibility_graph = args
+        .iter()
+        .any(|arg| arg == "--visibility-graph")
+        .then(|| visibility::Visibility::build(&level, &graph));
+    let mut preview_expanded = 0;
+    let mut preview_cost = 0.0;
+    let mut destinations = VecDeque::from([first_waypoint_idx + player.node]);
+    let mut destination_markers: VecDeque<Option<pool::Handle>> = VecDeque::from([None]);
+    let mut memory_stats = false;
+    let mut instance_overlay = false;
+    let mut instances = InstanceStats::default();
+
+    let mut governor = governor::Governor::new(FRAME_BUDGET);
+    let power_mode = arg("--low-power").unwrap_or("off");
+    assert!(["on", "off", "auto"].contains(&power_mode), "{power_mode}");
+    let mut on_battery =
+        (power_mode == "auto") && power::on_battery(Path::new(power::POWER_SUPPLY));
+    let mut power_polled = time::Instant::now();
+    let mut low_power = (power_mode == "on") || on_battery;
+    let mut log_filter = None;
+
+    // NOTE: `K` shows per-algorithm query statistics; `--stats <stem>` writes them to `<stem>.csv`
+    // and `<stem>.json` on exit.
+    let mut stats = stats::Stats::default();
+    let mut asked = Asked::default();
+    let mut stats_screen = false;
+
+    let mut hud_lines = 0;
+    let mut trip: Option<Trip> = None;
+    let mut frame_start = time::Instant::now();
+    while unsafe { ffi::glfwWindowShouldClose(window) } != 1 {
+        if governor.low_power {
+            let idle = agents.is_empty()
+                && (quad_tweens.len() + marker_tweens.len() == 0)
+                && rebuild.is_empty()
+                && (destinations.back() == Some(&(first_waypoint_idx + player.node)))
+                && (player.velocity.dot(player.velocity).sqrt() < IDLE_SPEED)
+                && (camera_speed.dot(camera_speed).sqrt() < IDLE_SPEED);
+            if idle {
+                unsafe {
+                    ffi::glfwWaitEventsTimeout(LOW_POWER_IDLE_WAIT.as_secs_f64());
+                }
+            } else {
+                thread::sleep(LOW_POWER_FRAME.saturating_sub(frame_start.elapsed()));
+            }
+        }
+        frame_start = time::Instant::now();
+        let elapsed = now.elapsed();
+        if 0 < elapsed.as_secs() {
+            let cursor_cell = grid.world_to_cell(Vec2 {
+                x: world_cursor.x,
+                y: world_cursor.y,
+            });
+            let mut hud = format!(
+                "{:12.2} elapsed ns\n\
+                 {frames:12} frames\n\
+                 {:12} ns / frame\n\
                  {:12.2} world_cursor.x\n\
                  {:12.2} world_cursor.y\n\
                  {:12.2} world_cursor.z\n\
-                 {:12} path_counter",
+                 {:12.2} cursor_cell.x\n\
+                 {:12.2} cursor_cell.y\n\
+                 {:>12} heuristic\n\
+                 {:>12} tie_break\n\
+                 {:12.1} heuristic_weight\n\
+                 {:12.2} clearance_preference\n\
+                 {path_expanded:12} path_expanded\n\
+                 {path_relaxed:12} path_relaxed\n\
+                 {path_stale:12} path_stale\n\
+                 {player_room:12} player_room\n\
+                 {:12} player_floor\n\
+                 {rooms_entered:12} rooms_entered\n\
+                 {path_rooms:12} path_rooms\n\
+                 {path_cost:12.2} path_cost\n\
+                 {path_length:12.2} path_length\n\
+                 {path_deviation:12.2} path_deviation\n\
+                 {:12} destinations\n\
+                 {:12} marker_pool.len\n\
+                 {:12} marker_pool.capacity\n\
+                 {:12} tweens\n\
+                 {:12} rebuild_pending\n\
+                 {:12} path_cache.hits\n\
+                 {:12} path_cache.misses\n\
+                 {:12} path_cache.len\n\
+                 {preview_expanded:12} preview_expanded\n\
+                 {preview_cost:12.2} preview_cost\n\
+                 {:12.2} trip.traveled\n\
+                 {:12.2} trip.planned\n\
+                 {:12.2} trip.straight\n\
+                 {:12.4} trip.optimality_gap\n\
+                 {n_rooms:12} rooms\n\
+                 {graph_loaded:>12} graph_loaded\n\
+                 {:12.2} governor.average_ms\n\
+                 {:12} governor.shed\n\
+                 {:12.2} input.latency_ms\n\
+                 {:12} gl_state.changes / frame\n\
+                 {:12} gl_state.skipped / frame\n",
                 elapsed.as_nanos(),
                 elapsed.as_nanos() / frames,
                 world_cursor.x,
                 world_cursor.y,
                 world_cursor.z,
-                path_counter,
+                cursor_cell.x,
+                cursor_cell.y,
+                options.heuristic.name(),
+                options.tie_break.name(),
+                options.weight(),
+                cost_model.0,
+                level.floor(level.waypoints[player.node]),
+                destinations.len(),
+                marker_pool.len(),
+                marker_pool.capacity(),
+                quad_tweens.len() + marker_tweens.len(),
+                rebuild.len(),
+                path_cache.hits,
+                path_cache.misses,
+                path_cache.len(),
+                trip.map_or(0.0, |trip| trip.traveled),
+                trip.map_or(0.0, |trip| trip.planned),
+                trip.map_or(0.0, |trip| trip.straight),
+                trip.map_or(0.0, |trip| (trip.traveled / trip.planned) - 1.0),
+                governor.average * 1000.0,
+                governor.shed,
+                input.latency * 1000.0,
+                gl_state.changes / u64::try_from(frames).unwrap(),
+                gl_state.skipped / u64::try_from(frames).unwrap(),
             );
+            gl_state.changes = 0;
+            gl_state.skipped = 0;
+            if instance_overlay {
+                write!(
+                    hud,
+                    "{:12} instances.submitted\n\
+                     {:12} instances.culled\n\
+                     {:12} instances.visible\n",
+                    instances.submitted, instances.culled, instances.visible,
+                )
+                .unwrap();
+            }
+            // NOTE: The remaining cost is the shortest path's from the agent's node to its goal,
+            // whatever the agent's own planner makes of it.
+            if let Some(k) = inspected {
+                let agent = &agents[k];
+                let position = quads[agent.quad_idx].translate.0;
+                write!(
+                    hud,
+                    "{k:12} agent\n\
+                     {:12.2} agent.position.x\n\
+                     {:12.2} agent.position.y\n\
+                     {:12.2} agent.velocity.x\n\
+                     {:12.2} agent.velocity.y\n\
+                     {:12} agent.node\n\
+                     {:12} agent.goal\n\
+                     {:12.2} agent.remaining_cost\n\
+                     {:12} agent.frames_since_replan\n\
+                     {:>12} clear goal\n\
+                     {:>12} teleport to cursor\n",
+                    position.x,
+                    position.y,
+                    agent.velocity.x,
+                    agent.velocity.y,
+                    agent.node,
+                    agent.goal.map_or_else(|| "none".to_owned(), |goal| goal.to_string()),
+                    agent.goal.map_or(0.0, |goal| {
+                        path_cache
+                            .shortest_path(
+                                &graph,
+                                agent.node,
+                                goal,
+                                pathfinding::Options::default(),
+                            )
+                            .cost
+                    }),
+                    frame - agent.replanned,
+                    "X",
+                    "J",
+                )
+                .unwrap();
+            }
+            if let Some((world, cells, path_cost)) = measurement {
+                write!(
+                    hud,
+                    "{world:12.2} measure.world\n\
+                     {cells:12.2} measure.cells\n\
+                     {path_cost:12.2} measure.path_cost\n",
+                )
+                .unwrap();
+            }
+            if let Some(min) = log_filter {
+                log.panel(min, LOG_PANEL_LINES, &mut hud);
+            }
+            if stats_screen {
+                stats.screen(&mut hud);
+            }
+            if memory_stats {
+                write!(
+                    hud,
+                    "{:12} graph bytes\n\
+                     {:12} quads bytes\n\
+                     {:12} lines bytes\n\
+                     {:12} path_cache bytes\n\
+                     {:12} all_pairs bytes\n\
+                     {:12} rebuild bytes\n",
+                    graph.memory(),
+                    mem::size_of_val(quads.as_slice()),
+                    mem::size_of_val(lines.as_slice()),
+                    path_cache.memory(),
+                    all_pairs.as_ref().map_or(0, pathfinding::AllPairs::memory),
+                    rebuild.memory(),
+                )
+                .unwrap();
+            }
+            if 0 < hud_lines {
+                print!("\x1B[{hud_lines}A");
+            }
+            print!("\x1B[J{hud}");
+            hud_lines = hud.lines().count();
             now = time::Instant::now();
             frames = 0;
         }
 
         unsafe {
             ffi::glfwPollEvents();
+            input.update(ffi::glfwGetTime());
+        }
+        // NOTE: A minimized window reports a size of zero; the old projection stands until it is
+        // restored.
+        if let Some(size) = input.resized.take().filter(|size| (0 < size.x) && (0 < size.y)) {
+            framebuffer = size;
+            (projection, spectator_projection) = projections(framebuffer);
+            unsafe {
+                ffi::glViewport(0, 0, framebuffer.x, framebuffer.y);
+            }
+        }
+
+        if let Some((contents, modified)) =
+            watched.as_mut().filter(|_| RELOAD_INTERVAL <= polled.elapsed())
+        {
+            polled = time::Instant::now();
+            let latest =
+                fs::metadata(&floor_plan_path).and_then(|metadata| metadata.modified()).ok();
+            if latest != *modified {
+                *modified = latest;
+                let latest = fs::read(&floor_plan_path).unwrap_or_default();
+                if !latest.is_empty() && (latest != *contents) {
+                    match load(&latest) {
+                        Ok(loaded) => {
+                            log.push(
+                                frame,
+                                log::Severity::Info,
+                                format!("{} changed; reloading", floor_plan_path.display()),
+                            );
+                            outcome = Outcome::Changed(Box::new(loaded));
+                            break;
+                        }
+                        Err(error) => log.push(
+                            frame,
+                            log::Severity::Error,
+                            format!(
+                                "{} changed but doesn't load ({error}); keeping the old level",
+                                floor_plan_path.display()
+                            ),
+                        ),
+                    }
+                    *contents = latest;
+                }
+            }
         }
 
         {
             let mut step: Vec2<f32> = Vec2::default();
 
-            if pressed(window, ffi::GLFW_KEY_W) {
+            if input.held(ffi::GLFW_KEY_W) {
                 step.y += 1.0;
             }
-            if pressed(window, ffi::GLFW_KEY_S) {
+            if input.held(ffi::GLFW_KEY_S) {
                 step.y -= 1.0;
             }
-            if pressed(window, ffi::GLFW_KEY_A) {
+            if input.held(ffi::GLFW_KEY_A) {
                 step.x -= 1.0;
             }
-            if pressed(window, ffi::GLFW_KEY_D) {
+            if input.held(ffi::GLFW_KEY_D) {
                 step.x += 1.0;
             }
 
-            camera_speed += step.normalize() * CAMERA_ACCEL.into();
-            camera_speed *= CAMERA_DRAG.into();
+            if let Some(spectator) = &mut spectator {
+                spectator.fly(step.normalize() * SPECTATOR_SPEED.into());
+            } else {
+                camera_speed += step.normalize() * CAMERA_ACCEL.into();
+                camera_speed *= CAMERA_DRAG.into();
 
-            camera.x += camera_speed.x;
-            camera.y += camera_speed.y;
+                camera.x += camera_speed.x;
+                camera.y += camera_speed.y;
+            }
+        }
+
+        if input.tapped(ffi::GLFW_KEY_M) {
+            memory_stats = !memory_stats;
+        }
+        if input.tapped(ffi::GLFW_KEY_H) {
+            options.heuristic = options.heuristic.next();
+        }
+        if input.tapped(ffi::GLFW_KEY_LEFT_BRACKET) {
+            options.epsilon = options.epsilon.saturating_sub(1);
+        }
+        if input.tapped(ffi::GLFW_KEY_RIGHT_BRACKET) {
+            options.epsilon = (options.epsilon + 1).min(pathfinding::MAX_EPSILON);
+        }
+        if input.tapped(ffi::GLFW_KEY_MINUS) {
+            cost_model.0 = (cost_model.0 - CLEARANCE_STEP).max(0.0);
+            for i in 0..graph.nodes.len() {
+                rebuild.schedule(i);
+            }
+        }
+        if input.tapped(ffi::GLFW_KEY_EQUAL) {
+            cost_model.0 = (cost_model.0 + CLEARANCE_STEP).min(MAX_CLEARANCE_PREFERENCE);
+            for i in 0..graph.nodes.len() {
+                rebuild.schedule(i);
+            }
+        }
+        if input.tapped(ffi::GLFW_KEY_L) {
+            log_filter = match log_filter {
+                None => Some(log::Severity::Info),
+                Some(log::Severity::Info) => Some(log::Severity::Warn),
+                Some(log::Severity::Warn) => Some(log::Severity::Error),
+                Some(log::Severity::Error) => None,
+            };
+        }
+        if input.tapped(ffi::GLFW_KEY_K) {
+            stats_screen = !stats_screen;
+        }
+        if input.tapped(ffi::GLFW_KEY_B) {
+            low_power = !low_power;
+        }
+        if (power_mode == "auto") && (POWER_POLL_INTERVAL <= power_polled.elapsed()) {
+            power_polled = time::Instant::now();
+            if power::on_battery(Path::new(power::POWER_SUPPLY)) != on_battery {
+                on_battery = !on_battery;
+                low_power = on_battery;
+            }
+        }
+        if low_power != governor.low_power {
+            governor.low_power = low_power;
+            unsafe {
+                if governor.enabled(governor::Feature::Multisample) {
+                    ffi::glEnable(ffi::GL_MULTISAMPLE);
+                } else {
+                    ffi::glDisable(ffi::GL_MULTISAMPLE);
+                }
+            }
+            log.push(
+                frame,
+                log::Severity::Info,
+                format!("low power {}", if low_power { "on" } else { "off" }),
+            );
+        }
+        if input.tapped(ffi::GLFW_KEY_I) {
+            instance_overlay = !instance_overlay;
+        }
+
+        if input.tapped(ffi::GLFW_KEY_R) {
+            room_tints = !room_tints;
+            for quad in &mut quads[first_room_tint_idx..last_room_tint_idx] {
+                quad.color.0.w = if room_tints { ROOM_TINT_ALPHA } else { 0.0 };
+            }
         }
 
         let view_to = Vec3 {
@@ -687,52 +2085,78 @@ fn main() {
         };
         let view = math::look_at(camera, view_to, VIEW_UP);
 
+        if input.tapped(ffi::GLFW_KEY_C) {
+            spectator = if spectator.is_some() {
+                unsafe {
+                    ffi::glfwSetInputMode(window, ffi::GLFW_CURSOR, ffi::GLFW_CURSOR_NORMAL);
+                }
+                None
+            } else {
+                unsafe {
+                    ffi::glfwSetInputMode(window, ffi::GLFW_CURSOR, ffi::GLFW_CURSOR_DISABLED);
+                    if ffi::glfwRawMouseMotionSupported() == 1 {
+                        ffi::glfwSetInputMode(window, ffi::GLFW_RAW_MOUSE_MOTION, 1);
+                    }
+                    ffi::glfwGetCursorPos(window, &mut spectator_cursor.x, &mut spectator_cursor.y);
+                }
+                Some(camera::Spectator::looking_at(camera, view_to))
+            };
+        }
+        if let Some(spectator) = &mut spectator {
+            let mut cursor: Vec2<f64> = Vec2::default();
+            unsafe {
+                ffi::glfwGetCursorPos(window, &mut cursor.x, &mut cursor.y);
+            }
+            let delta = cursor - spectator_cursor;
+            spectator_cursor = cursor;
+            #[allow(clippy::cast_possible_truncation)]
+            spectator.turn(Vec2 {
+                x: delta.x as f32 * SPECTATOR_SENSITIVITY,
+                y: delta.y as f32 * SPECTATOR_SENSITIVITY,
+            });
+        }
+
         {
             let mut screen_cursor: Vec2<f64> = Vec2::default();
+            let mut window_size: Vec2<c_int> = Vec2::default();
             unsafe {
                 ffi::glfwGetCursorPos(window, &mut screen_cursor.x, &mut screen_cursor.y);
+                ffi::glfwGetWindowSize(window, &mut window_size.x, &mut window_size.y);
             }
 
-            screen_cursor.x /= f64::from(WINDOW_WIDTH);
-            screen_cursor.y /= f64::from(WINDOW_HEIGHT);
-            screen_cursor = (screen_cursor * 2.0.into()) - 1.0.into();
-
-            #[allow(clippy::cast_possible_truncation)]
-            let mut near = Vec4 {
-                x: screen_cursor.x as f32,
-                y: -screen_cursor.y as f32,
-                z: -1.0,
-                w: 1.0,
-            };
-            let mut far = Vec4 { z: 1.0, ..near };
-
-            near = near.dot(&inverse_projection);
-            near /= near.w.into();
-            far = far.dot(&inverse_projection);
-            far /= far.w.into();
-
-            let inverse_view = math::invert(&view);
-
-            let ray_origin = near.dot(&inverse_view);
-            let ray_origin = Vec3 {
-                x: ray_origin.x,
-                y: ray_origin.y,
-                z: ray_origin.z,
+            // NOTE: Clamped so a minimized window doesn't divide by zero.
+            let viewport = Vec2 {
+                x: f64::from(window_size.x.max(1)),
+                y: f64::from(window_size.y.max(1)),
             };
-
-            let ray_direction = (far - near).dot(&inverse_view);
-            let ray_direction = Vec3 {
-                x: ray_direction.x,
-                y: ray_direction.y,
-                z: ray_direction.z,
-            }
-            .normalize();
-
+            let cursor_ray = picking::ray(&projection, &view, viewport, screen_cursor);
             let plane_origin = Vec3 { x: camera.x, y: camera.y, z: 0.0 };
             let plane_normal = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
-            let t = (plane_origin - ray_origin).dot(plane_normal) / plane_normal.dot(ray_direction);
+            if let Some(hit) = picking::intersect_plane(&cursor_ray, plane_origin, plane_normal) {
+                world_cursor = hit;
+            }
 
-            world_cursor = ray_origin + (ray_direction * t.into());
+            if input.tapped(ffi::GLFW_KEY_V) {
+                ray = if ray.is_some() {
+                    None
+                } else {
+                    Some((cursor_ray.origin, cursor_ray.far, world_cursor))
+                };
+
+                let (near, far, hit) = ray.unwrap_or_default();
+                let ray_line = Line3(near, far);
+                lines[ray_line_idx].translate = Translate::from(Line::from(ray_line));
+                lines[ray_line_idx].scale = Scale::from(Line::from(ray_line));
+                lines[ray_line_idx].depth = ray_line.into();
+                for (i, marker) in [near, far, hit].into_iter().enumerate() {
+                    let quad = &mut quads[first_ray_marker_idx + i];
+                    quad.translate = Vec2 { x: marker.x, y: marker.y }.into();
+                    quad.scale =
+                        Vec2::<f32>::from(if ray.is_some() { RAY_MARKER_SCALE } else { 0.0 })
+                            .into();
+                    quad.depth = Depth(Vec2 { x: marker.z, y: 0.0 });
+                }
+            }
         };
 
         let cursor_waypoint_idx = {
@@ -755,42 +2179,541 @@ fn main() {
             first_waypoint_idx + cursor_waypoint_idx
         };
 
-        let path = pathfinding::shortest_path(
-            &nodes,
-            &weights,
-            player_waypoint_idx - first_waypoint_idx,
-            cursor_waypoint_idx - first_waypoint_idx,
-            &mut path_counter,
-        );
+        let queueing = input.held(ffi::GLFW_KEY_LEFT_SHIFT);
+        let mut commits = vec![];
+        // NOTE: The cursor is captured for mouse-look while spectating, so clicks are ignored.
+        if input.tapped(ffi::GLFW_KEY_T) {
+            measure = !measure;
+            ruler.clear();
+        }
+        if input.tapped(ffi::GLFW_KEY_F11) {
+            toggle_fullscreen(window, windowed);
+        }
+        if input.tapped(ffi::GLFW_KEY_TAB) {
+            edit_mode = !edit_mode;
+            log.push(
+                frame,
+                log::Severity::Info,
+                format!("edit mode {}", if edit_mode { "on" } else { "off" }),
+            );
+        }
+        // NOTE: `P` saves the level, edits included, as a text floor plan: over the map itself when
+        // it is one (or was generated as one), beside it otherwise.
+        if input.tapped(ffi::GLFW_KEY_P) {
+            let path = floor_plan_path.with_extension("txt");
+            match fs::write(&path, level::floor_plan(&level)) {
+                Ok(()) => log.push(frame, log::Severity::Info, format!("saved {}", path.display())),
+                Err(error) => log.push(
+                    frame,
+                    log::Severity::Error,
+                    format!("couldn't save {}: {error}", path.display()),
+                ),
+            }
+        }
+        let hovered = {
+            let cell = grid.world_to_cell(Vec2 {
+                x: world_cursor.x,
+                y: world_cursor.y,
+            });
+            let (x, y) = (cell.x.round(), cell.y.round());
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            ((0.0 <= x) && (0.0 <= y) && (x < f32::from(bounds.x)) && (y < f32::from(bounds.y)))
+                .then_some(Vec2 { x: x as u16, y: y as u16 })
+        };
+        let mut walls_edited = vec![];
+        if input.clicked(ffi::GLFW_MOUSE_BUTTON_LEFT) && spectator.is_none() {
+            if edit_mode {
+                walls_edited.extend(hovered.map(|cell| (cell, true)));
+            } else if measure {
+                if ruler.len() == 2 {
+                    ruler.clear();
+                }
+                ruler.push((
+                    Vec2 {
+                        x: world_cursor.x,
+                        y: world_cursor.y,
+                    },
+                    cursor_waypoint_idx - first_waypoint_idx,
+                ));
+            } else if let Some(k) = agents.iter().position(|agent| {
+                quads[agent.quad_idx].translate.0.distance(Vec2 {
+                    x: world_cursor.x,
+                    y: world_cursor.y,
+                }) <= AGENT_QUAD_SCALE
+            }) {
+                inspected = (inspected != Some(k)).then_some(k);
+            } else {
+                commits.push((cursor_waypoint_idx, queueing));
+            }
+        }
+        if input.tapped(ffi::GLFW_KEY_N) {
+            focused_floor = (focused_floor + 1) % level.floors.len();
+            camera.y = floor_camera_y(&level, grid, focused_floor);
+            camera_speed.y = 0.0;
+        }
+        let mut doors_toggled = vec![];
+        if input.clicked(ffi::GLFW_MOUSE_BUTTON_RIGHT) && spectator.is_none() {
+            if edit_mode {
+                walls_edited.extend(hovered.map(|cell| (cell, false)));
+            } else {
+                doors_toggled.push(level.waypoints[cursor_waypoint_idx - first_waypoint_idx]);
+            }
+        }
+        // NOTE: `O` works the nearest door within `DOOR_REACH` of the player.
+        if input.tapped(ffi::GLFW_KEY_O) {
+            let nearest = level
+                .doors
+                .iter()
+                .enumerate()
+                .map(|(k, door)| {
+                    (quads[first_door_idx + k].translate.0.distance(player.position), *door)
+                })
+                .filter(|(d, _)| *d <= DOOR_REACH * grid.scale.x.abs())
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            if let Some((_, door)) = nearest {
+                doors_toggled.push(door);
+            }
+        }
+        if input.tapped(ffi::GLFW_KEY_Y) {
+            if let Some(recovery) = recovered.take() {
+                for door in recovery.closed_doors {
+                    if doors_open[level.doors.iter().position(|cell| *cell == door).unwrap()] {
+                        doors_toggled.push(door);
+                    }
+                }
+                walls_edited.extend(recovery.walls.into_iter().map(|cell| (cell, true)));
+            }
+        }
+        let mut screenshot = None;
+        if let Some(cell) = goal.take() {
+            commits.push((first_waypoint_idx + level.waypoint_indices[&cell], false));
+        }
+        for (_, command) in scenario.as_mut().map_or(&[][..], |scenario| scenario.due(frame)) {
+            match command {
+                scenario::Command::Destination(cell) => {
+                    commits.push((first_waypoint_idx + level.waypoint_indices[cell], false));
+                }
+                scenario::Command::Queue(cell) => {
+                    commits.push((first_waypoint_idx + level.waypoint_indices[cell], true));
+                }
+                scenario::Command::Heuristic(heuristic) => options.heuristic = *heuristic,
+                scenario::Command::TieBreak(tie_break) => options.tie_break = *tie_break,
+                scenario::Command::Door(cell) => doors_toggled.push(*cell),
+                scenario::Command::Screenshot(path) => screenshot = Some(path.clone()),
+                scenario::Command::Quit => unsafe {
+                    ffi::glfwSetWindowShouldClose(window, 1);
+                },
+                scenario::Command::Map(_) | scenario::Command::Agents(_) => (),
+            }
+        }
+
+        for (destination_idx, queueing) in commits {
+            let (i, j) = (player.node, destination_idx - first_waypoint_idx);
+            if components.1[i] != components.1[j] {
+                log.push(
+                    frame,
+                    log::Severity::Warn,
+                    format!(
+                        "destination {} {} unreachable",
+                        level.waypoints[j].x, level.waypoints[j].y
+                    ),
+                );
+            }
+            if !queueing {
+                destinations.clear();
+                for marker in mem::take(&mut destination_markers).into_iter().flatten() {
+                    fade_out(&mut marker_tweens, marker, marker_pool.get_mut(marker));
+                }
+            }
+            if destinations.back() != Some(&destination_idx) {
+                destinations.push_back(destination_idx);
+                let marker = marker_pool.spawn(Geom {
+                    translate: quads[destination_idx].translate,
+                    color: if destinations.len() == 1 {
+                        DESTINATION_MARKER_ACTIVE_COLOR.into()
+                    } else {
+                        DESTINATION_MARKER_COLOR.into()
+                    },
+                    ..destination_marker
+                });
+                if let Some(marker) = marker {
+                    marker_tweens.start(
+                        marker,
+                        tween::Channel::Scale,
+                        0.0,
+                        destination_marker.scale.0.x,
+                        MARKER_POP_FRAMES,
+                        tween::Easing::Pop,
+                    );
+                }
+                destination_markers.push_back(marker);
+            }
+            trip = None;
+            // NOTE: Re-arm arrival so a destination queued while resting still advances the queue.
+            player.state = agent::State::Idle;
+        }
+        for cell in doors_toggled {
+            if let Some(k) = level.doors.iter().position(|door| *door == cell) {
+                doors_open[k] = !doors_open[k];
+                edited = true;
+                log.push(
+                    frame,
+                    log::Severity::Info,
+                    format!(
+                        "door at {} {} {}",
+                        cell.x,
+                        cell.y,
+                        if doors_open[k] { "opened" } else { "closed" },
+                    ),
+                );
+                for i in level::set_door(&mut graph, &level, cell, doors_open[k]) {
+                    if governor.enabled(governor::Feature::Heat) {
+                        heat(&mut quad_tweens, first_heat_idx + i);
+                    }
+                }
+                quads[first_door_idx + k].color = if doors_open[k] {
+                    DOOR_OPEN_COLOR
+                } else {
+                    DOOR_CLOSED_COLOR
+                }
+                .into();
+            }
+        }
+        // NOTE: Only plain waypoints can be walled off; the player's own node, doors and stairs are
+        // left alone. Walls from the floor plan have no waypoint to reconnect, since the graph's
+        // nodes are fixed at load, so they stay put. The path cache goes stale with the graph
+        // version.
+        for (cell, wall) in walls_edited {
+            let Some(i) = level.waypoint_indices.get(&cell).copied() else {
+                if !wall && level.walls[level.index(cell)] {
+                    log.push(
+                        frame,
+                        log::Severity::Warn,
+                        format!("wall at {} {} is part of the floor plan", cell.x, cell.y),
+                    );
+                }
+                continue;
+            };
+            if (level.walls[level.index(cell)] == wall)
+                || (wall && (i == player.node))
+                || level.doors.contains(&cell)
+                || level.stairs.contains_key(&cell)
+            {
+                continue;
+            }
+            edited = true;
+            for j in level::set_wall(&mut graph, &mut level, &cost_model, cell, wall) {
+                if governor.enabled(governor::Feature::Heat) {
+                    heat(&mut quad_tweens, first_heat_idx + j);
+                }
+            }
+            // NOTE: Clearance reaches past the neighbors `set_wall` reconnects.
+            if 0.0 < cost_model.0 {
+                for j in 0..graph.nodes.len() {
+                    rebuild.schedule(j);
+                }
+            }
+            let cell_bounds = cell_bounds(grid, cell);
+            if wall {
+                quads[first_edited_wall_idx + i].scale = grid.scale.into();
+                wall_bounds.push(cell_bounds);
+            } else {
+                quads[first_edited_wall_idx + i].scale = Vec2::default().into();
+                wall_bounds.retain(|other| *other != cell_bounds);
+            }
+            log.push(
+                frame,
+                log::Severity::Info,
+                format!(
+                    "wall at {} {} {}",
+                    cell.x,
+                    cell.y,
+                    if wall { "placed" } else { "removed" }
+                ),
+            );
+        }
+        // NOTE: A plan with no directory on disk (the embedded default, run from elsewhere) has
+        // nowhere to autosave to.
+        if edited
+            && (AUTOSAVE_INTERVAL <= autosaved.elapsed())
+            && floor_plan_path.parent().is_some_and(Path::is_dir)
         {
-            let distance = |i: usize| {
-                let d = quads[i].translate.0.distance(quads[player_quad_idx].translate.0);
-                assert!(d.is_sign_positive());
-                d
+            let recovery = recovery::Recovery {
+                closed_doors: level
+                    .doors
+                    .iter()
+                    .zip(&doors_open)
+                    .filter(|(_, open)| !**open)
+                    .map(|(door, _)| *door)
+                    .collect(),
+                walls: level
+                    .waypoints
+                    .iter()
+                    .filter(|cell| level.walls[level.index(**cell)])
+                    .copied()
+                    .collect(),
+            };
+            recovery::save(&recovery_path, &recovery);
+            if recovered.take().is_some() {
+                log.push(frame, log::Severity::Info, "recovered edits discarded".to_owned());
+            }
+            edited = false;
+            autosaved = time::Instant::now();
+        }
+        if input.tapped(ffi::GLFW_KEY_BACKSPACE) && (1 < destinations.len()) {
+            destinations.pop_back();
+            if let Some(marker) = destination_markers.pop_back().flatten() {
+                fade_out(&mut marker_tweens, marker, marker_pool.get_mut(marker));
+            }
+        }
+
+        // NOTE: Selecting landmarks runs a Dijkstra pass per landmark, too slow to repeat on every
+        // graph version unless they are in use.
+        if options.heuristic == pathfinding::Heuristic::Landmarks {
+            graph.update_landmarks(pathfinding::LANDMARKS);
+        }
+        if components.0 != graph.version {
+            components = (graph.version, pathfinding::components(&graph));
+        }
+        if let Some(visibility) = &mut visibility_graph {
+            if visibility.version != graph.version {
+                *visibility = visibility::Visibility::build(&level, &graph);
+            }
+        }
+        let result = {
+            let start = player.node;
+            let end = destinations[0] - first_waypoint_idx;
+            let any_angle = visibility_graph
+                .as_mut()
+                .map(|visibility| visibility.shortest_path(&level, &graph, start, end))
+                .filter(|result| !result.nodes.is_empty());
+            let table = all_pairs
+                .as_mut()
+                .and_then(|table| table.shortest_path(&graph, start, end, options));
+            let reachable = options.partial || (components.1[start] == components.1[end]);
+            if (start != end) && any_angle.is_none() && table.is_none() && reachable {
+                path_service.submit(&graph, start, end, options, rebuild.is_empty());
+            }
+            if let Some(response) = path_service.latest() {
+                stats.record(
+                    response.options.heuristic.name(),
+                    false,
+                    response.result.expanded,
+                    response.result.cost,
+                );
+                path_response = Some(response);
+            }
+            // NOTE: Until the worker answers the current query the player holds position. An answer
+            // found on an older graph is still followed until a newer one arrives, so the player
+            // keeps moving while a big map rebuilds.
+            match (any_angle.or(table), &path_response) {
+                (Some(result), _) => result,
+                (None, Some(response))
+                    if (start != end)
+                        && (response.start, response.end, response.options)
+                            == (start, end, options) =>
+                {
+                    response.result.clone()
+                }
+                _ => pathfinding::PathResult {
+                    nodes: pathfinding::Path::from([start]),
+                    ..pathfinding::PathResult::default()
+                },
+            }
+        };
+        path_expanded = result.expanded;
+        path_relaxed = result.relaxed;
+        path_stale = result.stale;
+        path_cost = result.cost;
+        let path = pathfinding::smooth(&result.nodes, |i, j| {
+            line_of_sight(&wall_bounds, graph.nodes[i], graph.nodes[j])
+        });
+
+        asked.swap(0, 1);
+        asked[0].clear();
+        let route = {
+            let mut route = VecDeque::new();
+            for (i, j) in destinations.iter().zip(destinations.iter().skip(1)) {
+                let (i, j) = (i - first_waypoint_idx, j - first_waypoint_idx);
+                let leg = all_pairs
+                    .as_mut()
+                    .and_then(|table| table.shortest_path(&graph, i, j, options))
+                    .unwrap_or_else(|| {
+                        cached_path(&mut path_cache, &mut stats, &mut asked, &graph, i, j, options)
+                    });
+                path_cost += leg.cost;
+                route.extend(leg.nodes.into_iter().skip(1));
+            }
+            route
+        };
+
+        let cursor_reachable = {
+            let origin_waypoint_idx = if queueing {
+                *destinations.back().unwrap()
+            } else {
+                first_waypoint_idx + player.node
             };
+            let key = (
+                origin_waypoint_idx - first_waypoint_idx,
+                cursor_waypoint_idx - first_waypoint_idx,
+                options,
+            );
+            preview.nodes.clear();
+            preview.cost = 0.0;
+            preview.expanded = 0;
+            // NOTE: A cursor in another component can't be reached, so there is nothing to search
+            // for unless a partial path was asked for.
+            let reachable = components.1[key.0] == components.1[key.1];
+            if (cursor_waypoint_idx != origin_waypoint_idx) && (reachable || options.partial) {
+                // NOTE: The search (and its buffers) is reused from frame to frame.
+                let search = preview_search.get_or_insert_with(|| {
+                    pathfinding::IncrementalSearch::new(&graph, key.0, key.1, key.2)
+                });
+                if (search.key() != key) || (search.version != graph.version) {
+                    search.restart(&graph, key.0, key.1, key.2);
+                }
+                match search.step(&graph, SEARCH_BUDGET) {
+                    pathfinding::SearchState::Found => search.result_into(&mut preview),
+                    pathfinding::SearchState::Exhausted if options.partial => {
+                        search.partial_into(&mut preview);
+                    }
+                    _ => (),
+                }
+            }
+            reachable
+        };
+        preview_expanded = preview.expanded;
+        preview_cost = preview.cost;
 
-            let d = distance(player_waypoint_idx);
-            let d = if (1 < path.len()) && (d <= (PLAYER_QUAD_SCALE / 2.0)) {
-                player_waypoint_idx = first_waypoint_idx + path[1];
-                distance(player_waypoint_idx)
+        {
+            player.accel = PLAYER_ACCEL * level.speed(grid.world_to_cell(player.position));
+            player.update(
+                &graph.nodes,
+                &path,
+                destinations[0] - first_waypoint_idx,
+                |a, b| line_of_sight(&wall_bounds, a, b),
+                |_| {
+                    if 1 < destinations.len() {
+                        destinations.pop_front();
+                        if let Some(marker) = destination_markers.pop_front().flatten() {
+                            fade_out(&mut marker_tweens, marker, marker_pool.get_mut(marker));
+                        }
+                        if let Some(Some(marker)) = destination_markers.front() {
+                            marker_pool.get_mut(*marker).color =
+                                DESTINATION_MARKER_ACTIVE_COLOR.into();
+                        }
+                    }
+                },
+            );
+            quads[player_quad_idx].translate = player.position.into();
+        }
+
+        // NOTE: Exits go by the cell under the player, since a smoothed path may cut past the `E`
+        // waypoint without ever making it `player.node`.
+        let exited = level.on_exit(grid.world_to_cell(player.position));
+        if exited && !on_exit {
+            if level_path.is_some() {
+                log.push(frame, log::Severity::Info, "took the exit".to_owned());
+                outcome = Outcome::Exited;
+                break;
+            }
+            log.push(
+                frame,
+                log::Severity::Warn,
+                "exit tile reached, but no --levels to go on to".to_owned(),
+            );
+        }
+        on_exit = exited;
+
+        if player.node != warp_node {
+            warp_node = player.node;
+            if let Some(entry) = exits.get(&level.waypoints[player.node]) {
+                warp_node = level.waypoint_indices[entry];
+                player.node = warp_node;
+                player.position = graph.nodes[warp_node];
+                player.velocity = Vec2::default();
+                quads[player_quad_idx].translate = player.position.into();
+                focused_floor = level.floor(*entry);
+                camera.y = floor_camera_y(&level, grid, focused_floor);
+                camera_speed.y = 0.0;
+                // NOTE: Without planning across maps, whatever was queued on the old map is out of
+                // reach, so the player stops at the entry.
+                if !plan_across {
+                    destinations.clear();
+                    destinations.push_back(first_waypoint_idx + warp_node);
+                    for marker in mem::take(&mut destination_markers).into_iter().flatten() {
+                        fade_out(&mut marker_tweens, marker, marker_pool.get_mut(marker));
+                    }
+                    destination_markers.push_back(None);
+                }
+                trip = None;
+                player.state = agent::State::Idle;
+                log.push(
+                    frame,
+                    log::Severity::Info,
+                    format!("warped to {} {} on {}", entry.x, entry.y, maps[focused_floor]),
+                );
+            }
+        }
+
+        {
+            path_length = path.length(&graph.nodes);
+            path_deviation = path
+                .nearest(&graph.nodes, player.position)
+                .map_or(0.0, |(_, point)| point.distance(player.position));
+            let mut trail = path.clone();
+            trail.truncate_length(&graph.nodes, TRAIL_RANGE);
+            let dots = if governor.enabled(governor::Feature::Trail) {
+                trail.resample(&graph.nodes, TRAIL_SPACING)
             } else {
-                d
+                vec![]
             };
+            for (k, quad) in
+                quads[first_trail_idx..(first_trail_idx + TRAIL_DOTS)].iter_mut().enumerate()
+            {
+                if let Some(dot) = dots.get(k) {
+                    quad.translate = (*dot).into();
+                    quad.scale.0 = (TRAIL_DOT_SCALE * points).into();
+                } else {
+                    quad.scale = Vec2::default().into();
+                }
+            }
+        }
+
+        {
+            let position = quads[player_quad_idx].translate.0;
+            let trip = trip.get_or_insert_with(|| Trip {
+                traveled: 0.0,
+                planned: path_cost,
+                straight: position.distance(quads[*destinations.back().unwrap()].translate.0),
+            });
+            trip.traveled += player.velocity.dot(player.velocity).sqrt();
+        }
 
-            if (PLAYER_QUAD_SCALE / 2.0) < d {
-                let step =
-                    quads[player_waypoint_idx].translate.0 - quads[player_quad_idx].translate.0;
-                player_speed += step.normalize() * PLAYER_ACCEL.into();
+        {
+            let room = waypoint_rooms[player.node];
+            if room != player_room {
+                // NOTE: This is the `on_enter_room` hook.
+                player_room = room;
+                rooms_entered += 1;
             }
-            player_speed *= PLAYER_DRAG.into();
+        }
 
-            quads[player_quad_idx].translate.0 += player_speed;
+        {
+            path_rooms = 0;
+            let mut previous = None;
+            for i in &path {
+                if previous != Some(waypoint_rooms[*i]) {
+                    previous = Some(waypoint_rooms[*i]);
+                    path_rooms += 1;
+                }
+            }
         }
 
         {
             let player_line = Line(
                 quads[player_quad_idx].translate.0,
-                quads[player_quad_idx].translate.0 + (player_speed * PLAYER_LINE_SCALE.into()),
+                quads[player_quad_idx].translate.0 + (player.velocity * PLAYER_LINE_SCALE.into()),
             );
             lines[player_line_idx].translate = player_line.into();
             lines[player_line_idx].scale = player_line.into();
@@ -806,28 +2729,275 @@ fn main() {
             lines[cursor_line_idx].scale = cursor_line.into();
         }
 
-        for i in &path {
+        {
+            // NOTE: Until its second end is placed the ruler follows the cursor.
+            let ends = ruler.first().map(|start| {
+                let end = ruler.get(1).copied().unwrap_or((
+                    Vec2 {
+                        x: world_cursor.x,
+                        y: world_cursor.y,
+                    },
+                    cursor_waypoint_idx - first_waypoint_idx,
+                ));
+                (*start, end)
+            });
+            measurement = ends.map(|((a, i), (b, j))| {
+                (
+                    a.distance(b),
+                    grid.world_to_cell(a).distance(grid.world_to_cell(b)),
+                    cached_path(&mut path_cache, &mut stats, &mut asked, &graph, i, j, options)
+                        .cost,
+                )
+            });
+            let ruler_line = ends.map_or_else(
+                || Line(Vec2::default(), Vec2::default()),
+                |((a, _), (b, _))| Line(a, b),
+            );
+            lines[ruler_line_idx].translate = ruler_line.into();
+            lines[ruler_line_idx].scale = ruler_line.into();
+        }
+
+        agent_frames += 1;
+        if AGENT_STEP_FRAMES <= agent_frames {
+            agent_frames = 0;
+            // NOTE: Agents advance in lockstep, then replan in a fixed priority order against a
+            // fresh reservation table.
+            reservations.clear();
+            for (k, agent) in agents.iter_mut().enumerate() {
+                if let Some(next) = agent.plan.get(1) {
+                    agent.node = *next;
+                }
+                agent.replanned = frame;
+                let Some(mut goal) = agent.goal else {
+                    agent.plan = VecDeque::from([agent.node]);
+                    reservations.reserve(k, &agent.plan, AGENT_WINDOW);
+                    continue;
+                };
+                if agent.node == goal {
+                    goal = ((goal * 7) + 13) % graph.nodes.len();
+                    agent.goal = Some(goal);
+                }
+                if let Some(penalty) = agent.turn_penalty {
+                    let result = pathfinding::turning_path(
+                        &graph,
+                        agent.node,
+                        goal,
+                        pathfinding::Options::default(),
+                        penalty,
+                    );
+                    stats.record("turning", false, result.expanded, result.cost);
+                    agent.plan = result.nodes.iter().copied().take(AGENT_WINDOW + 1).collect();
+                    reservations.clip(k, &mut agent.plan);
+                } else {
+                    agent.plan = pathfinding::cooperative_path(
+                        &graph,
+                        agent.node,
+                        goal,
+                        k,
+                        &reservations,
+                        AGENT_WINDOW,
+                    );
+                }
+                reservations.reserve(k, &agent.plan, AGENT_WINDOW);
+            }
+        }
+        if let Some(agent) = inspected.map(|k| &mut agents[k]) {
+            if input.tapped(ffi::GLFW_KEY_X) {
+                agent.goal = None;
+            }
+            if input.tapped(ffi::GLFW_KEY_J) {
+                agent.node = cursor_waypoint_idx - first_waypoint_idx;
+                agent.plan = VecDeque::from([agent.node]);
+                quads[agent.quad_idx].translate = graph.nodes[agent.node].into();
+            }
+        }
+        for agent in &mut agents {
+            let translate = &mut quads[agent.quad_idx].translate.0;
+            let zone = level.speed(grid.world_to_cell(*translate));
+            agent.velocity = (graph.nodes[agent.node] - *translate) * (AGENT_FOLLOW * zone).into();
+            *translate += agent.velocity;
+        }
+
+        if input.tapped(ffi::GLFW_KEY_G) {
+            for i in 0..graph.nodes.len() {
+                rebuild.schedule(i);
+            }
+        }
+        if !rebuild.is_empty() {
+            rebuild.step(REBUILD_BUDGET, |i| {
+                level::connect(&mut graph, &level, &cost_model, i);
+                if governor.enabled(governor::Feature::Heat) {
+                    heat(&mut quad_tweens, first_heat_idx + i);
+                }
+            });
+        }
+
+        quad_tweens.update(|i, channel, value| channel.apply(&mut quads[i], value));
+        marker_tweens.update(|marker, channel, value| {
+            channel.apply(marker_pool.get_mut(marker), value);
+        });
+        for (marker, channel) in marker_tweens.finished() {
+            if *channel == tween::Channel::Alpha {
+                marker_pool.despawn(*marker);
+            }
+        }
+
+        for (name, enabled) in overlays.poll(|key| input.tapped(key)) {
+            log.push(
+                frame,
+                log::Severity::Info,
+                format!("{name} overlay {}", if enabled { "on" } else { "off" }),
+            );
+        }
+        overlays.render(
+            &overlay::Frame {
+                level: &level,
+                graph: &graph,
+                governor: &governor,
+                cursor: cursor_waypoint_idx - first_waypoint_idx,
+                preview: preview_search.as_ref(),
+            },
+            &mut draw_list,
+        );
+        for (k, quad) in quads[first_overlay_point_idx..first_waypoint_idx].iter_mut().enumerate() {
+            if let Some((at, scale, color)) = draw_list.points.get(k) {
+                quad.translate = (*at).into();
+                quad.scale.0 = (*scale * points).into();
+                quad.color = (*color).into();
+            } else {
+                quad.scale = Vec2::default().into();
+            }
+        }
+        for (k, line) in lines[first_overlay_line_idx..].iter_mut().enumerate() {
+            if let Some((from, to, color)) = draw_list.lines.get(k) {
+                line.translate = Line(*from, *to).into();
+                line.scale = Line(*from, *to).into();
+                line.color = (*color).into();
+            } else {
+                line.scale.0 = Vec2::default();
+            }
+        }
+
+        {
+            let distance = spectator.as_ref().map_or(camera.z, |spectator| spectator.position.z);
+            if (point_scale(distance) - points).abs() > f32::EPSILON {
+                points = point_scale(distance);
+                for quad in &mut quads[first_waypoint_idx..] {
+                    quad.scale.0 = (WAYPOINT_SCALE * points).into();
+                }
+                destination_marker.scale.0 = (DESTINATION_MARKER_SCALE * points).into();
+                for marker in marker_pool.as_mut_slice() {
+                    marker.scale = destination_marker.scale;
+                }
+            }
+        }
+
+        for i in &preview.nodes {
+            quads[first_waypoint_idx + i].color.0 = WAYPOINT_PREVIEW_COLOR;
+            quads[first_waypoint_idx + i].scale.0 = (WAYPOINT_PREVIEW_SCALE * points).into();
+        }
+        for i in path.iter().chain(&route) {
             quads[first_waypoint_idx + i].color.0 = WAYPOINT_HIGHLIGHT_COLOR;
-            quads[first_waypoint_idx + i].scale.0 = WAYPOINT_HIGHLIGHT_SCALE.into();
+            quads[first_waypoint_idx + i].scale.0 = (WAYPOINT_HIGHLIGHT_SCALE * points).into();
+        }
+        if !cursor_reachable {
+            quads[cursor_waypoint_idx].color.0 = WAYPOINT_UNREACHABLE_COLOR;
+            quads[cursor_waypoint_idx].scale.0 = (WAYPOINT_HIGHLIGHT_SCALE * points).into();
+        }
+
+        let (view, projection) = spectator
+            .as_ref()
+            .map_or((view, projection), |spectator| (spectator.view(), spectator_projection));
+
+        if instance_overlay {
+            let clip = view.dot(&projection);
+            instances = InstanceStats::default();
+            instance_stats(&quads, &clip, &mut instances);
+            instance_stats(&lines, &clip, &mut instances);
+            instance_stats(marker_pool.as_slice(), &clip, &mut instances);
         }
 
+        gl_state.use_program(program);
+        gl_state.blend(true);
         unsafe {
             uniform!(program, view);
+            uniform!(program, projection);
             ffi::glClear(ffi::GL_COLOR_BUFFER_BIT);
         }
 
-        bind_and_draw(vao[0], instance_vbo[0], &quads, &QUAD_VERTICES, ffi::GL_TRIANGLE_STRIP);
-        bind_and_draw(vao[1], instance_vbo[1], &lines, &LINE_VERTICES, ffi::GL_LINES);
+        bind_and_draw(
+            &mut gl_state,
+            vao[0],
+            instance_vbo[0],
+            &quads,
+            &QUAD_VERTICES,
+            ffi::GL_TRIANGLE_STRIP,
+        );
+        bind_and_draw(
+            &mut gl_state,
+            vao[1],
+            instance_vbo[1],
+            &lines,
+            &LINE_VERTICES,
+            ffi::GL_LINES,
+        );
+        bind_and_draw(
+            &mut gl_state,
+            vao[2],
+            instance_vbo[2],
+            marker_pool.as_slice(),
+            &QUAD_VERTICES,
+            ffi::GL_TRIANGLE_STRIP,
+        );
+
+        let change = governor.update(frame_start.elapsed());
+        if let Some((feature, enabled)) = change {
+            log.push(
+                frame,
+                if enabled {
+                    log::Severity::Info
+                } else {
+                    log::Severity::Warn
+                },
+                format!(
+                    "{feature:?} {} at {:.2} ms / frame",
+                    if enabled { "restored" } else { "shed" },
+                    governor.average * 1000.0,
+                ),
+            );
+        }
+        if let Some((governor::Feature::Multisample, _)) = change {
+            unsafe {
+                if governor.enabled(governor::Feature::Multisample) {
+                    ffi::glEnable(ffi::GL_MULTISAMPLE);
+                } else {
+                    ffi::glDisable(ffi::GL_MULTISAMPLE);
+                }
+            }
+        }
 
         unsafe {
+            if let Some(path) = screenshot {
+                write_screenshot(&path, framebuffer);
+                log.push(frame, log::Severity::Info, format!("wrote {}", path.display()));
+            }
             ffi::glfwSwapBuffers(window);
         }
 
-        for i in path {
+        let cursor = cursor_waypoint_idx - first_waypoint_idx;
+        for i in preview.nodes.iter().copied().chain(path).chain(route).chain([cursor]) {
             quads[first_waypoint_idx + i].color.0 = WAYPOINT_COLOR;
-            quads[first_waypoint_idx + i].scale.0 = WAYPOINT_SCALE.into();
+            quads[first_waypoint_idx + i].scale.0 = (WAYPOINT_SCALE * points).into();
         }
 
         frames += 1;
+        frame += 1;
+    }
+
+    recovery::clear(&recovery_path);
+    if let Some(stem) = arg("--stats") {
+        fs::write(Path::new(stem).with_extension("csv"), stats.csv()).unwrap();
+        fs::write(Path::new(stem).with_extension("json"), stats.json()).unwrap();
     }
+    outcome
 }
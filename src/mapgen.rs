@@ -0,0 +1,220 @@
+use crate::math::Vec2;
+
+// NOTE: Generators emit an ordinary floor plan (`+` walls, `.` waypoints) that goes through
+// `level::parse` like any file would. The same generator, size and seed always give the same plan,
+// and every waypoint can reach every other.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Generator {
+    Rooms,
+    Caves,
+}
+
+impl Generator {
+    pub const ALL: [Self; 2] = [Self::Rooms, Self::Caves];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Rooms => "rooms",
+            Self::Caves => "caves",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|generator| generator.name() == name)
+    }
+}
+
+const MIN_SIZE: u16 = 8;
+const ROOM_ATTEMPTS: usize = 200;
+const ROOM_MIN: usize = 3;
+const ROOM_MAX: usize = 12;
+// NOTE: Share of cells that start out as rock before smoothing.
+const CAVE_FILL: u64 = 45;
+const CAVE_STEPS: usize = 5;
+
+// NOTE: SplitMix64.
+pub struct Rng(pub u64);
+
+impl Rng {
+    pub const fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // NOTE: Uniform enough in `[low, high)` for map layouts.
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn range(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % ((high - low) as u64)) as usize
+    }
+}
+
+struct Grid {
+    width: usize,
+    height: usize,
+    rock: Vec<bool>,
+}
+
+impl Grid {
+    fn carve(&mut self, x: usize, y: usize) {
+        self.rock[(y * self.width) + x] = false;
+    }
+
+    fn rock_around(&self, x: usize, y: usize) -> usize {
+        let mut n = 0;
+        for v in (y - 1)..=(y + 1) {
+            for u in (x - 1)..=(x + 1) {
+                if ((u, v) != (x, y)) && self.rock[(v * self.width) + u] {
+                    n += 1;
+                }
+            }
+        }
+        n
+    }
+
+    // NOTE: Fills in every open cell that can't reach the largest open region.
+    fn keep_largest_region(&mut self) {
+        let mut region = vec![usize::MAX; self.rock.len()];
+        let mut sizes = vec![];
+        for start in 0..self.rock.len() {
+            if self.rock[start] || (region[start] != usize::MAX) {
+                continue;
+            }
+            let mut stack = vec![start];
+            region[start] = sizes.len();
+            let mut size = 0;
+            while let Some(i) = stack.pop() {
+                size += 1;
+                for j in [i - 1, i + 1, i - self.width, i + self.width] {
+                    if !self.rock[j] && (region[j] == usize::MAX) {
+                        region[j] = sizes.len();
+                        stack.push(j);
+                    }
+                }
+            }
+            sizes.push(size);
+        }
+        let largest = (0..sizes.len()).max_by_key(|k| sizes[*k]);
+        for (rock, region) in self.rock.iter_mut().zip(region) {
+            *rock = *rock || (Some(region) != largest);
+        }
+    }
+
+    fn floor_plan(&self) -> Vec<u8> {
+        let mut floor_plan = Vec::with_capacity((self.width + 1) * self.height);
+        for row in self.rock.chunks(self.width) {
+            floor_plan.extend(row.iter().map(|rock| if *rock { b'+' } else { b'.' }));
+            floor_plan.push(b'\n');
+        }
+        floor_plan
+    }
+}
+
+// NOTE: Non-overlapping rectangular rooms, each joined to the one placed before it by an L-shaped
+// corridor.
+fn rooms(grid: &mut Grid, rng: &mut Rng) {
+    let mut rooms: Vec<(usize, usize, usize, usize)> = vec![];
+    for _ in 0..ROOM_ATTEMPTS {
+        let w = rng.range(ROOM_MIN, ROOM_MAX.min(grid.width - 2) + 1);
+        let h = rng.range(ROOM_MIN, ROOM_MAX.min(grid.height - 2) + 1);
+        let x = rng.range(1, grid.width - w);
+        let y = rng.range(1, grid.height - h);
+        if rooms.iter().any(|(u, v, other_w, other_h)| {
+            (x <= u + other_w) && (*u <= x + w) && (y <= v + other_h) && (*v <= y + h)
+        }) {
+            continue;
+        }
+        for v in y..(y + h) {
+            for u in x..(x + w) {
+                grid.carve(u, v);
+            }
+        }
+        if let Some((u, v, other_w, other_h)) = rooms.last().copied() {
+            let from = (x + (w / 2), y + (h / 2));
+            let to = (u + (other_w / 2), v + (other_h / 2));
+            let corner = if rng.next() & 1 == 0 {
+                (to.0, from.1)
+            } else {
+                (from.0, to.1)
+            };
+            for u in from.0.min(corner.0)..=from.0.max(corner.0) {
+                grid.carve(u, from.1);
+            }
+            for v in from.1.min(corner.1)..=from.1.max(corner.1) {
+                grid.carve(from.0, v);
+            }
+            for u in to.0.min(corner.0)..=to.0.max(corner.0) {
+                grid.carve(u, to.1);
+            }
+            for v in to.1.min(corner.1)..=to.1.max(corner.1) {
+                grid.carve(to.0, v);
+            }
+        }
+        rooms.push((x, y, w, h));
+    }
+}
+
+// NOTE: Random fill smoothed by the 4-5 rule (a cell becomes rock with at least five rock
+// neighbours, stays rock with four), then cut down to the largest open region.
+fn caves(grid: &mut Grid, rng: &mut Rng) {
+    for y in 1..(grid.height - 1) {
+        for x in 1..(grid.width - 1) {
+            if CAVE_FILL <= rng.next() % 100 {
+                grid.carve(x, y);
+            }
+        }
+    }
+    for _ in 0..CAVE_STEPS {
+        let mut rock = grid.rock.clone();
+        for y in 1..(grid.height - 1) {
+            for x in 1..(grid.width - 1) {
+                let n = grid.rock_around(x, y);
+                rock[(y * grid.width) + x] =
+                    (5 <= n) || ((n == 4) && grid.rock[(y * grid.width) + x]);
+            }
+        }
+        grid.rock = rock;
+    }
+    grid.keep_largest_region();
+}
+
+pub fn generate(generator: Generator, size: Vec2<u16>, seed: u64) -> Vec<u8> {
+    assert!((MIN_SIZE <= size.x) && (MIN_SIZE <= size.y));
+    let mut grid = Grid {
+        width: usize::from(size.x),
+        height: usize::from(size.y),
+        rock: vec![true; usize::from(size.x) * usize::from(size.y)],
+    };
+    let mut rng = Rng(seed);
+    match generator {
+        Generator::Rooms => rooms(&mut grid, &mut rng),
+        Generator::Caves => caves(&mut grid, &mut rng),
+    }
+    grid.floor_plan()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::GridToWorld;
+    use crate::level;
+    use crate::pathfinding;
+
+    #[test]
+    fn test_generate() {
+        let size = Vec2 { x: 48, y: 32 };
+        for generator in Generator::ALL {
+            let floor_plan = generate(generator, size, 7);
+            assert!(floor_plan == generate(generator, size, 7));
+            assert!(floor_plan != generate(generator, size, 8));
+
+            let level = level::parse(&floor_plan, false).unwrap();
+            assert!((level.bounds.x, level.bounds.y) == (48, 32));
+            assert!(16 < level.waypoints.len());
+            let graph = level::graph(&level, GridToWorld::new(level.bounds, 1.0), &level::Standard);
+            assert!(pathfinding::flow_field(0, &graph).iter().skip(1).all(Option::is_some));
+        }
+    }
+}
@@ -67,6 +67,17 @@ impl<T: ops::MulAssign> ops::MulAssign for Vec2<T> {
     }
 }
 
+impl<T: ops::Div<Output = T>> ops::Div for Vec2<T> {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        Self {
+            x: self.x / other.x,
+            y: self.y / other.y,
+        }
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Default)]
 pub struct Vec3<T> {
@@ -145,6 +156,19 @@ impl<T: Copy> From<T> for Vec4<T> {
     }
 }
 
+impl<T: ops::Add<Output = T>> ops::Add for Vec4<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            x: self.x + other.x,
+            y: self.y + other.y,
+            z: self.z + other.z,
+            w: self.w + other.w,
+        }
+    }
+}
+
 impl<T: ops::Sub<Output = T>> ops::Sub for Vec4<T> {
     type Output = Self;
 
@@ -158,6 +182,19 @@ impl<T: ops::Sub<Output = T>> ops::Sub for Vec4<T> {
     }
 }
 
+impl<T: ops::Mul<Output = T>> ops::Mul for Vec4<T> {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            x: self.x * other.x,
+            y: self.y * other.y,
+            z: self.z * other.z,
+            w: self.w * other.w,
+        }
+    }
+}
+
 impl<T: ops::DivAssign> ops::DivAssign for Vec4<T> {
     fn div_assign(&mut self, other: Self) {
         self.x /= other.x;
@@ -338,6 +375,33 @@ impl Cross for Vec3<f32> {
     }
 }
 
+fn orientation(a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>) -> f32 {
+    (b.x - a.x).mul_add(c.y - a.y, -((b.y - a.y) * (c.x - a.x)))
+}
+
+// NOTE: See `https://en.wikipedia.org/wiki/Line%E2%80%93line_intersection#Given_two_points_on_each_line_segment`.
+pub fn segments_intersect(p0: Vec2<f32>, p1: Vec2<f32>, q0: Vec2<f32>, q1: Vec2<f32>) -> bool {
+    let d0 = orientation(q0, q1, p0);
+    let d1 = orientation(q0, q1, p1);
+    let d2 = orientation(p0, p1, q0);
+    let d3 = orientation(p0, p1, q1);
+    if (((0.0 < d0) && (d1 < 0.0)) || ((d0 < 0.0) && (0.0 < d1)))
+        && (((0.0 < d2) && (d3 < 0.0)) || ((d2 < 0.0) && (0.0 < d3)))
+    {
+        return true;
+    }
+    let on_segment = |a: Vec2<f32>, b: Vec2<f32>, c: Vec2<f32>| {
+        (a.x.min(b.x) <= c.x)
+            && (c.x <= a.x.max(b.x))
+            && (a.y.min(b.y) <= c.y)
+            && (c.y <= a.y.max(b.y))
+    };
+    ((d0 == 0.0) && on_segment(q0, q1, p0))
+        || ((d1 == 0.0) && on_segment(q0, q1, p1))
+        || ((d2 == 0.0) && on_segment(p0, p1, q0))
+        || ((d3 == 0.0) && on_segment(p0, p1, q1))
+}
+
 pub trait Normalize {
     fn normalize(self) -> Self;
 }
@@ -387,6 +451,16 @@ mod tests {
         assert!(compare(&projection.dot(&invert(&projection)), &IDENTITY, f32::EPSILON));
     }
 
+    #[test]
+    fn test_segments_intersect() {
+        let p0 = Vec2 { x: 0.0, y: 0.0 };
+        let p1 = Vec2 { x: 2.0, y: 2.0 };
+        assert!(segments_intersect(p0, p1, Vec2 { x: 0.0, y: 2.0 }, Vec2 { x: 2.0, y: 0.0 }));
+        assert!(segments_intersect(p0, p1, Vec2 { x: 1.0, y: 1.0 }, Vec2 { x: 3.0, y: 0.0 }));
+        assert!(!segments_intersect(p0, p1, Vec2 { x: 1.0, y: 0.0 }, Vec2 { x: 3.0, y: 0.0 }));
+        assert!(!segments_intersect(p0, p1, Vec2 { x: 3.0, y: 3.0 }, Vec2 { x: 4.0, y: 4.0 }));
+    }
+
     #[test]
     fn test_inverse_look_at() {
         let view = look_at(
@@ -0,0 +1,130 @@
+use crate::governor::Governor;
+use crate::level::Level;
+use crate::math::{Vec2, Vec4};
+use crate::pathfinding::{Graph, IncrementalSearch};
+use std::ffi::c_int;
+
+// NOTE: Shapes an overlay asks for this frame, in world units. Points are scaled with the camera
+// like every other marker.
+#[derive(Default)]
+pub struct DrawList {
+    pub points: Vec<(Vec2<f32>, f32, Vec4<f32>)>,
+    pub lines: Vec<(Vec2<f32>, Vec2<f32>, Vec4<f32>)>,
+}
+
+impl DrawList {
+    pub fn point(&mut self, at: Vec2<f32>, scale: f32, color: Vec4<f32>) {
+        self.points.push((at, scale, color));
+    }
+
+    pub fn line(&mut self, from: Vec2<f32>, to: Vec2<f32>, color: Vec4<f32>) {
+        self.lines.push((from, to, color));
+    }
+}
+
+// NOTE: What overlays get to look at, borrowed from the main loop for the length of a frame.
+pub struct Frame<'a> {
+    pub level: &'a Level,
+    pub graph: &'a Graph<Vec2<f32>>,
+    pub governor: &'a Governor,
+    // NOTE: The node under the cursor.
+    pub cursor: usize,
+    pub preview: Option<&'a IncrementalSearch>,
+}
+
+pub trait DebugOverlay {
+    fn name(&self) -> &'static str;
+
+    // NOTE: A GLFW key code.
+    fn key(&self) -> c_int;
+
+    fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList);
+}
+
+// NOTE: Overlays start hidden and are toggled by their own key.
+#[derive(Default)]
+pub struct Registry {
+    overlays: Vec<(Box<dyn DebugOverlay>, bool)>,
+}
+
+impl Registry {
+    pub fn register<O: DebugOverlay + 'static>(&mut self, overlay: O) {
+        assert!(self.overlays.iter().all(|(other, _)| other.key() != overlay.key()));
+        self.overlays.push((Box::new(overlay), false));
+    }
+
+    // NOTE: Toggles the overlays whose key was tapped this frame; returns them with their new
+    // state.
+    pub fn poll<F: Fn(c_int) -> bool>(&mut self, tapped: F) -> Vec<(&'static str, bool)> {
+        let mut toggled = vec![];
+        for (overlay, enabled) in &mut self.overlays {
+            if tapped(overlay.key()) {
+                *enabled = !*enabled;
+                toggled.push((overlay.name(), *enabled));
+            }
+        }
+        toggled
+    }
+
+    pub fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+        draw.points.clear();
+        draw.lines.clear();
+        for (overlay, enabled) in &mut self.overlays {
+            if *enabled {
+                overlay.render(frame, draw);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::GridToWorld;
+    use crate::level;
+    use std::time::Duration;
+
+    struct Waypoints;
+
+    impl DebugOverlay for Waypoints {
+        fn name(&self) -> &'static str {
+            "waypoints"
+        }
+
+        fn key(&self) -> c_int {
+            1
+        }
+
+        fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+            for node in &frame.graph.nodes {
+                draw.point(*node, 1.0, Vec4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 });
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let level = level::parse(b"...\n", false).unwrap();
+        let graph = level::graph(&level, GridToWorld::new(level.bounds, 1.0), &level::Standard);
+        let governor = Governor::new(Duration::from_millis(10));
+        let frame = Frame {
+            level: &level,
+            graph: &graph,
+            governor: &governor,
+            cursor: 0,
+            preview: None,
+        };
+
+        let mut registry = Registry::default();
+        registry.register(Waypoints);
+        let mut draw = DrawList::default();
+        registry.render(&frame, &mut draw);
+        assert!(draw.points.is_empty());
+
+        assert!(registry.poll(|key| key == 1) == [("waypoints", true)]);
+        assert!(registry.poll(|_| false).is_empty());
+        registry.render(&frame, &mut draw);
+        registry.render(&frame, &mut draw);
+        assert!(draw.points.len() == 3);
+    }
+}
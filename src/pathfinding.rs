@@ -1,6 +1,739 @@
-use crate::math::Distance;
+use crate::format;
+use crate::math::{Distance, Dot, Normalize, Vec2};
 use std::cmp;
-use std::collections::{BinaryHeap, VecDeque};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::thread;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Heuristic {
+    Euclidean,
+    Octile,
+    Manhattan,
+    Zero,
+    Landmarks,
+}
+
+impl Heuristic {
+    pub const ALL: [Self; 5] = [
+        Self::Euclidean,
+        Self::Octile,
+        Self::Manhattan,
+        Self::Zero,
+        Self::Landmarks,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Euclidean => "euclidean",
+            Self::Octile => "octile",
+            Self::Manhattan => "manhattan",
+            Self::Zero => "zero",
+            Self::Landmarks => "landmarks",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|heuristic| heuristic.name() == name)
+    }
+
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|heuristic| *heuristic == self).unwrap();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum TieBreak {
+    None,
+    Straight,
+    Cross,
+}
+
+impl TieBreak {
+    pub const ALL: [Self; 3] = [Self::None, Self::Straight, Self::Cross];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Straight => "straight",
+            Self::Cross => "cross",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|tie_break| tie_break.name() == name)
+    }
+}
+
+// NOTE: Tie-break penalties are scaled down so they only reorder (nearly) equal-cost nodes.
+const TIE_BREAK_WEIGHT: f32 = 0.001;
+
+// NOTE: Weighted A* searches on `f = g + (1 + epsilon / 10) * h`; paths cost at most that factor
+// more than optimal, in exchange for (often far) fewer expansions. `epsilon` is kept in tenths so
+// `Options` can stay a hashable cache key.
+pub const MAX_EPSILON: u8 = 40;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Options {
+    pub heuristic: Heuristic,
+    pub tie_break: TieBreak,
+    pub epsilon: u8,
+    pub partial: bool,
+    pub avoid: u32,
+}
+
+impl Options {
+    pub fn weight(self) -> f32 {
+        1.0 + (f32::from(self.epsilon) / 10.0)
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            heuristic: Heuristic::Euclidean,
+            tie_break: TieBreak::None,
+            epsilon: 0,
+            partial: false,
+            avoid: 0,
+        }
+    }
+}
+
+// NOTE: Edge weights and path costs. Integer costs compare exactly (handy for deterministic replay
+// tests); only the `f32` impl needs the `total_cmp` workaround and can carry tie-break nudges.
+pub trait Cost: Copy + Default + PartialOrd {
+    const ZERO: Self;
+    const INFINITY: Self;
+
+    fn plus(self, other: Self) -> Self;
+
+    fn minus(self, other: Self) -> Self;
+
+    fn order(&self, other: &Self) -> cmp::Ordering;
+
+    fn nudge(self, penalty: f32) -> Self;
+
+    fn inflate(self, epsilon: u8) -> Self;
+}
+
+impl Cost for f32 {
+    const ZERO: Self = 0.0;
+    const INFINITY: Self = Self::INFINITY;
+
+    fn plus(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn minus(self, other: Self) -> Self {
+        self - other
+    }
+
+    // NOTE: See `https://doc.rust-lang.org/std/primitive.f32.html#method.total_cmp`.
+    fn order(&self, other: &Self) -> cmp::Ordering {
+        self.total_cmp(other)
+    }
+
+    fn nudge(self, penalty: f32) -> Self {
+        penalty.mul_add(TIE_BREAK_WEIGHT, self)
+    }
+
+    fn inflate(self, epsilon: u8) -> Self {
+        (Self::from(epsilon) / 10.0).mul_add(self, self)
+    }
+}
+
+impl Cost for u32 {
+    const ZERO: Self = 0;
+    const INFINITY: Self = Self::MAX;
+
+    fn plus(self, other: Self) -> Self {
+        self.saturating_add(other)
+    }
+
+    fn minus(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+
+    fn order(&self, other: &Self) -> cmp::Ordering {
+        self.cmp(other)
+    }
+
+    // NOTE: Fractional nudges would round away, so integer costs ignore tie-breaking.
+    fn nudge(self, _: f32) -> Self {
+        self
+    }
+
+    fn inflate(self, epsilon: u8) -> Self {
+        self.saturating_mul(10 + Self::from(epsilon)) / 10
+    }
+}
+
+pub trait Estimate<C: Cost = f32> {
+    fn estimate(self, other: Self, heuristic: Heuristic) -> C;
+
+    fn tie_break(
+        self,
+        parent: Self,
+        grandparent: Option<Self>,
+        start: Self,
+        end: Self,
+        tie_break: TieBreak,
+    ) -> f32
+    where
+        Self: Sized;
+}
+
+impl Estimate for Vec2<f32> {
+    fn estimate(self, other: Self, heuristic: Heuristic) -> f32 {
+        let dx = (self.x - other.x).abs();
+        let dy = (self.y - other.y).abs();
+        // NOTE: Landmark bounds need the whole graph (see `Graph::estimate`); per node pair they
+        // fall back to straight-line distance.
+        match heuristic {
+            Heuristic::Euclidean | Heuristic::Landmarks => self.distance(other),
+            // NOTE: See `https://theory.stanford.edu/~amitp/GameProgramming/Heuristics.html#diagonal-distance`.
+            Heuristic::Octile => (std::f32::consts::SQRT_2 - 1.0).mul_add(dx.min(dy), dx.max(dy)),
+            Heuristic::Manhattan => dx + dy,
+            Heuristic::Zero => 0.0,
+        }
+    }
+
+    fn tie_break(
+        self,
+        parent: Self,
+        grandparent: Option<Self>,
+        start: Self,
+        end: Self,
+        tie_break: TieBreak,
+    ) -> f32 {
+        match tie_break {
+            TieBreak::None => 0.0,
+            TieBreak::Straight => grandparent.map_or(0.0, |grandparent| {
+                1.0 - (parent - grandparent).normalize().dot((self - parent).normalize())
+            }),
+            // NOTE: See `https://theory.stanford.edu/~amitp/GameProgramming/Heuristics.html#breaking-ties`.
+            TieBreak::Cross => {
+                let a = self - end;
+                let b = start - end;
+                a.x.mul_add(b.y, -(b.x * a.y)).abs() / b.dot(b).sqrt()
+            }
+        }
+    }
+}
+
+// NOTE: Integer grid coordinates with integer costs; every estimate rounds down so it stays
+// admissible.
+impl Estimate<u32> for Vec2<i32> {
+    fn estimate(self, other: Self, heuristic: Heuristic) -> u32 {
+        let dx = self.x.abs_diff(other.x);
+        let dy = self.y.abs_diff(other.y);
+        match heuristic {
+            Heuristic::Euclidean | Heuristic::Landmarks => {
+                (u64::from(dx).pow(2) + u64::from(dy).pow(2)).isqrt().try_into().unwrap()
+            }
+            // NOTE: `41 / 100` rounds `SQRT_2 - 1` down.
+            Heuristic::Octile => dx.max(dy) + ((dx.min(dy) * 41) / 100),
+            Heuristic::Manhattan => dx + dy,
+            Heuristic::Zero => 0,
+        }
+    }
+
+    fn tie_break(self, _: Self, _: Option<Self>, _: Self, _: Self, _: TieBreak) -> f32 {
+        0.0
+    }
+}
+
+// NOTE: `tags` holds a bitmask of labels per node; searches never enter a node whose tags overlap
+// `Options::avoid` (other than their start).
+#[derive(Clone)]
+pub struct Graph<T, C = f32> {
+    pub nodes: Vec<T>,
+    pub weights: Vec<C>,
+    pub tags: Vec<u32>,
+    pub version: u64,
+    disabled: HashMap<(usize, usize), C>,
+    landmarks: Landmarks<C>,
+}
+
+// NOTE: Costs from and to each landmark, valid only for the graph version they were computed at.
+// By the triangle inequality `d(L, j) - d(L, i)` and `d(i, L) - d(j, L)` never exceed `d(i, j)`.
+#[derive(Clone)]
+struct Landmarks<C> {
+    version: Option<u64>,
+    from: Vec<Vec<C>>,
+    to: Vec<Vec<C>>,
+}
+
+impl<C> Default for Landmarks<C> {
+    fn default() -> Self {
+        Self {
+            version: None,
+            from: vec![],
+            to: vec![],
+        }
+    }
+}
+
+pub const LANDMARKS: usize = 8;
+
+impl<T, C: Cost> Graph<T, C> {
+    pub fn new(nodes: Vec<T>) -> Self {
+        let weights = vec![C::INFINITY; nodes.len() * nodes.len()];
+        let tags = vec![0; nodes.len()];
+        Self {
+            nodes,
+            weights,
+            tags,
+            version: 0,
+            disabled: HashMap::new(),
+            landmarks: Landmarks::default(),
+        }
+    }
+
+    pub fn masked(&self, i: usize, avoid: u32) -> bool {
+        (self.tags[i] & avoid) != 0
+    }
+
+    pub fn weight(&self, i: usize, j: usize) -> C {
+        self.weights[(i * self.nodes.len()) + j]
+    }
+
+    // NOTE: Writes to a disabled edge are parked until the edge is enabled again, so rebuilding a
+    // node never reopens a closed door.
+    pub fn set_weight(&mut self, i: usize, j: usize, weight: C) {
+        let n = self.nodes.len();
+        if let Some(disabled) = self.disabled.get_mut(&(i, j)) {
+            *disabled = weight;
+        } else {
+            self.weights[(i * n) + j] = weight;
+        }
+        self.version += 1;
+    }
+
+    pub fn disable_edge(&mut self, i: usize, j: usize) {
+        let n = self.nodes.len();
+        let weight = self.weights[(i * n) + j];
+        if (weight == C::INFINITY) || self.disabled.contains_key(&(i, j)) {
+            return;
+        }
+        self.disabled.insert((i, j), weight);
+        self.weights[(i * n) + j] = C::INFINITY;
+        self.version += 1;
+    }
+
+    pub fn enable_edge(&mut self, i: usize, j: usize) {
+        let n = self.nodes.len();
+        let Some(weight) = self.disabled.remove(&(i, j)) else {
+            return;
+        };
+        self.weights[(i * n) + j] = weight;
+        self.version += 1;
+    }
+
+    pub fn memory(&self) -> usize {
+        (self.nodes.capacity() * mem::size_of::<T>())
+            + (self.weights.capacity() * mem::size_of::<C>())
+            + (self.tags.capacity() * mem::size_of::<u32>())
+            + self
+                .landmarks
+                .from
+                .iter()
+                .chain(&self.landmarks.to)
+                .map(|costs| costs.capacity() * mem::size_of::<C>())
+                .sum::<usize>()
+    }
+
+    // NOTE: Re-selects up to `k` landmarks if the graph changed since the last call. Each one is
+    // the node furthest from its nearest landmark so far (the first is furthest from node `0`);
+    // unreachable nodes count as furthest, so every component gets a landmark.
+    pub fn update_landmarks(&mut self, k: usize) {
+        if (self.landmarks.version == Some(self.version)) || self.nodes.is_empty() {
+            return;
+        }
+        let mut nearest = costs_from(self, 0, false);
+        let mut from = Vec::with_capacity(k);
+        let mut to = Vec::with_capacity(k);
+        for _ in 0..k {
+            let Some(landmark) = (0..self.nodes.len())
+                .max_by(|i, j| nearest[*i].order(&nearest[*j]).then_with(|| j.cmp(i)))
+                .filter(|i| nearest[*i] != C::ZERO)
+            else {
+                break;
+            };
+            let costs = costs_from(self, landmark, false);
+            for (nearest, cost) in nearest.iter_mut().zip(&costs) {
+                if *cost < *nearest {
+                    *nearest = *cost;
+                }
+            }
+            from.push(costs);
+            to.push(costs_from(self, landmark, true));
+        }
+        self.landmarks = Landmarks {
+            version: Some(self.version),
+            from,
+            to,
+        };
+    }
+
+    pub fn clear_edges(&mut self, i: usize) {
+        let n = self.nodes.len();
+        self.weights[(i * n)..((i + 1) * n)].fill(C::INFINITY);
+        self.version += 1;
+    }
+}
+
+impl<T: Estimate<C> + Copy, C: Cost> Graph<T, C> {
+    // NOTE: Both bounds are admissible, so landmark estimates take the larger of the two. Landmarks
+    // left over from an older graph version are ignored.
+    pub fn estimate(&self, i: usize, j: usize, heuristic: Heuristic) -> C {
+        let mut estimate = self.nodes[i].estimate(self.nodes[j], heuristic);
+        if (heuristic != Heuristic::Landmarks) || (self.landmarks.version != Some(self.version)) {
+            return estimate;
+        }
+        for (from, to) in self.landmarks.from.iter().zip(&self.landmarks.to) {
+            for (a, b) in [(from[j], from[i]), (to[i], to[j])] {
+                if (a != C::INFINITY) && (b != C::INFINITY) && (estimate < a.minus(b)) {
+                    estimate = a.minus(b);
+                }
+            }
+        }
+        estimate
+    }
+}
+
+// NOTE: Dijkstra from `source` over every edge, or toward it when `reverse` is set.
+fn costs_from<T, C: Cost>(graph: &Graph<T, C>, source: usize, reverse: bool) -> Vec<C> {
+    let n = graph.nodes.len();
+    let mut costs = vec![C::INFINITY; n];
+    costs[source] = C::ZERO;
+    let mut heap = BinaryHeap::new();
+    heap.push(Node {
+        index: source,
+        cost: C::ZERO,
+        heuristic: C::ZERO,
+    });
+    while let Some(node) = heap.pop() {
+        if costs[node.index] < node.cost {
+            continue;
+        }
+        for (j, best) in costs.iter_mut().enumerate() {
+            let weight = if reverse {
+                graph.weight(j, node.index)
+            } else {
+                graph.weight(node.index, j)
+            };
+            if weight == C::INFINITY {
+                continue;
+            }
+            let cost = node.cost.plus(weight);
+            if cost < *best {
+                heap.push(Node { index: j, cost, heuristic: C::ZERO });
+                *best = cost;
+            }
+        }
+    }
+    costs
+}
+
+const GRAPH_KIND: [u8; 4] = *b"GRPH";
+const GRAPH_LEGACY_MAGIC: [u8; 4] = *b"PGRF";
+const GRAPH_MIGRATIONS: [format::Migration; 1] = [
+    // NOTE: Version 2 appends the disabled edges.
+    |body| Some([body, &0_u64.to_le_bytes()].concat()),
+];
+
+// NOTE: The body is little-endian: key (u64), node count (u64), nodes (f32 pairs), edge count
+// (u64), edges as (u32, u32, f32), then disabled edges in the same shape. Missing edges are
+// implied.
+impl Graph<Vec2<f32>> {
+    pub fn serialize(&self, key: u64) -> Vec<u8> {
+        let n = self.nodes.len();
+        let mut body = vec![];
+        body.extend_from_slice(&key.to_le_bytes());
+        body.extend_from_slice(&(n as u64).to_le_bytes());
+        for node in &self.nodes {
+            body.extend_from_slice(&node.x.to_le_bytes());
+            body.extend_from_slice(&node.y.to_le_bytes());
+        }
+        let edges: Vec<(usize, usize, f32)> = self
+            .weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| weight.is_finite())
+            .map(|(k, weight)| (k / n, k % n, *weight))
+            .collect();
+        let mut disabled: Vec<(usize, usize, f32)> =
+            self.disabled.iter().map(|((i, j), weight)| (*i, *j, *weight)).collect();
+        disabled.sort_unstable_by_key(|(i, j, _)| (*i, *j));
+        for edges in [edges, disabled] {
+            body.extend_from_slice(&(edges.len() as u64).to_le_bytes());
+            for (i, j, weight) in edges {
+                body.extend_from_slice(&u32::try_from(i).unwrap().to_le_bytes());
+                body.extend_from_slice(&u32::try_from(j).unwrap().to_le_bytes());
+                body.extend_from_slice(&weight.to_le_bytes());
+            }
+        }
+        format::write(GRAPH_KIND, (GRAPH_MIGRATIONS.len() + 1).try_into().unwrap(), &body)
+    }
+
+    pub fn deserialize(bytes: &[u8], key: u64) -> Option<Self> {
+        let body = format::migrate(bytes, GRAPH_KIND, Some(GRAPH_LEGACY_MAGIC), &GRAPH_MIGRATIONS)?;
+        let mut reader = format::Reader::new(&body);
+        if reader.u64()? != key {
+            return None;
+        }
+        let n = usize::try_from(reader.u64()?).ok()?;
+        let mut nodes = Vec::with_capacity(n);
+        for _ in 0..n {
+            nodes.push(Vec2 { x: reader.f32()?, y: reader.f32()? });
+        }
+        let mut graph = Self::new(nodes);
+        for disabled in [false, true] {
+            for _ in 0..reader.u64()? {
+                let i = usize::try_from(reader.u32()?).ok()?;
+                let j = usize::try_from(reader.u32()?).ok()?;
+                let weight = reader.f32()?;
+                if (n <= i) || (n <= j) {
+                    return None;
+                }
+                graph.set_weight(i, j, weight);
+                if disabled {
+                    graph.disable_edge(i, j);
+                }
+            }
+        }
+        reader.is_empty().then_some(graph)
+    }
+}
+
+// NOTE: Node indices along a route, start first. It dereferences to the underlying deque; the
+// geometric queries take the graph's node positions.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Path(VecDeque<usize>);
+
+impl Deref for Path {
+    type Target = VecDeque<usize>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for Path {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<const N: usize> From<[usize; N]> for Path {
+    fn from(nodes: [usize; N]) -> Self {
+        Self(VecDeque::from(nodes))
+    }
+}
+
+impl<const N: usize> PartialEq<[usize; N]> for Path {
+    fn eq(&self, other: &[usize; N]) -> bool {
+        self.0 == *other
+    }
+}
+
+impl FromIterator<usize> for Path {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
+impl IntoIterator for Path {
+    type Item = usize;
+    type IntoIter = std::collections::vec_deque::IntoIter<usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Path {
+    type Item = &'a usize;
+    type IntoIter = std::collections::vec_deque::Iter<'a, usize>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl Path {
+    pub fn points<'a>(&'a self, nodes: &'a [Vec2<f32>]) -> impl Iterator<Item = Vec2<f32>> + 'a {
+        self.0.iter().map(|i| nodes[*i])
+    }
+
+    pub fn length(&self, nodes: &[Vec2<f32>]) -> f32 {
+        self.points(nodes)
+            .zip(self.points(nodes).skip(1))
+            .map(|(a, b)| a.distance(b))
+            .sum()
+    }
+
+    // NOTE: Points every `spacing` units of arc length, starting with the first node; the last
+    // node is only included if it falls on the spacing.
+    pub fn resample(&self, nodes: &[Vec2<f32>], spacing: f32) -> Vec<Vec2<f32>> {
+        assert!(0.0 < spacing);
+        let mut samples: Vec<Vec2<f32>> = self.points(nodes).take(1).collect();
+        let mut t = spacing;
+        for (a, b) in self.points(nodes).zip(self.points(nodes).skip(1)) {
+            let length = a.distance(b);
+            loop {
+                if length < t {
+                    break;
+                }
+                samples.push(a + ((b - a) * (t / length).into()));
+                t += spacing;
+            }
+            t -= length;
+        }
+        samples
+    }
+
+    // NOTE: Returns the segment (starting at node `k`) holding the point of the path closest to
+    // `point`, and that point.
+    pub fn nearest(&self, nodes: &[Vec2<f32>], point: Vec2<f32>) -> Option<(usize, Vec2<f32>)> {
+        if self.0.len() == 1 {
+            return Some((0, nodes[self.0[0]]));
+        }
+        let mut nearest = None;
+        let mut min_d = f32::INFINITY;
+        for (k, (a, b)) in self.points(nodes).zip(self.points(nodes).skip(1)).enumerate() {
+            let ab = b - a;
+            let t = ((point - a).dot(ab) / ab.dot(ab)).clamp(0.0, 1.0);
+            let q = a + (ab * t.into());
+            let d = q.distance(point);
+            if d < min_d {
+                min_d = d;
+                nearest = Some((k, q));
+            }
+        }
+        nearest
+    }
+
+    // NOTE: Drops the nodes lying beyond `length` units of arc length (the start always stays).
+    pub fn truncate_length(&mut self, nodes: &[Vec2<f32>], length: f32) {
+        let mut traveled = 0.0;
+        let mut keep = self.0.len().min(1);
+        for k in 1..self.0.len() {
+            traveled += nodes[self.0[k - 1]].distance(nodes[self.0[k]]);
+            if length < traveled {
+                break;
+            }
+            keep = k + 1;
+        }
+        self.0.truncate(keep);
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct PathResult<C = f32> {
+    pub nodes: Path,
+    pub cost: C,
+    pub expanded: usize,
+    pub relaxed: usize,
+    pub stale: usize,
+}
+
+#[derive(Default)]
+pub struct PathCache {
+    version: u64,
+    paths: HashMap<(usize, usize, Options), PathResult>,
+    buffers: SearchBuffers,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl PathCache {
+    pub fn shortest_path<T: Estimate + Copy>(
+        &mut self,
+        graph: &Graph<T>,
+        start: usize,
+        end: usize,
+        options: Options,
+    ) -> PathResult {
+        if self.version != graph.version {
+            self.paths.clear();
+            self.version = graph.version;
+        }
+        if let Some(result) = self.paths.get(&(start, end, options)) {
+            self.hits += 1;
+            return result.clone();
+        }
+        self.misses += 1;
+        let mut result = PathResult::default();
+        shortest_path_into(graph, start, end, options, &mut self.buffers, &mut result);
+        self.paths.insert((start, end, options), result.clone());
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn memory(&self) -> usize {
+        (self.paths.capacity() * mem::size_of::<((usize, usize, Options), PathResult)>())
+            + self
+                .paths
+                .values()
+                .map(|result| result.nodes.capacity() * mem::size_of::<usize>())
+                .sum::<usize>()
+            + self.buffers.memory()
+    }
+}
+
+// NOTE: Spreads per-node edge rebuilds over several frames; each call to `step` re-derives at most
+// `budget` queued nodes.
+pub struct Rebuild {
+    pending: VecDeque<usize>,
+    queued: Vec<bool>,
+}
+
+impl Rebuild {
+    pub fn new(n: usize) -> Self {
+        Self {
+            pending: VecDeque::new(),
+            queued: vec![false; n],
+        }
+    }
+
+    pub fn schedule(&mut self, i: usize) {
+        if !self.queued[i] {
+            self.queued[i] = true;
+            self.pending.push_back(i);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn memory(&self) -> usize {
+        (self.pending.capacity() * mem::size_of::<usize>())
+            + (self.queued.capacity() * mem::size_of::<bool>())
+    }
+
+    pub fn step<F: FnMut(usize)>(&mut self, budget: usize, mut f: F) {
+        for _ in 0..budget {
+            let Some(i) = self.pending.pop_front() else {
+                return;
+            };
+            self.queued[i] = false;
+            f(i);
+        }
+    }
+}
 
 #[derive(Copy, Clone, PartialEq)]
 struct Node<T> {
@@ -9,79 +742,1473 @@ struct Node<T> {
     heuristic: T,
 }
 
-// NOTE: See `https://stackoverflow.com/questions/39949939/how-can-i-implement-a-min-heap-of-f64-with-rusts-binaryheap`.
-impl Eq for Node<f32> {}
+// NOTE: See `https://stackoverflow.com/questions/39949939/how-can-i-implement-a-min-heap-of-f64-with-rusts-binaryheap`.
+impl<C: Cost> Eq for Node<C> {}
+
+impl<C: Cost> PartialOrd for Node<C> {
+    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Cost> Ord for Node<C> {
+    fn cmp(&self, other: &Self) -> cmp::Ordering {
+        other
+            .cost
+            .plus(other.heuristic)
+            .order(&self.cost.plus(self.heuristic))
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchState {
+    Searching,
+    Found,
+    Exhausted,
+}
+
+// NOTE: Scratch space for a search. Callers that keep one around (and a `PathResult` to write
+// into) make no heap allocations once the buffers have grown to the graph's size. Heuristics are
+// kept while consecutive searches share a goal and graph version (landmark bounds move with the
+// edges), so buffers must stay with one graph's nodes.
+pub struct SearchBuffers<C = f32> {
+    costs: Vec<C>,
+    heuristics: Vec<C>,
+    goal: Option<(usize, Heuristic, u8, u64)>,
+    previous: Vec<usize>,
+    closed: Vec<u64>,
+    heap: BinaryHeap<Node<C>>,
+}
+
+impl<C> Default for SearchBuffers<C> {
+    fn default() -> Self {
+        Self {
+            costs: vec![],
+            heuristics: vec![],
+            goal: None,
+            previous: vec![],
+            closed: vec![],
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<C: Cost> SearchBuffers<C> {
+    pub fn memory(&self) -> usize {
+        (self.costs.capacity() * mem::size_of::<C>())
+            + (self.heuristics.capacity() * mem::size_of::<C>())
+            + (self.previous.capacity() * mem::size_of::<usize>())
+            + (self.closed.capacity() * mem::size_of::<u64>())
+            + (self.heap.capacity() * mem::size_of::<Node<C>>())
+    }
+}
+
+// NOTE: The closed set is a bitset with one bit per node.
+fn is_closed(closed: &[u64], i: usize) -> bool {
+    (closed[i / 64] & (1 << (i % 64))) != 0
+}
+
+fn close(closed: &mut [u64], i: usize) {
+    closed[i / 64] |= 1 << (i % 64);
+}
+
+// NOTE: A* whose open set survives between calls, so a search can be spread across frames. The
+// graph is passed to every `step` rather than borrowed; callers restart the search if
+// `graph.version` moves underneath it.
+pub struct IncrementalSearch<C = f32> {
+    start: usize,
+    end: usize,
+    options: Options,
+    pub version: u64,
+    buffers: SearchBuffers<C>,
+    state: SearchState,
+    closest: usize,
+    pub expanded: usize,
+    pub relaxed: usize,
+    pub stale: usize,
+}
+
+impl<C: Cost> IncrementalSearch<C> {
+    pub fn new<T: Estimate<C> + Copy>(
+        graph: &Graph<T, C>,
+        start: usize,
+        end: usize,
+        options: Options,
+    ) -> Self {
+        Self::with_buffers(graph, start, end, options, SearchBuffers::default())
+    }
+
+    pub fn with_buffers<T: Estimate<C> + Copy>(
+        graph: &Graph<T, C>,
+        start: usize,
+        end: usize,
+        options: Options,
+        buffers: SearchBuffers<C>,
+    ) -> Self {
+        let mut search = Self {
+            start,
+            end,
+            options,
+            version: graph.version,
+            buffers,
+            state: SearchState::Searching,
+            closest: start,
+            expanded: 0,
+            relaxed: 0,
+            stale: 0,
+        };
+        search.restart(graph, start, end, options);
+        search
+    }
+
+    // NOTE: Begins a new search in place, reusing the buffers of the previous one.
+    pub fn restart<T: Estimate<C> + Copy>(
+        &mut self,
+        graph: &Graph<T, C>,
+        start: usize,
+        end: usize,
+        options: Options,
+    ) {
+        let nodes = &graph.nodes;
+        let buffers = &mut self.buffers;
+
+        buffers.costs.clear();
+        buffers.costs.resize(nodes.len(), C::INFINITY);
+        buffers.costs[start] = C::ZERO;
+
+        let goal = (end, options.heuristic, options.epsilon, graph.version);
+        if (buffers.goal != Some(goal)) || (buffers.heuristics.len() != nodes.len()) {
+            buffers.heuristics.clear();
+            buffers.heuristics.extend(
+                (0..nodes.len())
+                    .map(|i| graph.estimate(i, end, options.heuristic).inflate(options.epsilon)),
+            );
+            buffers.goal = Some(goal);
+        }
+
+        buffers.previous.clear();
+        buffers.previous.resize(nodes.len(), nodes.len());
+
+        buffers.closed.clear();
+        buffers.closed.resize(nodes.len().div_ceil(64), 0);
+
+        buffers.heap.clear();
+        buffers.heap.push(Node {
+            index: start,
+            cost: C::ZERO,
+            heuristic: buffers.heuristics[start],
+        });
+
+        self.start = start;
+        self.end = end;
+        self.options = options;
+        self.version = graph.version;
+        self.state = SearchState::Searching;
+        self.closest = start;
+        self.expanded = 0;
+        self.relaxed = 0;
+        self.stale = 0;
+    }
+
+    pub fn into_buffers(self) -> SearchBuffers<C> {
+        self.buffers
+    }
+
+    pub const fn key(&self) -> (usize, usize, Options) {
+        (self.start, self.end, self.options)
+    }
+
+    pub fn closed(&self, i: usize) -> bool {
+        is_closed(&self.buffers.closed, i)
+    }
+
+    pub fn step<T: Estimate<C> + Copy>(
+        &mut self,
+        graph: &Graph<T, C>,
+        max_expansions: usize,
+    ) -> SearchState {
+        assert!(self.version == graph.version);
+        let nodes = &graph.nodes;
+        let (start, end) = (self.start, self.end);
+        let buffers = &mut self.buffers;
+        for _ in 0..max_expansions {
+            if self.state != SearchState::Searching {
+                break;
+            }
+            let Some(node) = buffers.heap.pop() else {
+                self.state = SearchState::Exhausted;
+                break;
+            };
+            // NOTE: Improving an open node pushes a duplicate rather than decreasing its key; the
+            // outdated entries are dropped here once the node is closed.
+            if is_closed(&buffers.closed, node.index) {
+                self.stale += 1;
+                continue;
+            }
+            self.expanded += 1;
+            if node.index == end {
+                self.state = SearchState::Found;
+                break;
+            }
+            close(&mut buffers.closed, node.index);
+            if buffers.heuristics[node.index] < buffers.heuristics[self.closest] {
+                self.closest = node.index;
+            }
+            for j in 0..nodes.len() {
+                if (graph.weight(node.index, j) == C::INFINITY)
+                    || is_closed(&buffers.closed, j)
+                    || graph.masked(j, self.options.avoid)
+                {
+                    continue;
+                }
+                let cost = node.cost.plus(graph.weight(node.index, j));
+                if cost < buffers.costs[j] {
+                    let grandparent =
+                        (node.index != start).then(|| nodes[buffers.previous[node.index]]);
+                    let tie = nodes[j].tie_break(
+                        nodes[node.index],
+                        grandparent,
+                        nodes[start],
+                        nodes[end],
+                        self.options.tie_break,
+                    );
+                    buffers.heap.push(Node {
+                        index: j,
+                        cost,
+                        heuristic: buffers.heuristics[j].nudge(tie),
+                    });
+                    buffers.previous[j] = node.index;
+                    buffers.costs[j] = cost;
+                    self.relaxed += 1;
+                }
+            }
+        }
+        self.state
+    }
+
+    pub fn result_into(&self, result: &mut PathResult<C>) {
+        assert!(self.state == SearchState::Found);
+        self.trace(self.end, result);
+    }
+
+    // NOTE: After exhausting the graph, writes the path to the expanded node with the lowest
+    // heuristic, i.e. the closest the goal can be approached.
+    pub fn partial_into(&self, result: &mut PathResult<C>) {
+        assert!(self.state == SearchState::Exhausted);
+        self.trace(self.closest, result);
+    }
+
+    fn trace(&self, end: usize, result: &mut PathResult<C>) {
+        result.nodes.clear();
+        let mut i = end;
+        while i != self.start {
+            result.nodes.push_front(i);
+            i = self.buffers.previous[i];
+        }
+        result.nodes.push_front(self.start);
+        result.cost = self.buffers.costs[end];
+        result.expanded = self.expanded;
+        result.relaxed = self.relaxed;
+        result.stale = self.stale;
+    }
+}
+
+// NOTE: See `https://doc.rust-lang.org/std/collections/binary_heap/index.html`.
+pub fn shortest_path<T: Estimate<C> + Copy, C: Cost>(
+    graph: &Graph<T, C>,
+    start: usize,
+    end: usize,
+    options: Options,
+) -> PathResult<C> {
+    let mut result = PathResult::default();
+    shortest_path_into(graph, start, end, options, &mut SearchBuffers::default(), &mut result);
+    result
+}
+
+pub fn shortest_path_into<T: Estimate<C> + Copy, C: Cost>(
+    graph: &Graph<T, C>,
+    start: usize,
+    end: usize,
+    options: Options,
+    buffers: &mut SearchBuffers<C>,
+    result: &mut PathResult<C>,
+) {
+    let nodes = &graph.nodes;
 
-impl PartialOrd for Node<f32> {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+    result.nodes.clear();
+    if start == end {
+        result.nodes.push_back(start);
+        result.cost = C::ZERO;
+        result.expanded = 0;
+        result.relaxed = 0;
+        result.stale = 0;
+        return;
+    }
+    // NOTE: Straight-line distance never overestimates, so a direct edge no heavier than it is
+    // optimal.
+    if (graph.weight(start, end) <= nodes[start].estimate(nodes[end], Heuristic::Euclidean))
+        && !graph.masked(end, options.avoid)
+    {
+        result.nodes.extend([start, end]);
+        result.cost = graph.weight(start, end);
+        result.expanded = 1;
+        result.relaxed = 1;
+        result.stale = 0;
+        return;
+    }
+
+    let mut search =
+        IncrementalSearch::with_buffers(graph, start, end, options, mem::take(buffers));
+    // NOTE: An unreachable goal (e.g. behind a closed door) leaves the agent where it is, unless
+    // `options.partial` asks for a path toward it.
+    let state = search.step(graph, usize::MAX);
+    if state == SearchState::Found {
+        search.result_into(result);
+    } else if options.partial {
+        search.partial_into(result);
+    } else {
+        result.nodes.push_back(start);
+        result.cost = C::INFINITY;
+        result.expanded = search.expanded;
+        result.relaxed = search.relaxed;
+        result.stale = search.stale;
     }
+    *buffers = search.into_buffers();
 }
 
-impl Ord for Node<f32> {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        // NOTE: See `https://doc.rust-lang.org/std/primitive.f32.html#method.total_cmp`.
-        (other.cost + other.heuristic)
-            .total_cmp(&(self.cost + self.heuristic))
-            .then_with(|| other.index.cmp(&self.index))
+// NOTE: Answers many queries at once, in query order. Queries are grouped by goal so each goal's
+// heuristics are computed once per thread, then split across up to `threads` scoped threads that
+// each own their buffers.
+pub fn shortest_paths_batch<T, C>(
+    graph: &Graph<T, C>,
+    queries: &[(usize, usize)],
+    options: Options,
+    threads: usize,
+) -> Vec<PathResult<C>>
+where
+    T: Estimate<C> + Copy + Sync,
+    C: Cost + Send + Sync,
+{
+    let mut order: Vec<usize> = (0..queries.len()).collect();
+    order.sort_by_key(|k| queries[*k].1);
+    let chunk = order.len().div_ceil(threads.max(1)).max(1);
+
+    let mut results = vec![PathResult::default(); queries.len()];
+    thread::scope(|scope| {
+        let handles: Vec<_> = order
+            .chunks(chunk)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    let mut buffers = SearchBuffers::default();
+                    let mut answers = Vec::with_capacity(chunk.len());
+                    for k in chunk {
+                        let (start, end) = queries[*k];
+                        let mut result = PathResult::default();
+                        shortest_path_into(graph, start, end, options, &mut buffers, &mut result);
+                        answers.push((*k, result));
+                    }
+                    answers
+                })
+            })
+            .collect();
+        for handle in handles {
+            for (k, result) in handle.join().unwrap() {
+                results[k] = result;
+            }
+        }
+    });
+    results
+}
+
+enum Deepening<C> {
+    Found(C),
+    Bound(C),
+}
+
+#[allow(clippy::too_many_arguments)]
+fn deepen<T: Estimate<C> + Copy, C: Cost>(
+    graph: &Graph<T, C>,
+    end: usize,
+    options: Options,
+    cost: C,
+    bound: C,
+    path: &mut Vec<usize>,
+    on_path: &mut Vec<bool>,
+    result: &mut PathResult<C>,
+) -> Deepening<C> {
+    let nodes = &graph.nodes;
+    let i = *path.last().unwrap();
+    let estimate = cost.plus(graph.estimate(i, end, options.heuristic).inflate(options.epsilon));
+    if bound < estimate {
+        return Deepening::Bound(estimate);
     }
+    result.expanded += 1;
+    if i == end {
+        return Deepening::Found(cost);
+    }
+    let mut next = C::INFINITY;
+    for j in 0..nodes.len() {
+        let weight = graph.weight(i, j);
+        if (weight == C::INFINITY) || on_path[j] {
+            continue;
+        }
+        result.relaxed += 1;
+        path.push(j);
+        on_path[j] = true;
+        match deepen(graph, end, options, cost.plus(weight), bound, path, on_path, result) {
+            Deepening::Found(cost) => return Deepening::Found(cost),
+            Deepening::Bound(estimate) => {
+                if estimate < next {
+                    next = estimate;
+                }
+            }
+        }
+        on_path[j] = false;
+        path.pop();
+    }
+    Deepening::Bound(next)
 }
 
-// NOTE: See `https://doc.rust-lang.org/std/collections/binary_heap/index.html`.
-pub fn shortest_path<T: Distance<f32> + Copy>(
-    nodes: &[T],
-    weights: &[f32],
+// NOTE: Iterative-deepening A* (see `https://en.wikipedia.org/wiki/Iterative_deepening_A*`). Memory
+// is linear in the path length rather than the open set, at the price of re-expanding nodes on
+// every iteration; tie-breaking, partial paths and tag filters are not supported.
+pub fn ida_star<T: Estimate<C> + Copy, C: Cost>(
+    graph: &Graph<T, C>,
     start: usize,
     end: usize,
-    counter: &mut usize,
-) -> VecDeque<usize> {
-    let mut costs = vec![f32::INFINITY; nodes.len()];
-    costs[start] = 0.0;
+    options: Options,
+) -> PathResult<C> {
+    let nodes = &graph.nodes;
+    let mut result = PathResult::default();
+    let mut path = vec![start];
+    let mut on_path = vec![false; nodes.len()];
+    on_path[start] = true;
 
-    let heuristics: Vec<f32> = nodes.iter().map(|node| node.distance(nodes[end])).collect();
+    let mut bound = graph.estimate(start, end, options.heuristic).inflate(options.epsilon);
+    loop {
+        match deepen(graph, end, options, C::ZERO, bound, &mut path, &mut on_path, &mut result) {
+            Deepening::Found(cost) => {
+                result.nodes = path.into_iter().collect();
+                result.cost = cost;
+                return result;
+            }
+            Deepening::Bound(next) if next == C::INFINITY => {
+                result.nodes = Path::from([start]);
+                result.cost = C::INFINITY;
+                return result;
+            }
+            Deepening::Bound(next) => bound = next,
+        }
+    }
+}
+
+// NOTE: Space-time reservations for cooperative planning; `nodes` maps (node, timestep) to the
+// agent standing there and `edges` maps (from, to, timestep) to the agent crossing between them.
+#[derive(Default)]
+pub struct Reservations {
+    nodes: HashMap<(usize, usize), usize>,
+    edges: HashMap<(usize, usize, usize), usize>,
+}
+
+impl Reservations {
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+    }
+
+    // NOTE: `path[t]` is where `agent` is at timestep `t`; once the path ends the agent is assumed
+    // to park on its last node until the window closes.
+    pub fn reserve(&mut self, agent: usize, path: &VecDeque<usize>, window: usize) {
+        for t in 0..=window {
+            let i = path[t.min(path.len() - 1)];
+            self.nodes.insert((i, t), agent);
+            if let Some(j) = path.get(t + 1) {
+                self.edges.insert((i, *j, t), agent);
+            }
+        }
+    }
+
+    fn is_free(&self, agent: usize, i: usize, j: usize, t: usize) -> bool {
+        let other = |owner: Option<&usize>| owner.is_some_and(|owner| *owner != agent);
+        !other(self.nodes.get(&(j, t + 1))) && !other(self.edges.get(&(j, i, t)))
+    }
+
+    // NOTE: Cuts a path planned without reservations at the first step that would enter another
+    // agent's slot, so its owner stops short rather than running into them.
+    pub fn clip(&self, agent: usize, path: &mut VecDeque<usize>) {
+        if let Some(t) =
+            (1..path.len()).find(|t| !self.is_free(agent, path[t - 1], path[*t], t - 1))
+        {
+            path.truncate(t);
+        }
+    }
+}
+
+// NOTE: Windowed cooperative A* (see `https://www.davidsilver.uk/wp-content/uploads/2020/03/coop-path-AIIDE.pdf`).
+// States are (node, timestep) pairs; each timestep an agent either waits or crosses one edge, and
+// never enters a slot another agent reserved. Past `window` timesteps the plan is cut at the state
+// closest to the goal.
+pub fn cooperative_path<T: Estimate + Copy>(
+    graph: &Graph<T>,
+    start: usize,
+    end: usize,
+    agent: usize,
+    reservations: &Reservations,
+    window: usize,
+) -> VecDeque<usize> {
+    let nodes = &graph.nodes;
+    let n = nodes.len();
+    let heuristic = |i: usize| nodes[i].estimate(nodes[end], Heuristic::Euclidean);
+    let wait = |i: usize| {
+        (0..n)
+            .map(|j| graph.weight(i, j))
+            .fold(f32::INFINITY, f32::min)
+            .min(heuristic(start))
+    };
 
-    let mut heap = BinaryHeap::with_capacity(nodes.len());
+    let mut costs: HashMap<usize, f32> = HashMap::from([(start, 0.0)]);
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut heap = BinaryHeap::new();
     heap.push(Node {
         index: start,
-        cost: costs[start],
-        heuristic: heuristics[start],
+        cost: 0.0,
+        heuristic: heuristic(start),
     });
 
-    *counter = 0;
-    let mut previous = vec![nodes.len(); nodes.len()];
+    let mut best = start;
     while let Some(node) = heap.pop() {
-        *counter += 1;
-        if node.index == end {
+        let (i, t) = (node.index % n, node.index / n);
+        if costs[&node.index] < node.cost {
+            continue;
+        }
+        if (i == end) || (t == window) {
+            best = node.index;
             break;
         }
-        if costs[node.index] < node.cost {
+        for j in (0..n).filter(|j| (*j == i) || graph.weight(i, *j).is_finite()) {
+            if !reservations.is_free(agent, i, j, t) {
+                continue;
+            }
+            let state = ((t + 1) * n) + j;
+            let cost = node.cost + if j == i { wait(i) } else { graph.weight(i, j) };
+            if costs.get(&state).is_none_or(|other| cost < *other) {
+                costs.insert(state, cost);
+                previous.insert(state, node.index);
+                heap.push(Node {
+                    index: state,
+                    cost,
+                    heuristic: heuristic(j),
+                });
+            }
+        }
+    }
+
+    let mut path = VecDeque::with_capacity(window + 1);
+    let mut state = best;
+    path.push_front(state % n);
+    while let Some(parent) = previous.get(&state) {
+        state = *parent;
+        path.push_front(state % n);
+    }
+    path
+}
+
+// NOTE: A* over (node, predecessor) pairs, so each state knows the heading it was entered with and
+// a step can be charged `penalty` per right angle it turns (scaled by `1 - cos` of the turn).
+// Routes come out straighter than `shortest_path`'s, and the result's cost includes the penalties.
+// States are kept in maps since only a few predecessors of each node are ever reached.
+pub fn turning_path(
+    graph: &Graph<Vec2<f32>>,
+    start: usize,
+    end: usize,
+    options: Options,
+    penalty: f32,
+) -> PathResult {
+    let nodes = &graph.nodes;
+    let n = nodes.len();
+    let heuristic = |i: usize| graph.estimate(i, end, options.heuristic).inflate(options.epsilon);
+
+    // NOTE: State `(i * (n + 1)) + parent` is node `i` entered from `parent`, where `n` stands for
+    // no parent (the start).
+    let mut result = PathResult::default();
+    let mut costs: HashMap<usize, f32> = HashMap::from([((start * (n + 1)) + n, 0.0)]);
+    let mut previous: HashMap<usize, usize> = HashMap::new();
+    let mut heap = BinaryHeap::new();
+    heap.push(Node {
+        index: (start * (n + 1)) + n,
+        cost: 0.0,
+        heuristic: heuristic(start),
+    });
+
+    let mut best = None;
+    while let Some(node) = heap.pop() {
+        let (i, parent) = (node.index / (n + 1), node.index % (n + 1));
+        if costs[&node.index] < node.cost {
+            result.stale += 1;
             continue;
         }
-        for j in 0..nodes.len() {
-            if weights[(node.index * nodes.len()) + j].is_infinite() {
+        result.expanded += 1;
+        if i == end {
+            best = Some(node);
+            break;
+        }
+        let heading = (parent != n).then(|| (nodes[i] - nodes[parent]).normalize());
+        for j in 0..n {
+            let weight = graph.weight(i, j);
+            if weight.is_infinite() || graph.masked(j, options.avoid) {
                 continue;
             }
-            let cost = node.cost + weights[(node.index * nodes.len()) + j];
-            if cost < costs[j] {
+            let turn =
+                heading.map_or(0.0, |heading| 1.0 - heading.dot((nodes[j] - nodes[i]).normalize()));
+            let cost = node.cost + penalty.mul_add(turn, weight);
+            let state = (j * (n + 1)) + i;
+            if costs.get(&state).is_none_or(|other| cost < *other) {
+                costs.insert(state, cost);
+                previous.insert(state, node.index);
                 heap.push(Node {
-                    index: j,
+                    index: state,
                     cost,
-                    heuristic: heuristics[j],
+                    heuristic: heuristic(j),
                 });
-                previous[j] = node.index;
-                costs[j] = cost;
+                result.relaxed += 1;
             }
         }
     }
 
-    let mut path = VecDeque::with_capacity(nodes.len());
-    {
-        let mut i = end;
-        while i != start {
-            path.push_front(i);
-            i = previous[i];
+    let Some(best) = best else {
+        result.nodes.push_back(start);
+        result.cost = f32::INFINITY;
+        return result;
+    };
+    result.cost = best.cost;
+    let mut state = best.index;
+    result.nodes.push_front(state / (n + 1));
+    while let Some(parent) = previous.get(&state) {
+        state = *parent;
+        result.nodes.push_front(state / (n + 1));
+    }
+    result
+}
+
+// NOTE: Greedy string pulling; from each kept node, skip ahead to the furthest node that is still
+// directly visible.
+pub fn smooth<F: Fn(usize, usize) -> bool>(path: &Path, visible: F) -> Path {
+    let mut smoothed = Path(VecDeque::with_capacity(path.len()));
+    let Some(first) = path.front() else {
+        return smoothed;
+    };
+    smoothed.push_back(*first);
+
+    let mut i = 0;
+    while i + 1 < path.len() {
+        let mut j = i + 1;
+        for k in ((i + 2)..path.len()).rev() {
+            if visible(path[i], path[k]) {
+                j = k;
+                break;
+            }
         }
+        smoothed.push_back(path[j]);
+        i = j;
+    }
+    smoothed
+}
+
+// NOTE: Union-find over the graph's edges, ignoring their direction; returns a component label per
+// node. Nodes with different labels can never reach each other (nodes sharing one still might, if
+// an edge only runs one way or `Options::avoid` masks the way).
+pub fn components<T, C: Cost>(graph: &Graph<T, C>) -> Vec<usize> {
+    fn find(parents: &mut [usize], mut i: usize) -> usize {
+        // NOTE: Path halving; see `https://en.wikipedia.org/wiki/Disjoint-set_data_structure#Finding_set_representatives`.
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let n = graph.nodes.len();
+    let mut parents: Vec<usize> = (0..n).collect();
+    for i in 0..n {
+        for j in (i + 1)..n {
+            if (graph.weight(i, j) == C::INFINITY) && (graph.weight(j, i) == C::INFINITY) {
+                continue;
+            }
+            let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+    (0..n).map(|i| find(&mut parents, i)).collect()
+}
+
+// NOTE: Runs Dijkstra backwards from `goal`; each node then points at the neighbor that leads
+// toward `goal` most cheaply. The goal itself and unreachable nodes have no next hop.
+pub fn flow_field<T>(goal: usize, graph: &Graph<T>) -> Vec<Option<usize>> {
+    let n = graph.nodes.len();
+
+    let mut costs = vec![f32::INFINITY; n];
+    costs[goal] = 0.0;
+
+    let mut heap = BinaryHeap::with_capacity(n);
+    heap.push(Node {
+        index: goal,
+        cost: 0.0,
+        heuristic: 0.0,
+    });
+
+    let mut next = vec![None; n];
+    while let Some(node) = heap.pop() {
+        if costs[node.index] < node.cost {
+            continue;
+        }
+        for i in 0..n {
+            let weight = graph.weight(i, node.index);
+            if weight.is_infinite() {
+                continue;
+            }
+            let cost = node.cost + weight;
+            if cost < costs[i] {
+                heap.push(Node { index: i, cost, heuristic: 0.0 });
+                next[i] = Some(node.index);
+                costs[i] = cost;
+            }
+        }
+    }
+    next
+}
+
+// NOTE: A* straight over a row-major tile bitmap, for maps whose waypoint count would make a
+// `Graph`'s weight matrix (and building it) too large. Cells are the nodes, so `nodes` in the
+// result holds cell indices. Steps go to the eight neighbors, are priced like `level::connect`
+// prices them (in cells rather than world units) and never cut past a blocked corner. Only
+// `heuristic` and `epsilon` are read from `options`.
+pub fn grid_path(
+    blocked: &[bool],
+    costs: &[f32],
+    width: usize,
+    start: usize,
+    end: usize,
+    options: Options,
+) -> PathResult {
+    assert!((blocked.len() == costs.len()) && blocked.len().is_multiple_of(width));
+    let height = blocked.len() / width;
+    #[allow(clippy::cast_precision_loss)]
+    let position = |i: usize| Vec2 {
+        x: (i % width) as f32,
+        y: (i / width) as f32,
+    };
+    let goal = position(end);
+    let estimate =
+        |i: usize| position(i).estimate(goal, options.heuristic).inflate(options.epsilon);
+
+    let mut result = PathResult::default();
+    let mut distances = vec![f32::INFINITY; blocked.len()];
+    distances[start] = 0.0;
+    let mut previous = vec![start; blocked.len()];
+    let mut closed = vec![0; blocked.len().div_ceil(64)];
+    let mut heap = BinaryHeap::new();
+    heap.push(Node {
+        index: start,
+        cost: 0.0,
+        heuristic: estimate(start),
+    });
+    while let Some(node) = heap.pop() {
+        if is_closed(&closed, node.index) {
+            result.stale += 1;
+            continue;
+        }
+        result.expanded += 1;
+        if node.index == end {
+            break;
+        }
+        close(&mut closed, node.index);
+        let (x, y) = (node.index % width, node.index / width);
+        for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+            for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+                let j = (ny * width) + nx;
+                if blocked[j] || is_closed(&closed, j) {
+                    continue;
+                }
+                if (nx != x)
+                    && (ny != y)
+                    && (blocked[(y * width) + nx] || blocked[(ny * width) + x])
+                {
+                    continue;
+                }
+                let weight = position(node.index).distance(position(j))
+                    * ((costs[node.index] + costs[j]) * 0.5);
+                let cost = node.cost + weight;
+                if cost < distances[j] {
+                    heap.push(Node {
+                        index: j,
+                        cost,
+                        heuristic: estimate(j),
+                    });
+                    previous[j] = node.index;
+                    distances[j] = cost;
+                    result.relaxed += 1;
+                }
+            }
+        }
+    }
+
+    result.cost = distances[end];
+    if result.cost.is_infinite() {
+        result.nodes.push_back(start);
+        return result;
+    }
+    let mut i = end;
+    while i != start {
+        result.nodes.push_front(i);
+        i = previous[i];
+    }
+    result.nodes.push_front(start);
+    result
+}
+
+// NOTE: Floyd-Warshall over the whole graph, leaving a next-hop table so queries are lookups. It
+// is rebuilt lazily whenever the graph version or avoid mask changes, which at O(n^3) only pays
+// off for small graphs. Weighted queries are left to the search.
+pub struct AllPairs {
+    costs: Vec<f32>,
+    next: Vec<Option<usize>>,
+    version: u64,
+    avoid: u32,
+}
+
+impl AllPairs {
+    pub fn new<T>(graph: &Graph<T>, avoid: u32) -> Self {
+        let n = graph.nodes.len();
+        let mut costs = vec![f32::INFINITY; n * n];
+        let mut next = vec![None; n * n];
+        for i in 0..n {
+            costs[(i * n) + i] = 0.0;
+            next[(i * n) + i] = Some(i);
+            for j in 0..n {
+                let weight = graph.weight(i, j);
+                if (i != j) && !weight.is_infinite() && !graph.masked(j, avoid) {
+                    costs[(i * n) + j] = weight;
+                    next[(i * n) + j] = Some(j);
+                }
+            }
+        }
+        for k in (0..n).filter(|k| !graph.masked(*k, avoid)) {
+            for i in 0..n {
+                let via = costs[(i * n) + k];
+                if via.is_infinite() {
+                    continue;
+                }
+                for j in 0..n {
+                    let cost = via + costs[(k * n) + j];
+                    if cost < costs[(i * n) + j] {
+                        costs[(i * n) + j] = cost;
+                        next[(i * n) + j] = next[(i * n) + k];
+                    }
+                }
+            }
+        }
+        Self {
+            costs,
+            next,
+            version: graph.version,
+            avoid,
+        }
+    }
+
+    // NOTE: Returns `None` when the table cannot answer the query (weighted options, or no path
+    // at all) so the caller can fall back to a search.
+    pub fn shortest_path<T>(
+        &mut self,
+        graph: &Graph<T>,
+        start: usize,
+        end: usize,
+        options: Options,
+    ) -> Option<PathResult> {
+        if options.epsilon != 0 {
+            return None;
+        }
+        if (self.version, self.avoid) != (graph.version, options.avoid) {
+            *self = Self::new(graph, options.avoid);
+        }
+        let n = graph.nodes.len();
+        let cost = self.costs[(start * n) + end];
+        if cost.is_infinite() {
+            return None;
+        }
+        let mut nodes = Path::from([start]);
+        let mut i = start;
+        while i != end {
+            i = self.next[(i * n) + end].unwrap();
+            nodes.push_back(i);
+        }
+        Some(PathResult {
+            nodes,
+            cost,
+            ..PathResult::default()
+        })
+    }
+
+    pub const fn memory(&self) -> usize {
+        (self.costs.capacity() * mem::size_of::<f32>())
+            + (self.next.capacity() * mem::size_of::<Option<usize>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    fn line(n: usize) -> Graph<Vec2<f32>> {
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new((0..n).map(|i| Vec2 { x: i as f32, y: 0.0 }).collect());
+        for i in 1..n {
+            graph.set_weight(i - 1, i, 1.0);
+            graph.set_weight(i, i - 1, 1.0);
+        }
+        graph
+    }
+
+    #[test]
+    fn test_shortest_path() {
+        let graph = line(5);
+        let result = shortest_path(&graph, 0, 4, Options::default());
+        assert!(result.nodes == [0, 1, 2, 3, 4]);
+        assert!((result.cost - 4.0).abs() < f32::EPSILON);
+        assert!(result.relaxed == 4);
+    }
+
+    #[test]
+    fn test_heuristics() {
+        let graph = line(6);
+        for heuristic in Heuristic::ALL {
+            let options = Options { heuristic, ..Options::default() };
+            let result = shortest_path(&graph, 5, 0, options);
+            assert!(result.nodes == [5, 4, 3, 2, 1, 0]);
+        }
+        let a = Vec2 { x: 0.0, y: 0.0 };
+        let b = Vec2 { x: 3.0, y: 4.0 };
+        assert!((a.estimate(b, Heuristic::Octile) - 5.242_64).abs() < 0.001);
+        assert!((a.estimate(b, Heuristic::Manhattan) - 7.0).abs() < 0.001);
+        assert!(Heuristic::Euclidean.next() == Heuristic::Octile);
+        assert!(Heuristic::Zero.next() == Heuristic::Landmarks);
+        assert!(Heuristic::Landmarks.next() == Heuristic::Euclidean);
+    }
+
+    #[test]
+    fn test_landmarks() {
+        // NOTE: A corridor winding from `(0, 0)` round to `(0, 5)`, plus a dead-end spur from the
+        // start pointing straight at the goal; straight-line distance is lured into the spur.
+        let mut points = vec![];
+        points.extend((0..6).map(|x| (x, 0)));
+        points.extend((1..6).map(|y| (5, y)));
+        points.extend((0..5).rev().map(|x| (x, 5)));
+        let corridor = points.len();
+        points.extend((1..4).map(|y| (0, y)));
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph =
+            Graph::new(points.iter().map(|(x, y)| Vec2 { x: *x as f32, y: *y as f32 }).collect());
+        for (i, j) in (1..corridor)
+            .map(|i| (i - 1, i))
+            .chain([(0, corridor)])
+            .chain(((corridor + 1)..points.len()).map(|i| (i - 1, i)))
+        {
+            graph.set_weight(i, j, 1.0);
+            graph.set_weight(j, i, 1.0);
+        }
+        let end = corridor - 1;
+        let euclidean = shortest_path(&graph, 0, end, Options::default());
+        let options = Options {
+            heuristic: Heuristic::Landmarks,
+            ..Options::default()
+        };
+        graph.update_landmarks(LANDMARKS);
+        let landmarks = shortest_path(&graph, 0, end, options);
+        assert!((landmarks.cost - euclidean.cost).abs() < 0.001);
+        assert!(landmarks.expanded < euclidean.expanded);
+
+        for i in 0..points.len() {
+            let costs = costs_from(&graph, i, false);
+            for (j, cost) in costs.into_iter().enumerate() {
+                assert!(graph.estimate(i, j, Heuristic::Landmarks) <= (cost + 0.001));
+            }
+        }
+
+        // NOTE: Stale landmarks are ignored until they are re-selected.
+        graph.set_weight(0, 1, 0.5);
+        assert!(graph.estimate(0, end, Heuristic::Landmarks) < 6.0);
+
+        // NOTE: A shortcut from the spur's tip; heuristics cached against the old landmarks would
+        // overestimate past it.
+        let mut cache = PathCache::default();
+        graph.update_landmarks(LANDMARKS);
+        cache.shortest_path(&graph, 0, end, options);
+        graph.set_weight(points.len() - 1, end - 1, 2.3);
+        graph.update_landmarks(LANDMARKS);
+        let shortcut = cache.shortest_path(&graph, 0, end, options);
+        assert!(
+            (shortcut.cost - shortest_path(&graph, 0, end, Options::default()).cost).abs() < 0.001
+        );
+    }
+
+    #[test]
+    fn test_weighted() {
+        // NOTE: A concave wall between the corners traps plain A* in its pocket.
+        let wall = |i: usize| {
+            (i % 8 == 5 && (1..6).contains(&(i / 8))) || (i / 8 == 5 && (1..6).contains(&(i % 8)))
+        };
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new(
+            (0..64)
+                .map(|i| Vec2 {
+                    x: (i % 8) as f32,
+                    y: (i / 8) as f32,
+                })
+                .collect(),
+        );
+        for i in 0..64 {
+            for j in 0..64 {
+                let distance = graph.nodes[i].distance(graph.nodes[j]);
+                if (i != j) && (distance < 1.5) && !wall(i) && !wall(j) {
+                    graph.set_weight(i, j, distance);
+                }
+            }
+        }
+        let optimal = shortest_path(&graph, 0, 63, Options::default());
+        for epsilon in [5, 10, MAX_EPSILON] {
+            let options = Options { epsilon, ..Options::default() };
+            let result = shortest_path(&graph, 0, 63, options);
+            assert!(result.nodes.back() == Some(&63));
+            assert!(optimal.cost <= result.cost + 0.001);
+            assert!(result.cost <= optimal.cost.mul_add(options.weight(), 0.001));
+            assert!(result.expanded < optimal.expanded);
+        }
+        assert!(3_u32.inflate(10) == 6);
+        assert!((2.0_f32.inflate(5) - 3.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_tie_break_straight() {
+        let mut graph = Graph::new(
+            [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (1.0, 1.0), (2.0, 1.0)]
+                .into_iter()
+                .map(|(x, y)| Vec2 { x, y })
+                .collect(),
+        );
+        for (i, j) in [(0, 1), (1, 2), (0, 3), (3, 4), (1, 4), (3, 2)] {
+            let weight = graph.nodes[i].distance(graph.nodes[j]);
+            graph.set_weight(i, j, weight);
+        }
+        let options = Options {
+            heuristic: Heuristic::Zero,
+            tie_break: TieBreak::Straight,
+            ..Options::default()
+        };
+        assert!(shortest_path(&graph, 0, 2, options).nodes == [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_graph_serialize() {
+        let graph = line(4);
+        let bytes = graph.serialize(7);
+        assert!(Graph::deserialize(&bytes, 8).is_none());
+        assert!(Graph::deserialize(&bytes[..bytes.len() - 1], 7).is_none());
+        let loaded = Graph::deserialize(&bytes, 7).unwrap();
+        assert!(loaded.nodes == graph.nodes);
+        assert!(loaded.weights == graph.weights);
+
+        let mut graph = graph;
+        graph.disable_edge(1, 2);
+        let mut loaded = Graph::deserialize(&graph.serialize(7), 7).unwrap();
+        assert!(loaded.weight(1, 2).is_infinite());
+        loaded.enable_edge(1, 2);
+        assert!((loaded.weight(1, 2) - 1.0).abs() < f32::EPSILON);
+
+        let legacy = [b"PGRF".as_slice(), &bytes[12..(bytes.len() - 8)]].concat();
+        assert!(Graph::deserialize(&legacy, 7).unwrap().weights == line(4).weights);
+    }
+
+    #[test]
+    fn test_closed_set() {
+        // NOTE: Every node of a 5x5 grid is reachable along several routes, so Dijkstra pushes
+        // duplicates; each node is still expanded at most once.
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new(
+            (0..25)
+                .map(|i| Vec2 {
+                    x: (i % 5) as f32,
+                    y: (i / 5) as f32,
+                })
+                .collect(),
+        );
+        for i in 0..25 {
+            for j in 0..25 {
+                let distance = graph.nodes[i].distance(graph.nodes[j]);
+                if (i != j) && (distance < 1.5) {
+                    graph.set_weight(i, j, distance * if (i + j) % 3 == 0 { 1.0 } else { 1.2 });
+                }
+            }
+        }
+        let options = Options {
+            heuristic: Heuristic::Zero,
+            ..Options::default()
+        };
+        let result = shortest_path(&graph, 0, 24, options);
+        assert!(result.expanded <= graph.nodes.len());
+        assert!(0 < result.stale);
+        assert!(result.nodes.back() == Some(&24));
+        let heuristic = shortest_path(&graph, 0, 24, Options::default());
+        assert!((result.cost - heuristic.cost).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_incremental_search() {
+        let graph = line(6);
+        let mut search = IncrementalSearch::new(&graph, 0, 5, Options::default());
+        assert!(search.step(&graph, 2) == SearchState::Searching);
+        assert!(search.expanded == 2);
+        while search.step(&graph, 2) == SearchState::Searching {}
+        let mut result = PathResult::default();
+        search.result_into(&mut result);
+        assert!(result.nodes == shortest_path(&graph, 0, 5, Options::default()).nodes);
+
+        let mut graph = graph;
+        graph.clear_edges(2);
+        let mut search = IncrementalSearch::new(&graph, 0, 5, Options::default());
+        assert!(search.step(&graph, usize::MAX) == SearchState::Exhausted);
+    }
+
+    #[test]
+    fn test_disable_edge() {
+        let mut graph = line(3);
+        let version = graph.version;
+        graph.disable_edge(1, 2);
+        assert!(graph.version != version);
+        assert!(graph.weight(1, 2).is_infinite());
+
+        graph.clear_edges(1);
+        graph.set_weight(1, 2, 2.0);
+        assert!(graph.weight(1, 2).is_infinite());
+
+        graph.enable_edge(1, 2);
+        assert!((graph.weight(1, 2) - 2.0).abs() < f32::EPSILON);
+        assert!(shortest_path(&graph, 0, 2, Options::default()).nodes == [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_shortest_paths_batch() {
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new(
+            (0..16)
+                .map(|i| Vec2 {
+                    x: (i % 4) as f32,
+                    y: (i / 4) as f32,
+                })
+                .collect(),
+        );
+        for i in 0..16 {
+            for j in 0..16 {
+                let distance = graph.nodes[i].distance(graph.nodes[j]);
+                if (i != j) && (distance < 1.5) {
+                    graph.set_weight(i, j, distance);
+                }
+            }
+        }
+        let queries = [
+            (0, 15),
+            (3, 12),
+            (5, 15),
+            (15, 0),
+            (7, 7),
+            (12, 15),
+            (1, 14),
+        ];
+        for threads in [1, 3, 16] {
+            let results = shortest_paths_batch(&graph, &queries, Options::default(), threads);
+            assert!(results.len() == queries.len());
+            for ((start, end), result) in queries.iter().zip(&results) {
+                let expected = shortest_path(&graph, *start, *end, Options::default());
+                assert!(result.nodes == expected.nodes);
+                assert!((result.cost - expected.cost).abs() < f32::EPSILON);
+            }
+        }
+        assert!(shortest_paths_batch(&graph, &[], Options::default(), 4).is_empty());
+    }
+
+    #[test]
+    fn test_avoid_tags() {
+        // NOTE: A square `0-1-2-3-0` where `1` is tagged, so avoiding it goes the long way round.
+        let mut graph = line(4);
+        graph.set_weight(3, 0, 5.0);
+        graph.set_weight(0, 3, 5.0);
+        graph.tags[1] = 0b10;
+        assert!(shortest_path(&graph, 0, 2, Options::default()).nodes == [0, 1, 2]);
+
+        let options = Options { avoid: 0b10, ..Options::default() };
+        assert!(shortest_path(&graph, 0, 2, options).nodes == [0, 3, 2]);
+        assert!(shortest_path(&graph, 1, 0, options).nodes == [1, 0]);
+        assert!(shortest_path(&graph, 0, 1, options).cost.is_infinite());
+        let options = Options { avoid: 0b01, ..options };
+        assert!(shortest_path(&graph, 0, 2, options).nodes == [0, 1, 2]);
+    }
+
+    #[test]
+    fn test_partial_path() {
+        let mut graph = line(5);
+        graph.disable_edge(2, 3);
+        graph.disable_edge(3, 2);
+        let result = shortest_path(&graph, 0, 4, Options::default());
+        assert!((result.nodes == [0]) && result.cost.is_infinite());
+
+        let options = Options { partial: true, ..Options::default() };
+        let result = shortest_path(&graph, 0, 4, options);
+        assert!(result.nodes == [0, 1, 2]);
+        assert!((result.cost - 2.0).abs() < f32::EPSILON);
+        assert!(shortest_path(&graph, 0, 1, options).nodes == [0, 1]);
+    }
+
+    #[test]
+    fn test_cooperative_path() {
+        // NOTE: A corridor `0-1-2-3-4` with a pocket `5` hanging off `2`.
+        let mut graph = line(5);
+        graph.nodes.push(Vec2 { x: 2.0, y: 1.0 });
+        let mut graph = Graph::new(graph.nodes);
+        for (i, j) in [(0, 1), (1, 2), (2, 3), (3, 4), (2, 5)] {
+            graph.set_weight(i, j, 1.0);
+            graph.set_weight(j, i, 1.0);
+        }
+        let window = 10;
+        let mut reservations = Reservations::default();
+
+        let first = cooperative_path(&graph, 0, 4, 0, &reservations, window);
+        assert!(first == [0, 1, 2, 3, 4]);
+        reservations.reserve(0, &first, window);
+
+        let second = cooperative_path(&graph, 3, 0, 1, &reservations, window);
+        assert!(second.back() == Some(&0));
+        let at = |path: &VecDeque<usize>, t: usize| path[t.min(path.len() - 1)];
+        for t in 0..window {
+            assert!(at(&first, t) != at(&second, t));
+            assert!((at(&first, t), at(&first, t + 1)) != (at(&second, t + 1), at(&second, t)));
+        }
+
+        reservations.clear();
+        assert!(cooperative_path(&graph, 0, 4, 1, &reservations, 2).len() == 3);
+    }
+
+    #[test]
+    fn test_integer_costs() {
+        let mut graph: Graph<Vec2<i32>, u32> =
+            Graph::new((0..9).map(|i| Vec2 { x: (i % 3) * 10, y: (i / 3) * 10 }).collect());
+        for i in 0..9 {
+            for j in 0..9 {
+                let d = graph.nodes[i].estimate(graph.nodes[j], Heuristic::Manhattan);
+                match d {
+                    10 => graph.set_weight(i, j, 10),
+                    20 if graph.nodes[i].x != graph.nodes[j].x
+                        && graph.nodes[i].y != graph.nodes[j].y =>
+                    {
+                        graph.set_weight(i, j, 15);
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for heuristic in Heuristic::ALL {
+            let options = Options { heuristic, ..Options::default() };
+            let result = shortest_path(&graph, 0, 5, options);
+            assert!(result.cost == 25);
+            assert!(result.nodes.len() == 3);
+        }
+        assert!(shortest_path(&graph, 0, 8, Options::default()).cost == 30);
+    }
+
+    #[test]
+    fn test_shortest_path_into() {
+        let graph = line(6);
+        let mut buffers = SearchBuffers::default();
+        let mut result = PathResult::default();
+        shortest_path_into(&graph, 0, 5, Options::default(), &mut buffers, &mut result);
+        assert!(result.nodes == [0, 1, 2, 3, 4, 5]);
+
+        let memory = buffers.memory();
+        let capacity = result.nodes.capacity();
+        shortest_path_into(&graph, 5, 1, Options::default(), &mut buffers, &mut result);
+        assert!(result.nodes == [5, 4, 3, 2, 1]);
+        assert!((buffers.memory(), result.nodes.capacity()) == (memory, capacity));
+    }
+
+    #[test]
+    fn test_ida_star() {
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new(
+            (0..12)
+                .map(|i| Vec2 {
+                    x: (i % 4) as f32,
+                    y: (i / 4) as f32,
+                })
+                .collect(),
+        );
+        for i in 0..12 {
+            for j in 0..12 {
+                let distance = graph.nodes[i].distance(graph.nodes[j]);
+                if (i != j) && (distance < 1.5) && ((i * j) % 5 != 1) {
+                    graph.set_weight(i, j, distance);
+                }
+            }
+        }
+        for heuristic in [Heuristic::Euclidean, Heuristic::Octile, Heuristic::Zero] {
+            let options = Options { heuristic, ..Options::default() };
+            for (start, end) in [(0, 11), (3, 8), (5, 6), (11, 0), (7, 7)] {
+                let expected = shortest_path(&graph, start, end, options);
+                let result = ida_star(&graph, start, end, options);
+                assert!((result.cost - expected.cost).abs() < 0.001);
+                assert!(result.nodes.front() == Some(&start));
+                assert!(result.nodes.back() == Some(&end));
+            }
+        }
+
+        let mut graph = line(4);
+        graph.clear_edges(2);
+        let result = ida_star(&graph, 0, 3, Options::default());
+        assert!((result.nodes == [0]) && result.cost.is_infinite());
+    }
+
+    #[test]
+    fn test_shortest_path_trivial() {
+        let graph = line(3);
+        let result = shortest_path(&graph, 1, 1, Options::default());
+        assert!((result.nodes == [1]) && (result.expanded == 0));
+        let result = shortest_path(&graph, 1, 2, Options::default());
+        assert!((result.nodes == [1, 2]) && (result.expanded == 1));
+    }
+
+    #[test]
+    fn test_turning_path() {
+        // NOTE: A 3x3 lattice; going corner to corner either way around costs four steps, but the
+        // staircase through the middle turns three times where an L turns once.
+        #[allow(clippy::cast_precision_loss)]
+        let mut graph = Graph::new(
+            (0..9)
+                .map(|i| Vec2 {
+                    x: (i % 3) as f32,
+                    y: (i / 3) as f32,
+                })
+                .collect(),
+        );
+        for i in 0..9 {
+            if i % 3 != 2 {
+                graph.set_weight(i, i + 1, 1.0);
+                graph.set_weight(i + 1, i, 1.0);
+            }
+            if i < 6 {
+                graph.set_weight(i, i + 3, 1.0);
+                graph.set_weight(i + 3, i, 1.0);
+            }
+        }
+        let result = turning_path(&graph, 0, 8, Options::default(), 0.5);
+        assert!((result.nodes == [0, 1, 2, 5, 8]) || (result.nodes == [0, 3, 6, 7, 8]));
+        assert!((result.cost - 4.5).abs() < 0.001);
+
+        let result = turning_path(&graph, 0, 4, Options::default(), 0.5);
+        assert!((result.nodes.len() == 3) && ((result.cost - 2.5).abs() < 0.001));
+    }
+
+    #[test]
+    fn test_smooth() {
+        let path = Path::from([0, 1, 2, 3, 4]);
+        assert!(smooth(&path, |_, _| true) == [0, 4]);
+        assert!(smooth(&path, |i, j| (j - i) == 1) == [0, 1, 2, 3, 4]);
+        assert!(smooth(&path, |i, j| (i < 2) == (j <= 2)) == [0, 2, 4]);
+    }
+
+    #[test]
+    fn test_path() {
+        let nodes = [
+            Vec2 { x: 0.0, y: 0.0 },
+            Vec2 { x: 2.0, y: 0.0 },
+            Vec2 { x: 2.0, y: 2.0 },
+        ];
+        let mut path = Path::from([0, 1, 2]);
+        assert!((path.length(&nodes) - 4.0).abs() < 0.01);
+
+        let samples = path.resample(&nodes, 1.5);
+        assert!(samples.len() == 3);
+        assert!((samples[1].x - 1.5).abs() < 0.01);
+        assert!((samples[2].x - 2.0).abs() < 0.01 && (samples[2].y - 1.0).abs() < 0.01);
+
+        let (k, point) = path.nearest(&nodes, Vec2 { x: 3.0, y: 1.5 }).unwrap();
+        assert!((k == 1) && ((point.x - 2.0).abs() < 0.01) && ((point.y - 1.5).abs() < 0.01));
+
+        path.truncate_length(&nodes, 3.0);
+        assert!(path == [0, 1]);
+        path.truncate_length(&nodes, 0.0);
+        assert!(path == [0]);
+    }
+
+    #[test]
+    fn test_components() {
+        let mut graph = line(5);
+        graph.disable_edge(2, 3);
+        graph.disable_edge(3, 2);
+        assert!(components(&graph) == [0, 0, 0, 3, 3]);
+
+        // NOTE: One direction is enough to join two components.
+        graph.enable_edge(3, 2);
+        assert!(components(&graph) == [0; 5]);
+    }
+
+    #[test]
+    fn test_flow_field() {
+        let mut graph = line(5);
+        graph.set_weight(3, 4, f32::INFINITY);
+        graph.set_weight(4, 3, f32::INFINITY);
+        let next = flow_field(1, &graph);
+        assert!(next == [Some(1), None, Some(1), Some(2), None]);
+    }
+
+    #[test]
+    fn test_grid_path() {
+        // NOTE: The center is blocked, so going corner to corner takes four orthogonal steps.
+        let mut blocked = [false; 9];
+        blocked[4] = true;
+        let costs = [1.0; 9];
+        let result = grid_path(&blocked, &costs, 3, 0, 8, Options::default());
+        assert!((result.nodes.len() == 5) && ((result.cost - 4.0).abs() < 0.001));
+
+        let result = grid_path(&[false; 9], &costs, 3, 0, 8, Options::default());
+        assert!(result.nodes == [0, 4, 8]);
+        assert!((result.cost - 2.828).abs() < 0.001);
+
+        blocked[5] = true;
+        blocked[7] = true;
+        let result = grid_path(&blocked, &costs, 3, 0, 8, Options::default());
+        assert!(result.nodes == [0] && result.cost.is_infinite());
+    }
+
+    #[test]
+    fn test_all_pairs() {
+        let mut graph = line(5);
+        graph.set_weight(4, 0, 1.5);
+        let mut table = AllPairs::new(&graph, 0);
+        let result = table.shortest_path(&graph, 4, 1, Options::default()).unwrap();
+        assert!((result.nodes == [4, 0, 1]) && ((result.cost - 2.5).abs() < f32::EPSILON));
+        assert!(
+            table.shortest_path(&graph, 0, 4, Options::default()).unwrap().nodes == [0, 1, 2, 3, 4]
+        );
+
+        let options = Options { epsilon: 10, ..Options::default() };
+        assert!(table.shortest_path(&graph, 0, 4, options).is_none());
+
+        graph.tags[2] = 1;
+        let options = Options { avoid: 1, ..Options::default() };
+        assert!(table.shortest_path(&graph, 0, 4, options).is_none());
+        assert!(table.shortest_path(&graph, 4, 1, options).unwrap().nodes == [4, 0, 1]);
+
+        graph.disable_edge(4, 0);
+        assert!(
+            table.shortest_path(&graph, 4, 1, Options::default()).unwrap().nodes == [4, 3, 2, 1]
+        );
     }
-    path.push_front(start);
-    path
 }
@@ -0,0 +1,119 @@
+use crate::math::{self, Dot, Mat4, Normalize, Vec2, Vec3, Vec4};
+
+// NOTE: A ray cast through the cursor, from the near plane towards `far` on the far plane.
+pub struct Ray {
+    pub origin: Vec3<f32>,
+    pub direction: Vec3<f32>,
+    pub far: Vec3<f32>,
+}
+
+const fn xyz(v: Vec4<f32>) -> Vec3<f32> {
+    Vec3 { x: v.x, y: v.y, z: v.z }
+}
+
+// NOTE: `cursor` is in window coordinates (origin at the top left, `y` down) and `viewport` is the
+// window size in the same units, as reported by `glfwGetCursorPos` and `glfwGetWindowSize`.
+// Nothing here assumes a perspective projection, so orthographic views pick just the same.
+#[allow(clippy::cast_possible_truncation)]
+pub fn ray(
+    projection: &Mat4<f32>,
+    view: &Mat4<f32>,
+    viewport: Vec2<f64>,
+    cursor: Vec2<f64>,
+) -> Ray {
+    let ndc = ((cursor / viewport) * 2.0.into()) - 1.0.into();
+    let inverse_projection = math::invert(projection);
+    let inverse_view = math::invert(view);
+
+    let mut near = Vec4 {
+        x: ndc.x as f32,
+        y: -ndc.y as f32,
+        z: -1.0,
+        w: 1.0,
+    };
+    let mut far = Vec4 { z: 1.0, ..near };
+    near = near.dot(&inverse_projection);
+    near /= near.w.into();
+    far = far.dot(&inverse_projection);
+    far /= far.w.into();
+
+    Ray {
+        origin: xyz(near.dot(&inverse_view)),
+        direction: xyz((far - near).dot(&inverse_view)).normalize(),
+        far: xyz(far.dot(&inverse_view)),
+    }
+}
+
+// NOTE: `None` when the ray runs parallel to the plane.
+pub fn intersect_plane(ray: &Ray, origin: Vec3<f32>, normal: Vec3<f32>) -> Option<Vec3<f32>> {
+    let denominator = normal.dot(ray.direction);
+    if denominator.abs() < f32::EPSILON {
+        return None;
+    }
+    let t = (origin - ray.origin).dot(normal) / denominator;
+    Some(ray.origin + (ray.direction * t.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Distance;
+
+    const VIEWPORT: Vec2<f64> = Vec2 { x: 1400.0, y: 900.0 };
+    const GROUND: Vec3<f32> = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+    const UP: Vec3<f32> = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+
+    fn orthographic(half_width: f32, half_height: f32, near: f32, far: f32) -> Mat4<f32> {
+        let mut mat = Mat4::default();
+        mat[0][0] = 1.0 / half_width;
+        mat[1][1] = 1.0 / half_height;
+        mat[2][2] = -2.0 / (far - near);
+        mat[3][2] = -(far + near) / (far - near);
+        mat[3][3] = 1.0;
+        mat
+    }
+
+    // NOTE: Where `point` lands in the window, so picking there should find it again.
+    fn screen(projection: &Mat4<f32>, view: &Mat4<f32>, point: Vec3<f32>) -> Vec2<f64> {
+        let mut clip = Vec4 {
+            x: point.x,
+            y: point.y,
+            z: point.z,
+            w: 1.0,
+        }
+        .dot(view)
+        .dot(projection);
+        clip /= clip.w.into();
+        Vec2 {
+            x: (f64::from(clip.x) + 1.0) * 0.5 * VIEWPORT.x,
+            y: (1.0 - f64::from(clip.y)) * 0.5 * VIEWPORT.y,
+        }
+    }
+
+    #[test]
+    #[allow(clippy::cast_possible_truncation)]
+    fn test_pick() {
+        let target = Vec3 { x: 7.5, y: -3.0, z: 0.0 };
+        let eye = |tilt: f32| Vec3 { x: 5.0, y: -tilt, z: 60.0 };
+        let to = Vec3 { x: 5.0, y: 0.0, z: 0.0 };
+        let aspect_ratio = (VIEWPORT.x / VIEWPORT.y) as f32;
+        for projection in [
+            math::perspective(45.0, aspect_ratio, 1.0, 200.0),
+            math::perspective(30.0, aspect_ratio * 0.5, 10.0, 100.0),
+            orthographic(20.0 * aspect_ratio, 20.0, 1.0, 200.0),
+        ] {
+            for tilt in [0.0, 25.0] {
+                let view = math::look_at(eye(tilt), to, Vec3 { x: 0.0, y: 1.0, z: 0.0 });
+                let cursor = screen(&projection, &view, target);
+                let hit = intersect_plane(&ray(&projection, &view, VIEWPORT, cursor), GROUND, UP);
+                assert!(hit.unwrap().distance(target) < 0.01);
+            }
+        }
+
+        let view = math::look_at(eye(0.0), to, Vec3 { x: 0.0, y: 1.0, z: 0.0 });
+        let projection = math::perspective(45.0, aspect_ratio, 1.0, 200.0);
+        let center = ray(&projection, &view, VIEWPORT, VIEWPORT * 0.5.into());
+        assert!(intersect_plane(&center, GROUND, UP).unwrap().distance(to) < 0.01);
+        assert!(intersect_plane(&center, GROUND, Vec3 { x: 1.0, y: 0.0, z: 0.0 }).is_none());
+    }
+}
@@ -0,0 +1,389 @@
+use crate::level::Error;
+use crate::math::Vec2;
+
+// NOTE: Imports a PNG as a floor plan: dark gray pixels (and transparent ones) are walls, light
+// gray ones open floor, pure green the player's spawn waypoint, and any other color a waypoint. The
+// plan then goes through `level::parse` like any other. Only non-interlaced 8-bit images are read.
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn fail(message: &str) -> Error {
+    Error::Import {
+        format: "PNG",
+        message: message.to_owned(),
+    }
+}
+
+struct Bits<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl Bits<'_> {
+    // NOTE: Deflate packs values least significant bit first.
+    fn bits(&mut self, n: u8) -> Result<u32, Error> {
+        let mut value = 0;
+        for k in 0..n {
+            let byte = self
+                .bytes
+                .get(self.offset / 8)
+                .ok_or_else(|| fail("truncated deflate stream"))?;
+            value |= u32::from((byte >> (self.offset % 8)) & 1) << k;
+            self.offset += 1;
+        }
+        Ok(value)
+    }
+
+    const fn align(&mut self) {
+        self.offset = self.offset.next_multiple_of(8);
+    }
+}
+
+// NOTE: Canonical Huffman code, decoded a bit at a time from the number of codes of each length.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; 16];
+        for length in lengths {
+            counts[usize::from(*length)] += 1;
+        }
+        counts[0] = 0;
+        let mut offsets = [0; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, length) in (0..).zip(lengths) {
+            if *length != 0 {
+                let offset = &mut offsets[usize::from(*length)];
+                symbols[usize::from(*offset)] = symbol;
+                *offset += 1;
+            }
+        }
+        Self { counts, symbols }
+    }
+
+    fn decode(&self, bits: &mut Bits<'_>) -> Result<u16, Error> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for count in &self.counts[1..] {
+            code |= bits.bits(1)?;
+            let count = u32::from(*count);
+            if code < first + count {
+                return Ok(self.symbols[usize::try_from(index + (code - first)).unwrap()]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(fail("invalid Huffman code"))
+    }
+}
+
+fn fixed() -> (Huffman, Huffman) {
+    let mut lengths = [8; 288];
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic(bits: &mut Bits<'_>) -> Result<(Huffman, Huffman), Error> {
+    let n_literals = bits.bits(5)? as usize + 257;
+    let n_distances = bits.bits(5)? as usize + 1;
+    let n_code_lengths = bits.bits(4)? as usize + 4;
+    let mut code_lengths = [0; 19];
+    for i in CODE_LENGTH_ORDER.into_iter().take(n_code_lengths) {
+        code_lengths[i] = u8::try_from(bits.bits(3)?).unwrap();
+    }
+    let code_lengths = Huffman::new(&code_lengths);
+
+    let mut lengths = Vec::with_capacity(n_literals + n_distances);
+    while lengths.len() < n_literals + n_distances {
+        let (length, repeat) = match code_lengths.decode(bits)? {
+            16 => (*lengths.last().ok_or_else(|| fail("nothing to repeat"))?, 3 + bits.bits(2)?),
+            17 => (0, 3 + bits.bits(3)?),
+            18 => (0, 11 + bits.bits(7)?),
+            symbol => (u8::try_from(symbol).unwrap(), 1),
+        };
+        lengths.extend(std::iter::repeat_n(length, repeat as usize));
+    }
+    if lengths.len() != n_literals + n_distances {
+        return Err(fail("code lengths overrun"));
+    }
+    Ok((Huffman::new(&lengths[..n_literals]), Huffman::new(&lengths[n_literals..])))
+}
+
+// NOTE: Decompresses a zlib stream (RFC 1950 around RFC 1951); the checksum is not verified.
+fn inflate(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    if !((2 <= bytes.len()) && ((bytes[0] & 0x0F) == 8)) {
+        return Err(fail("not a zlib stream"));
+    }
+    let mut bits = Bits { bytes: &bytes[2..], offset: 0 };
+    let mut out = vec![];
+    loop {
+        let last = bits.bits(1)? == 1;
+        let (literals, distances) = match bits.bits(2)? {
+            0 => {
+                bits.align();
+                let start = bits.offset / 8;
+                let header = bits
+                    .bytes
+                    .get(start..(start + 4))
+                    .ok_or_else(|| fail("truncated deflate stream"))?;
+                let n = usize::from(u16::from_le_bytes([header[0], header[1]]));
+                out.extend_from_slice(
+                    bits.bytes
+                        .get((start + 4)..(start + 4 + n))
+                        .ok_or_else(|| fail("truncated deflate stream"))?,
+                );
+                bits.offset = (start + 4 + n) * 8;
+                if last {
+                    return Ok(out);
+                }
+                continue;
+            }
+            1 => fixed(),
+            2 => dynamic(&mut bits)?,
+            _ => return Err(fail("invalid deflate block")),
+        };
+        loop {
+            let symbol = usize::from(literals.decode(&mut bits)?);
+            if symbol < 256 {
+                out.push(u8::try_from(symbol).unwrap());
+                continue;
+            }
+            if symbol == 256 {
+                break;
+            }
+            let k = symbol - 257;
+            let (Some(base), Some(extra)) = (LENGTH_BASE.get(k), LENGTH_EXTRA.get(k)) else {
+                return Err(fail("invalid length code"));
+            };
+            let length = usize::from(*base) + bits.bits(*extra)? as usize;
+            let k = usize::from(distances.decode(&mut bits)?);
+            let (Some(base), Some(extra)) = (DISTANCE_BASE.get(k), DISTANCE_EXTRA.get(k)) else {
+                return Err(fail("invalid distance code"));
+            };
+            let distance = usize::from(*base) + bits.bits(*extra)? as usize;
+            if out.len() < distance {
+                return Err(fail("distance too far back"));
+            }
+            for _ in 0..length {
+                out.push(out[out.len() - distance]);
+            }
+        }
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = i16::from(a) + i16::from(b) - i16::from(c);
+    let (pa, pb, pc) =
+        ((p - i16::from(a)).abs(), (p - i16::from(b)).abs(), (p - i16::from(c)).abs());
+    if (pa <= pb) && (pa <= pc) {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+// NOTE: Decodes to RGBA, one row after another.
+fn decode(bytes: &[u8]) -> Result<(Vec2<u16>, Vec<[u8; 4]>), Error> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(fail("not a PNG"));
+    }
+    let mut offset = SIGNATURE.len();
+    let (mut header, mut palette, mut transparency, mut data) = (None, vec![], vec![], vec![]);
+    while offset + 8 <= bytes.len() {
+        let n = u32::from_be_bytes(bytes[offset..(offset + 4)].try_into().unwrap()) as usize;
+        let kind = &bytes[(offset + 4)..(offset + 8)];
+        let body = bytes
+            .get((offset + 8)..(offset + 8 + n))
+            .ok_or_else(|| fail("truncated PNG chunk"))?;
+        match kind {
+            b"IHDR" => header = Some(body),
+            b"PLTE" => {
+                palette = body.chunks_exact(3).map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect();
+            }
+            b"tRNS" => transparency = body.to_vec(),
+            b"IDAT" => data.extend_from_slice(body),
+            b"IEND" => break,
+            _ => (),
+        }
+        // NOTE: Skips the chunk's CRC.
+        offset += 12 + n;
+    }
+    let header = header.filter(|header| header.len() == 13).ok_or_else(|| fail("missing IHDR"))?;
+    let size = Vec2 {
+        x: u16::try_from(u32::from_be_bytes(header[0..4].try_into().unwrap()))
+            .map_err(|_| fail("image too wide"))?,
+        y: u16::try_from(u32::from_be_bytes(header[4..8].try_into().unwrap()))
+            .map_err(|_| fail("image too tall"))?,
+    };
+    if (size.x == 0) || (size.y == 0) {
+        return Err(fail("empty image"));
+    }
+    let (depth, color, interlace) = (header[8], header[9], header[12]);
+    if depth != 8 {
+        return Err(fail("only 8-bit PNGs are supported"));
+    }
+    if interlace != 0 {
+        return Err(fail("interlaced PNGs are not supported"));
+    }
+    let channels = match color {
+        0 | 3 => 1,
+        4 => 2,
+        2 => 3,
+        6 => 4,
+        _ => return Err(fail(&format!("unsupported PNG color type {color}"))),
+    };
+    for (entry, alpha) in palette.iter_mut().zip(&transparency) {
+        entry[3] = *alpha;
+    }
+
+    let stride = usize::from(size.x) * channels;
+    let filtered = inflate(&data)?;
+    if filtered.len() != (stride + 1) * usize::from(size.y) {
+        return Err(fail("wrong amount of image data"));
+    }
+    let mut rows: Vec<u8> = Vec::with_capacity(stride * usize::from(size.y));
+    for (y, row) in filtered.chunks_exact(stride + 1).enumerate() {
+        let (filter, row) = (row[0], &row[1..]);
+        for (x, byte) in row.iter().enumerate() {
+            let i = (y * stride) + x;
+            let a = if channels <= x { rows[i - channels] } else { 0 };
+            let b = if 0 < y { rows[i - stride] } else { 0 };
+            let c = if (0 < y) && (channels <= x) {
+                rows[i - stride - channels]
+            } else {
+                0
+            };
+            rows.push(byte.wrapping_add(match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => a.midpoint(b),
+                4 => paeth(a, b, c),
+                _ => return Err(fail(&format!("invalid PNG filter {filter}"))),
+            }));
+        }
+    }
+    let pixels = rows
+        .chunks_exact(channels)
+        .map(|pixel| match color {
+            0 => Ok([pixel[0], pixel[0], pixel[0], 255]),
+            3 => palette
+                .get(usize::from(pixel[0]))
+                .copied()
+                .ok_or_else(|| fail("palette index out of range")),
+            4 => Ok([pixel[0], pixel[0], pixel[0], pixel[1]]),
+            2 => Ok([pixel[0], pixel[1], pixel[2], 255]),
+            _ => Ok([pixel[0], pixel[1], pixel[2], pixel[3]]),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok((size, pixels))
+}
+
+// NOTE: Returns the floor plan and the spawn cell, if the image marks one.
+pub fn import(bytes: &[u8]) -> Result<(Vec<u8>, Option<Vec2<u16>>), Error> {
+    let (size, pixels) = decode(bytes)?;
+    let mut floor_plan = Vec::with_capacity((usize::from(size.x) + 1) * usize::from(size.y));
+    let mut spawn = None;
+    for (y, row) in (0..).zip(pixels.chunks_exact(usize::from(size.x))) {
+        for (x, [r, g, b, a]) in (0..).zip(row) {
+            let (min, max) = ((*r).min(*g).min(*b), (*r).max(*g).max(*b));
+            floor_plan.push(if *a < 128 {
+                b'+'
+            } else if max - min < 32 {
+                if max < 128 {
+                    b'+'
+                } else {
+                    b' '
+                }
+            } else {
+                if (*g == 255) && (*r == 0) && (*b == 0) {
+                    if spawn.is_some() {
+                        return Err(fail("more than one spawn pixel"));
+                    }
+                    spawn = Some(Vec2 { x, y });
+                }
+                b'.'
+            });
+        }
+        floor_plan.push(b'\n');
+    }
+    Ok((floor_plan, spawn))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // NOTE: Written by zlib at its highest level: an RGB image packed with a fixed Huffman code,
+    // and an RGBA image that cycles through every row filter and is packed with a dynamic one.
+    const FIXED: [u8; 82] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 6, 0, 0, 0, 4, 8, 2,
+        0, 0, 0, 34, 102, 217, 20, 0, 0, 0, 25, 73, 68, 65, 84, 120, 218, 99, 96, 192, 14, 254, 51,
+        252, 7, 131, 19, 114, 114, 48, 17, 36, 128, 93, 15, 0, 209, 25, 19, 242, 177, 236, 102,
+        241, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+    const DYNAMIC: [u8; 171] = [
+        137, 80, 78, 71, 13, 10, 26, 10, 0, 0, 0, 13, 73, 72, 68, 82, 0, 0, 0, 12, 0, 0, 0, 8, 8,
+        6, 0, 0, 0, 205, 228, 30, 241, 0, 0, 0, 114, 73, 68, 65, 84, 120, 218, 149, 143, 129, 13,
+        128, 32, 12, 4, 191, 234, 26, 202, 24, 14, 195, 22, 78, 32, 195, 192, 44, 58, 134, 147, 84,
+        138, 136, 8, 26, 227, 37, 79, 62, 223, 167, 1, 0, 224, 63, 162, 96, 56, 30, 30, 34, 2, 243,
+        225, 75, 100, 214, 0, 87, 185, 100, 85, 170, 202, 90, 175, 217, 147, 2, 99, 76, 144, 100,
+        253, 52, 37, 127, 206, 186, 114, 67, 254, 156, 101, 24, 48, 110, 27, 30, 58, 7, 190, 192,
+        31, 128, 226, 133, 219, 6, 173, 53, 172, 181, 239, 159, 22, 147, 203, 57, 87, 101, 34, 97,
+        7, 5, 37, 90, 244, 4, 28, 43, 244, 0, 0, 0, 0, 73, 69, 78, 68, 174, 66, 96, 130,
+    ];
+
+    #[test]
+    fn test_import() {
+        let (floor_plan, spawn) = import(&FIXED).unwrap();
+        assert!(floor_plan == b"++++++\n+.  .+\n+    +\n++++++\n");
+        assert!(spawn == Some(Vec2 { x: 1, y: 1 }));
+
+        let (floor_plan, spawn) = import(&DYNAMIC).unwrap();
+        assert!(
+            floor_plan
+                == b"++++++++++++\n\
+                     +.  +      +\n\
+                     +   +  .   +\n\
+                     +   ++ ++  +\n\
+                     +      .   +\n\
+                     +  .       +\n\
+                     +    +     +\n\
+                     ++++++++++++\n"
+        );
+        assert!(spawn == Some(Vec2 { x: 1, y: 1 }));
+
+        let error = import(&FIXED[..(FIXED.len() - 30)]).err().unwrap();
+        assert!(error.to_string() == "PNG: truncated PNG chunk");
+    }
+}
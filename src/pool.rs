@@ -0,0 +1,89 @@
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Handle(usize);
+
+// NOTE: Fixed-capacity instance storage. Items stay contiguous (despawning swap-removes) so the
+// whole pool can be uploaded and drawn in one call, while handles keep pointing at the same item
+// no matter how it moves; freed handles are recycled through a free-list.
+pub struct Pool<T> {
+    items: Vec<T>,
+    owners: Vec<usize>,
+    slots: Vec<Option<usize>>,
+    free: Vec<usize>,
+}
+
+impl<T> Pool<T> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            items: Vec::with_capacity(capacity),
+            owners: Vec::with_capacity(capacity),
+            slots: vec![None; capacity],
+            free: (0..capacity).rev().collect(),
+        }
+    }
+
+    pub fn spawn(&mut self, item: T) -> Option<Handle> {
+        let handle = self.free.pop()?;
+        self.slots[handle] = Some(self.items.len());
+        self.items.push(item);
+        self.owners.push(handle);
+        Some(Handle(handle))
+    }
+
+    pub fn despawn(&mut self, handle: Handle) -> T {
+        let slot = self.slots[handle.0].take().unwrap();
+        let item = self.items.swap_remove(slot);
+        self.owners.swap_remove(slot);
+        if let Some(moved) = self.owners.get(slot) {
+            self.slots[*moved] = Some(slot);
+        }
+        self.free.push(handle.0);
+        item
+    }
+
+    pub fn get_mut(&mut self, handle: Handle) -> &mut T {
+        &mut self.items[self.slots[handle.0].unwrap()]
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        &self.items
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+
+    pub const fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub const fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool() {
+        let mut pool = Pool::with_capacity(3);
+        let a = pool.spawn('a').unwrap();
+        let b = pool.spawn('b').unwrap();
+        let c = pool.spawn('c').unwrap();
+        assert!(pool.spawn('d').is_none());
+
+        assert!(pool.despawn(a) == 'a');
+        assert!(pool.as_slice() == ['c', 'b']);
+        *pool.get_mut(c) = 'C';
+        assert!(*pool.get_mut(b) == 'b');
+
+        let d = pool.spawn('d').unwrap();
+        assert!(d == a);
+        assert!(pool.as_slice() == ['C', 'b', 'd']);
+        assert!(pool.despawn(b) == 'b');
+        assert!(pool.as_slice() == ['C', 'd']);
+        assert!(*pool.get_mut(d) == 'd');
+        assert!((pool.len(), pool.capacity()) == (2, 3));
+    }
+}
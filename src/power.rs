@@ -0,0 +1,45 @@
+use std::fs;
+use std::path::Path;
+
+pub const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+// NOTE: `supplies` are `(type, online)` pairs as sysfs reports them. The machine is on battery when
+// it has mains supplies and none of them is online; with no mains supply to go by it counts as
+// plugged in.
+fn on_battery_from<'a>(supplies: impl Iterator<Item = (&'a str, &'a str)>) -> bool {
+    let mut mains = supplies.filter(|(r#type, _)| *r#type == "Mains").peekable();
+    mains.peek().is_some() && mains.all(|(_, online)| online != "1")
+}
+
+// NOTE: Linux only; anywhere `root` can't be read counts as plugged in.
+pub fn on_battery(root: &Path) -> bool {
+    let Ok(entries) = fs::read_dir(root) else {
+        return false;
+    };
+    let supplies: Vec<(String, String)> = entries
+        .flatten()
+        .map(|entry| {
+            let read = |name| {
+                fs::read_to_string(entry.path().join(name))
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned()
+            };
+            (read("type"), read("online"))
+        })
+        .collect();
+    on_battery_from(supplies.iter().map(|(r#type, online)| (r#type.as_str(), online.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_battery() {
+        assert!(on_battery_from([("Mains", "0"), ("Battery", "")].into_iter()));
+        assert!(!on_battery_from([("Mains", "1"), ("Battery", "")].into_iter()));
+        assert!(!on_battery_from([("Mains", "0"), ("Mains", "1")].into_iter()));
+        assert!(!on_battery_from([("Battery", "")].into_iter()));
+    }
+}
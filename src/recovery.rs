@@ -0,0 +1,100 @@
+use crate::format;
+use crate::math::Vec2;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// NOTE: Edits made while the app runs (which doors are closed and which waypoints are walled off)
+// are autosaved next to the floor plan and the file is removed on a clean exit, so finding one at
+// launch means the last session ended without cleaning up after itself.
+const RECOVERY_KIND: [u8; 4] = *b"RCVR";
+const RECOVERY_MIGRATIONS: [format::Migration; 1] = [
+    // NOTE: Version 2 appends the walled off waypoints.
+    |body| Some([body, &0_u64.to_le_bytes()].concat()),
+];
+
+#[derive(Eq, PartialEq)]
+pub struct Recovery {
+    pub closed_doors: Vec<Vec2<u16>>,
+    pub walls: Vec<Vec2<u16>>,
+}
+
+pub fn path(floor_plan_path: &Path) -> PathBuf {
+    floor_plan_path.with_extension("recovery")
+}
+
+// NOTE: The body is little-endian: door count (u64), then each closed door as (u32, u32), then the
+// walled off waypoints in the same shape.
+fn serialize(recovery: &Recovery) -> Vec<u8> {
+    let mut body = vec![];
+    for cells in [&recovery.closed_doors, &recovery.walls] {
+        body.extend_from_slice(&(cells.len() as u64).to_le_bytes());
+        for cell in cells {
+            body.extend_from_slice(&u32::from(cell.x).to_le_bytes());
+            body.extend_from_slice(&u32::from(cell.y).to_le_bytes());
+        }
+    }
+    format::write(RECOVERY_KIND, (RECOVERY_MIGRATIONS.len() + 1).try_into().unwrap(), &body)
+}
+
+fn read_cells(reader: &mut format::Reader<'_>, len: usize) -> Option<Vec<Vec2<u16>>> {
+    let n = usize::try_from(reader.u64()?).ok()?;
+    let mut cells = Vec::with_capacity(n.min(len));
+    for _ in 0..n {
+        let x = u16::try_from(reader.u32()?).ok()?;
+        let y = u16::try_from(reader.u32()?).ok()?;
+        cells.push(Vec2 { x, y });
+    }
+    Some(cells)
+}
+
+fn deserialize(bytes: &[u8]) -> Option<Recovery> {
+    let body = format::migrate(bytes, RECOVERY_KIND, None, &RECOVERY_MIGRATIONS)?;
+    let mut reader = format::Reader::new(&body);
+    let closed_doors = read_cells(&mut reader, body.len())?;
+    let walls = read_cells(&mut reader, body.len())?;
+    reader.is_empty().then_some(Recovery { closed_doors, walls })
+}
+
+// NOTE: Written to a temporary file and renamed into place, so a crash mid-save leaves the previous
+// save intact.
+pub fn save(path: &Path, recovery: &Recovery) {
+    let temporary = path.with_extension("recovery.tmp");
+    fs::write(&temporary, serialize(recovery)).unwrap();
+    fs::rename(&temporary, path).unwrap();
+}
+
+// NOTE: A file that is unreadable or from another version is treated as absent.
+pub fn load(path: &Path) -> Option<Recovery> {
+    fs::read(path).ok().and_then(|bytes| deserialize(&bytes))
+}
+
+pub fn clear(path: &Path) {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => panic!("{error}"),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recovery() {
+        let recovery = Recovery {
+            closed_doors: vec![Vec2 { x: 3, y: 1 }, Vec2 { x: 65535, y: 0 }],
+            walls: vec![Vec2 { x: 2, y: 4 }],
+        };
+        let bytes = serialize(&recovery);
+        assert!(deserialize(&bytes).unwrap() == recovery);
+        assert!(deserialize(&bytes[..(bytes.len() - 1)]).is_none());
+        assert!(deserialize(&[bytes.as_slice(), &[0]].concat()).is_none());
+
+        let mut body = 1_u64.to_le_bytes().to_vec();
+        body.extend_from_slice(&[3, 0, 0, 0, 1, 0, 0, 0]);
+        let legacy = deserialize(&format::write(RECOVERY_KIND, 1, &body)).unwrap();
+        assert!(legacy.closed_doors == [Vec2 { x: 3, y: 1 }]);
+        assert!(legacy.walls.is_empty());
+    }
+}
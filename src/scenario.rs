@@ -0,0 +1,121 @@
+use crate::math::Vec2;
+use crate::pathfinding::{Heuristic, TieBreak};
+use std::path::PathBuf;
+
+#[derive(Eq, PartialEq)]
+pub enum Command {
+    Map(PathBuf),
+    Agents(usize),
+    Destination(Vec2<u16>),
+    Queue(Vec2<u16>),
+    Heuristic(Heuristic),
+    TieBreak(TieBreak),
+    Door(Vec2<u16>),
+    Screenshot(PathBuf),
+    Quit,
+}
+
+// NOTE: One command per line, prefixed with the frame it runs on, e.g. `120 destination 3 4`. Blank
+// lines and lines starting with `;` are skipped. `map` and `agents` configure startup and must run
+// on frame `0`.
+pub struct Scenario {
+    commands: Vec<(u64, Command)>,
+    next: usize,
+}
+
+fn cell(x: Option<&str>, y: Option<&str>) -> Vec2<u16> {
+    Vec2 {
+        x: x.unwrap().parse().unwrap(),
+        y: y.unwrap().parse().unwrap(),
+    }
+}
+
+pub fn parse(text: &str) -> Scenario {
+    let mut commands = vec![];
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let frame: u64 = words.next().unwrap().parse().unwrap();
+        let command = match words.next().unwrap() {
+            "map" => Command::Map(PathBuf::from(words.next().unwrap())),
+            "agents" => Command::Agents(words.next().unwrap().parse().unwrap()),
+            "destination" => Command::Destination(cell(words.next(), words.next())),
+            "queue" => Command::Queue(cell(words.next(), words.next())),
+            "heuristic" => Command::Heuristic(Heuristic::from_name(words.next().unwrap()).unwrap()),
+            "tie-break" => Command::TieBreak(TieBreak::from_name(words.next().unwrap()).unwrap()),
+            "door" => Command::Door(cell(words.next(), words.next())),
+            "screenshot" => Command::Screenshot(PathBuf::from(words.next().unwrap())),
+            "quit" => Command::Quit,
+            command => panic!("{command}"),
+        };
+        assert!(words.next().is_none());
+        if matches!(command, Command::Map(_) | Command::Agents(_)) {
+            assert!(frame == 0);
+        }
+        commands.push((frame, command));
+    }
+    commands.sort_by_key(|(frame, _)| *frame);
+    Scenario { commands, next: 0 }
+}
+
+impl Scenario {
+    pub fn map(&self) -> Option<&PathBuf> {
+        self.commands.iter().find_map(|(_, command)| match command {
+            Command::Map(path) => Some(path),
+            _ => None,
+        })
+    }
+
+    pub fn agents(&self) -> Option<usize> {
+        self.commands.iter().find_map(|(_, command)| match command {
+            Command::Agents(n) => Some(*n),
+            _ => None,
+        })
+    }
+
+    // NOTE: Returns the commands scheduled up to and including `frame` that have not run yet.
+    pub fn due(&mut self, frame: u64) -> &[(u64, Command)] {
+        let start = self.next;
+        while self.commands.get(self.next).is_some_and(|(at, _)| *at <= frame) {
+            self.next += 1;
+        }
+        &self.commands[start..self.next]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario() {
+        let mut scenario = parse(
+            "; demo\n\
+             0 map assets/floor-plan.txt\n\
+             0 agents 2\n\
+             30 queue 1 2\n\
+             10 destination 3 4\n\
+             10 heuristic octile\n\
+             60 quit\n",
+        );
+        assert!(scenario.map() == Some(&PathBuf::from("assets/floor-plan.txt")));
+        assert!(scenario.agents() == Some(2));
+        assert!(scenario.due(0).len() == 2);
+        assert!(scenario.due(5).is_empty());
+        assert!(
+            scenario.due(20)
+                == [
+                    (10, Command::Destination(Vec2 { x: 3, y: 4 })),
+                    (10, Command::Heuristic(Heuristic::Octile)),
+                ]
+        );
+        assert!(scenario.due(100).len() == 2);
+        assert!(scenario.due(200).is_empty());
+
+        let demo = parse(&std::fs::read_to_string("assets/demo.scenario").unwrap());
+        assert!(demo.map().is_none() && (demo.agents() == Some(2)));
+    }
+}
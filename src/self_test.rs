@@ -0,0 +1,259 @@
+use crate::assets;
+use crate::defer;
+use crate::defer::Defer;
+use crate::ffi;
+use crate::geom::{Depth, Geom, GridToWorld};
+use crate::level;
+use crate::math::{Mat4, Vec2, Vec4};
+use crate::pathfinding::{self, Options};
+use crate::tracked;
+use crate::{GlState, QUAD_VERTICES};
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::ptr;
+
+// NOTE: The offscreen target is tiny; only its center pixel is checked.
+const TARGET_SIZE: c_int = 8;
+const TARGET_COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 0.0, z: 1.0, w: 1.0 };
+
+pub const IDENTITY: Mat4<f32> = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+// NOTE: Unlike the main error callback this one only logs, so a broken platform is reported as a
+// failed check rather than an abort.
+extern "C" fn callback_glfw_error(error_code: c_int, description: *const c_char) {
+    if description.is_null() {
+        eprintln!("glfw error {error_code}");
+    } else {
+        eprintln!(
+            "glfw error {error_code}: {}",
+            unsafe { CStr::from_ptr(description) }.to_string_lossy()
+        );
+    }
+}
+
+// NOTE: Runs `f` (unless a subsystem it depends on already failed) and prints its outcome; any
+// panic inside `f` counts as a failure.
+fn check<T>(name: &str, ready: bool, f: impl FnOnce() -> T) -> Option<T> {
+    let result = if ready {
+        panic::catch_unwind(AssertUnwindSafe(f)).ok()
+    } else {
+        None
+    };
+    let status = match (ready, &result) {
+        (false, _) => "SKIP",
+        (true, Some(_)) => "PASS",
+        (true, None) => "FAIL",
+    };
+    println!("{status} {name}");
+    result
+}
+
+fn compile(r#type: ffi::GLenum, path: &Path) -> ffi::GLuint {
+    let shader = crate::compile_shader(r#type, &assets::read_to_string(path));
+    let mut status = 0;
+    unsafe {
+        ffi::glGetShaderiv(shader, ffi::GL_COMPILE_STATUS, &mut status);
+    }
+    if status == 0 {
+        let mut log = vec![0_u8; 1024];
+        let mut length = 0;
+        unsafe {
+            ffi::glGetShaderInfoLog(
+                shader,
+                log.len().try_into().unwrap(),
+                &mut length,
+                log.as_mut_ptr().cast::<ffi::GLchar>(),
+            );
+        }
+        log.truncate(length.try_into().unwrap());
+        panic!("{}: {}", path.display(), String::from_utf8_lossy(&log));
+    }
+    shader
+}
+
+fn render(program: ffi::GLuint) {
+    let mut framebuffer = 0;
+    let mut renderbuffer = 0;
+    let mut vao: [ffi::GLuint; 1] = [0; 1];
+    let mut vbo: [ffi::GLuint; 2] = [0; 2];
+    unsafe {
+        ffi::glGenFramebuffers(1, &mut framebuffer);
+        ffi::glGenRenderbuffers(1, &mut renderbuffer);
+    }
+    tracked::gen_vertex_arrays(&mut vao);
+    tracked::gen_buffers(&mut vbo);
+    defer!({
+        unsafe {
+            ffi::glBindFramebuffer(ffi::GL_FRAMEBUFFER, 0);
+        }
+        tracked::delete_buffers(&vbo);
+        tracked::delete_vertex_arrays(&vao);
+        unsafe {
+            ffi::glDeleteRenderbuffers(1, &renderbuffer);
+            ffi::glDeleteFramebuffers(1, &framebuffer);
+        }
+    });
+
+    unsafe {
+        ffi::glBindRenderbuffer(ffi::GL_RENDERBUFFER, renderbuffer);
+        ffi::glRenderbufferStorage(ffi::GL_RENDERBUFFER, ffi::GL_RGBA8, TARGET_SIZE, TARGET_SIZE);
+        ffi::glBindFramebuffer(ffi::GL_FRAMEBUFFER, framebuffer);
+        ffi::glFramebufferRenderbuffer(
+            ffi::GL_FRAMEBUFFER,
+            ffi::GL_COLOR_ATTACHMENT0,
+            ffi::GL_RENDERBUFFER,
+            renderbuffer,
+        );
+        assert!(ffi::glCheckFramebufferStatus(ffi::GL_FRAMEBUFFER) == ffi::GL_FRAMEBUFFER_COMPLETE);
+
+        ffi::glViewport(0, 0, TARGET_SIZE, TARGET_SIZE);
+        ffi::glClearColor(0.0, 0.0, 0.0, 1.0);
+        ffi::glClear(ffi::GL_COLOR_BUFFER_BIT);
+    }
+
+    // NOTE: A single quad covering the whole of clip space.
+    let quads = [Geom {
+        translate: Vec2::default().into(),
+        scale: Vec2::from(2.0).into(),
+        color: TARGET_COLOR.into(),
+        depth: Depth::default(),
+    }];
+    let mut gl_state = GlState::default();
+    gl_state.use_program(program);
+    crate::buffers_and_attributes(
+        &mut gl_state,
+        program,
+        vao[0],
+        vbo[0],
+        vbo[1],
+        &quads,
+        &QUAD_VERTICES,
+    );
+    unsafe {
+        for name in [c"view", c"projection"] {
+            ffi::glUniformMatrix4fv(
+                ffi::glGetUniformLocation(program, name.as_ptr()),
+                1,
+                ffi::GL_FALSE,
+                IDENTITY.as_ptr().cast::<ffi::GLfloat>(),
+            );
+        }
+    }
+    crate::bind_and_draw(
+        &mut gl_state,
+        vao[0],
+        vbo[1],
+        &quads,
+        &QUAD_VERTICES,
+        ffi::GL_TRIANGLE_STRIP,
+    );
+
+    let mut pixel = [0_u8; 3];
+    unsafe {
+        ffi::glReadPixels(
+            TARGET_SIZE / 2,
+            TARGET_SIZE / 2,
+            1,
+            1,
+            ffi::GL_RGB,
+            ffi::GL_UNSIGNED_BYTE,
+            pixel.as_mut_ptr().cast(),
+        );
+    }
+    assert!(pixel == [255, 0, 255], "read back {pixel:?}");
+}
+
+// NOTE: Initializes GLFW and makes an invisible window's context current. `glfwTerminate` cleans
+// up after it.
+pub fn hidden_context() {
+    unsafe {
+        assert!(ffi::glfwInit() == 1);
+        ffi::glfwWindowHint(ffi::GLFW_CONTEXT_VERSION_MAJOR, 3);
+        ffi::glfwWindowHint(ffi::GLFW_CONTEXT_VERSION_MINOR, 3);
+        ffi::glfwWindowHint(ffi::GLFW_OPENGL_PROFILE, ffi::GLFW_OPENGL_CORE_PROFILE);
+        ffi::glfwWindowHint(ffi::GLFW_VISIBLE, 0);
+        let window = ffi::glfwCreateWindow(
+            TARGET_SIZE,
+            TARGET_SIZE,
+            CString::new(std::module_path!())
+                .unwrap()
+                .as_bytes_with_nul()
+                .as_ptr()
+                .cast::<c_char>(),
+            ptr::null_mut::<ffi::GLFWmonitor>(),
+            ptr::null_mut::<ffi::GLFWwindow>(),
+        );
+        assert!(!window.is_null());
+        ffi::glfwMakeContextCurrent(window);
+    }
+}
+
+// NOTE: Compiles and links the shaders under `src`, panicking with the compile log on failure.
+pub fn program() -> ffi::GLuint {
+    let vert_shader = compile(ffi::GL_VERTEX_SHADER, &Path::new("src").join("vert.glsl"));
+    let frag_shader = compile(ffi::GL_FRAGMENT_SHADER, &Path::new("src").join("frag.glsl"));
+    let mut status = 0;
+    unsafe {
+        let program = ffi::glCreateProgram();
+        ffi::glAttachShader(program, vert_shader);
+        ffi::glAttachShader(program, frag_shader);
+        ffi::glLinkProgram(program);
+        ffi::glDeleteShader(vert_shader);
+        ffi::glDeleteShader(frag_shader);
+        ffi::glGetProgramiv(program, ffi::GL_LINK_STATUS, &mut status);
+        assert!(status != 0);
+        program
+    }
+}
+
+// NOTE: Checks each subsystem in turn and prints `PASS`, `FAIL` or `SKIP` (when a subsystem it
+// needs failed) per line; returns whether everything passed.
+pub fn run(floor_plan_path: &Path, scale: f32) -> bool {
+    unsafe {
+        ffi::glfwSetErrorCallback(callback_glfw_error);
+    }
+    // NOTE: `glfwTerminate` is safe to call even if `glfwInit` failed, and destroys the window.
+    defer!(unsafe {
+        ffi::glfwTerminate();
+    });
+
+    let window = check("gl_context", true, hidden_context);
+    let program = check("shader_compile", window.is_some(), program);
+
+    let level = check("map_parse", true, || {
+        let level = level::parse(&assets::read(floor_plan_path), false).unwrap();
+        assert!(!level.waypoints.is_empty());
+        level
+    });
+
+    let path = check("path_query", level.is_some(), || {
+        let level = level.as_ref().unwrap();
+        let graph = level::graph(level, GridToWorld::new(level.bounds, scale), &level::Standard);
+        let result =
+            pathfinding::shortest_path(&graph, 0, graph.nodes.len() - 1, Options::default());
+        assert!(result.cost.is_finite());
+    });
+
+    let render = check("offscreen_render", program.is_some(), || render(program.unwrap()));
+
+    if let Some(program) = program {
+        unsafe {
+            ffi::glDeleteProgram(program);
+        }
+    }
+    [
+        window.is_some(),
+        program.is_some(),
+        level.is_some(),
+        path.is_some(),
+        render.is_some(),
+    ]
+    .into_iter()
+    .all(|passed| passed)
+}
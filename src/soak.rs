@@ -0,0 +1,215 @@
+use crate::agent::{self, Agent};
+use crate::defer;
+use crate::defer::Defer;
+use crate::ffi;
+use crate::geom::{Depth, Geom, GridToWorld};
+use crate::level;
+use crate::mapgen::{self, Generator, Rng};
+use crate::math::{Vec2, Vec4};
+use crate::pathfinding::{Options, Path, PathCache};
+use crate::pool::{Handle, Pool};
+use crate::self_test;
+use crate::tracked;
+use crate::{GlState, QUAD_VERTICES};
+use std::fs;
+use std::time;
+
+const SEED: u64 = 0x50A4;
+const MAP_MIN: usize = 16;
+const MAP_MAX: usize = 48;
+const QUERIES: usize = 64;
+const AGENTS: usize = 32;
+const TICKS: usize = 240;
+// NOTE: Chance (out of 16) of an agent spawning or despawning on each tick.
+const CHURN: usize = 3;
+const CHECK_INTERVAL: time::Duration = time::Duration::from_secs(10);
+// NOTE: Resident memory may grow this much past the first checkpoint (allocator slack, the first
+// large map) before it counts as a leak.
+const RSS_SLACK: f64 = 1.25;
+const COLOR: Vec4<f32> = Vec4 { x: 1.0, y: 1.0, z: 1.0, w: 1.0 };
+
+struct Walker {
+    agent: Agent,
+    path: Path,
+    goal: usize,
+}
+
+// NOTE: Linux only; `None` elsewhere, which skips the memory check.
+fn rss_kb() -> Option<usize> {
+    let status = fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+// NOTE: One map's worth of work: generate and parse it, draw its waypoints through freshly made
+// buffers, run random queries, and churn agents along the answers. Everything is dropped (and every
+// GL object deleted) on return. Returns the path cache's peak memory, in bytes.
+#[allow(clippy::cast_possible_truncation)]
+fn round(rng: &mut Rng, program: ffi::GLuint) -> usize {
+    let generator = Generator::ALL[rng.range(0, Generator::ALL.len())];
+    let size = Vec2 {
+        x: rng.range(MAP_MIN, MAP_MAX) as u16,
+        y: rng.range(MAP_MIN, MAP_MAX) as u16,
+    };
+    let level = level::parse(&mapgen::generate(generator, size, rng.next()), false).unwrap();
+    let grid = GridToWorld::new(level.bounds, crate::FLOOR_SCALE);
+    let graph = level::graph(&level, grid, &level::Standard);
+    let n = graph.nodes.len();
+
+    let mut vao: [ffi::GLuint; 1] = [0; 1];
+    let mut vbo: [ffi::GLuint; 2] = [0; 2];
+    tracked::gen_vertex_arrays(&mut vao);
+    tracked::gen_buffers(&mut vbo);
+    defer!({
+        tracked::delete_buffers(&vbo);
+        tracked::delete_vertex_arrays(&vao);
+    });
+    let quads: Vec<Geom<f32>> = graph
+        .nodes
+        .iter()
+        .map(|node| Geom {
+            translate: (*node).into(),
+            scale: grid.scale.into(),
+            color: COLOR.into(),
+            depth: Depth::default(),
+        })
+        .collect();
+    let mut gl_state = GlState::default();
+    gl_state.use_program(program);
+    crate::buffers_and_attributes(
+        &mut gl_state,
+        program,
+        vao[0],
+        vbo[0],
+        vbo[1],
+        &quads,
+        &QUAD_VERTICES,
+    );
+    unsafe {
+        ffi::glClear(ffi::GL_COLOR_BUFFER_BIT);
+    }
+    crate::bind_and_draw(
+        &mut gl_state,
+        vao[0],
+        vbo[1],
+        &quads,
+        &QUAD_VERTICES,
+        ffi::GL_TRIANGLE_STRIP,
+    );
+
+    let mut cache = PathCache::default();
+    for _ in 0..QUERIES {
+        cache.shortest_path(&graph, rng.range(0, n), rng.range(0, n), Options::default());
+    }
+    let mut peak = cache.memory();
+
+    let mut walkers: Pool<Walker> = Pool::with_capacity(AGENTS);
+    let mut handles: Vec<Handle> = vec![];
+    for _ in 0..TICKS {
+        if rng.range(0, 16) < CHURN {
+            let (node, goal) = (rng.range(0, n), rng.range(0, n));
+            let walker = Walker {
+                agent: Agent::new(
+                    graph.nodes[node],
+                    node,
+                    crate::PLAYER_ACCEL,
+                    crate::PLAYER_DRAG,
+                    grid.scale.x.abs() * crate::PLAYER_REACH,
+                ),
+                path: cache.shortest_path(&graph, node, goal, Options::default()).nodes,
+                goal,
+            };
+            if let Some(handle) = walkers.spawn(walker) {
+                handles.push(handle);
+            }
+        }
+        if !handles.is_empty() && (rng.range(0, 16) < CHURN) {
+            let handle = handles.swap_remove(rng.range(0, handles.len()));
+            walkers.despawn(handle);
+        }
+        for walker in walkers.as_mut_slice() {
+            walker
+                .agent
+                .update(&graph.nodes, &walker.path, walker.goal, |_, _| true, |_| ());
+            if walker.agent.state == agent::State::Arrived {
+                walker.goal = rng.range(0, n);
+                walker.path = cache
+                    .shortest_path(&graph, walker.agent.node, walker.goal, Options::default())
+                    .nodes;
+            }
+        }
+        peak = peak.max(cache.memory());
+    }
+    peak
+}
+
+// NOTE: Runs rounds (see `round`) back to back for `minutes`, printing a checkpoint every
+// `CHECK_INTERVAL`. Fails if any round leaves GL objects behind, or if resident memory at the end
+// is more than `RSS_SLACK` times what it was at the first checkpoint. Rounds are seeded from
+// `SEED`, so a failing run can be replayed.
+pub fn run(minutes: f64) -> bool {
+    defer!(unsafe {
+        ffi::glfwTerminate();
+    });
+    self_test::hidden_context();
+    let program = self_test::program();
+    defer!(unsafe {
+        ffi::glDeleteProgram(program);
+    });
+
+    let mut rng = Rng(SEED);
+    let start = time::Instant::now();
+    let deadline = time::Duration::from_secs_f64(minutes * 60.0);
+    let mut checked = start;
+    let mut first_rss = None;
+    let mut last_rss = None;
+    let mut leaks = 0;
+    let mut rounds: u64 = 0;
+    let mut peak_cache = 0;
+    println!(
+        "{:>8} {:>8} {:>10} {:>6} {:>8} {:>12}",
+        "seconds", "rounds", "rss_kb", "vaos", "buffers", "cache_bytes"
+    );
+    while start.elapsed() < deadline {
+        let live = tracked::live();
+        peak_cache = peak_cache.max(round(&mut rng, program));
+        rounds += 1;
+        if tracked::live() != live {
+            leaks += 1;
+            println!(
+                "round {rounds} left GL objects behind: {live:?} before, {:?} after",
+                tracked::live()
+            );
+        }
+        if CHECK_INTERVAL <= checked.elapsed() {
+            checked = time::Instant::now();
+            last_rss = rss_kb();
+            first_rss = first_rss.or(last_rss);
+            let live = tracked::live();
+            println!(
+                "{:8} {rounds:8} {:>10} {:6} {:8} {peak_cache:12}",
+                start.elapsed().as_secs(),
+                last_rss.map_or_else(|| "?".to_owned(), |rss| rss.to_string()),
+                live.vertex_arrays,
+                live.buffers,
+            );
+            peak_cache = 0;
+        }
+    }
+
+    let gl_passed = leaks == 0;
+    println!(
+        "{} gl_objects ({leaks} leaking rounds)",
+        if gl_passed { "PASS" } else { "FAIL" }
+    );
+    #[allow(clippy::cast_precision_loss)]
+    let rss_passed = if let (Some(first), Some(last)) = (first_rss, last_rss) {
+        let passed = (last as f64) <= (first as f64 * RSS_SLACK);
+        println!("{} rss ({first} kB -> {last} kB)", if passed { "PASS" } else { "FAIL" });
+        passed
+    } else {
+        println!("SKIP rss");
+        true
+    };
+    gl_passed && rss_passed
+}
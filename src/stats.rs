@@ -0,0 +1,172 @@
+use std::fmt::Write;
+
+// NOTE: Running totals for one algorithm. Cache hits count as queries but not as searches, so
+// expansion figures only cover searches that actually ran.
+#[derive(Default)]
+pub struct Aggregate {
+    pub queries: usize,
+    pub hits: usize,
+    expansions: Vec<usize>,
+    cost: f64,
+    costed: usize,
+}
+
+#[allow(clippy::cast_precision_loss)]
+impl Aggregate {
+    pub fn mean_expansions(&self) -> f64 {
+        if self.expansions.is_empty() {
+            return 0.0;
+        }
+        self.expansions.iter().sum::<usize>() as f64 / self.expansions.len() as f64
+    }
+
+    // NOTE: Nearest-rank percentile, `p` in `[0, 100]`.
+    pub fn percentile_expansions(&self, p: usize) -> usize {
+        if self.expansions.is_empty() {
+            return 0;
+        }
+        let mut expansions = self.expansions.clone();
+        expansions.sort_unstable();
+        let rank = (p * expansions.len()).div_ceil(100);
+        expansions[rank.clamp(1, expansions.len()) - 1]
+    }
+
+    pub fn mean_cost(&self) -> f64 {
+        if self.costed == 0 {
+            return 0.0;
+        }
+        self.cost / self.costed as f64
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        if self.queries == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / self.queries as f64
+    }
+}
+
+// NOTE: Aggregates are kept in the order algorithms were first seen.
+#[derive(Default)]
+pub struct Stats {
+    pub algorithms: Vec<(&'static str, Aggregate)>,
+}
+
+impl Stats {
+    // NOTE: Unreachable goals (infinite cost) count as queries but stay out of the mean cost.
+    pub fn record(&mut self, algorithm: &'static str, hit: bool, expanded: usize, cost: f32) {
+        let k =
+            self.algorithms
+                .iter()
+                .position(|(name, _)| *name == algorithm)
+                .unwrap_or_else(|| {
+                    self.algorithms.push((algorithm, Aggregate::default()));
+                    self.algorithms.len() - 1
+                });
+        let aggregate = &mut self.algorithms[k].1;
+        aggregate.queries += 1;
+        if hit {
+            aggregate.hits += 1;
+        } else {
+            aggregate.expansions.push(expanded);
+        }
+        if cost.is_finite() {
+            aggregate.cost += f64::from(cost);
+            aggregate.costed += 1;
+        }
+    }
+
+    pub fn screen(&self, out: &mut String) {
+        writeln!(
+            out,
+            "{:>12} {:>10} {:>10} {:>8} {:>8} {:>10} {:>8}",
+            "algorithm", "queries", "mean_exp", "p50_exp", "p95_exp", "mean_cost", "hit_rate",
+        )
+        .unwrap();
+        for (name, aggregate) in &self.algorithms {
+            writeln!(
+                out,
+                "{name:>12} {:10} {:10.1} {:8} {:8} {:10.2} {:8.3}",
+                aggregate.queries,
+                aggregate.mean_expansions(),
+                aggregate.percentile_expansions(50),
+                aggregate.percentile_expansions(95),
+                aggregate.mean_cost(),
+                aggregate.hit_rate(),
+            )
+            .unwrap();
+        }
+    }
+
+    pub fn csv(&self) -> String {
+        let mut csv = String::from(
+            "algorithm,queries,mean_expansions,p50_expansions,p95_expansions,mean_cost,hit_rate\n",
+        );
+        for (name, aggregate) in &self.algorithms {
+            writeln!(
+                csv,
+                "{name},{},{:.2},{},{},{:.2},{:.4}",
+                aggregate.queries,
+                aggregate.mean_expansions(),
+                aggregate.percentile_expansions(50),
+                aggregate.percentile_expansions(95),
+                aggregate.mean_cost(),
+                aggregate.hit_rate(),
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    // NOTE: Algorithm names are plain identifiers, so nothing needs escaping.
+    pub fn json(&self) -> String {
+        let mut json = String::from("[\n");
+        for (k, (name, aggregate)) in self.algorithms.iter().enumerate() {
+            writeln!(
+                json,
+                "  {{\"algorithm\": \"{name}\", \"queries\": {}, \"mean_expansions\": {:.2}, \
+                 \"p50_expansions\": {}, \"p95_expansions\": {}, \"mean_cost\": {:.2}, \
+                 \"hit_rate\": {:.4}}}{}",
+                aggregate.queries,
+                aggregate.mean_expansions(),
+                aggregate.percentile_expansions(50),
+                aggregate.percentile_expansions(95),
+                aggregate.mean_cost(),
+                aggregate.hit_rate(),
+                if k + 1 < self.algorithms.len() {
+                    ","
+                } else {
+                    ""
+                },
+            )
+            .unwrap();
+        }
+        json.push_str("]\n");
+        json
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let mut stats = Stats::default();
+        for expanded in 1..=20 {
+            stats.record("octile", false, expanded, 2.0);
+        }
+        stats.record("octile", true, 0, 4.0);
+        stats.record("zero", false, 7, f32::INFINITY);
+
+        let octile = &stats.algorithms[0].1;
+        assert!(octile.queries == 21);
+        assert!((octile.percentile_expansions(50), octile.percentile_expansions(95)) == (10, 19));
+        assert!((octile.mean_expansions() - 10.5).abs() < f64::EPSILON);
+        assert!((octile.hit_rate() - (1.0 / 21.0)).abs() < f64::EPSILON);
+        assert!(stats.algorithms[1].1.mean_cost().abs() < f64::EPSILON);
+
+        assert!(stats.csv().lines().count() == 3);
+        assert!(stats.json().contains("\"algorithm\": \"zero\", \"queries\": 1,"));
+    }
+}
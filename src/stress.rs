@@ -0,0 +1,214 @@
+use crate::ffi;
+use crate::geom::{Depth, Geom};
+use crate::input::Input;
+use crate::math::{Vec2, Vec4};
+use crate::self_test::IDENTITY;
+use crate::{Gl, GlState, QUAD_VERTICES};
+use std::fmt::Write;
+use std::mem;
+use std::time;
+
+const MIN_QUADS: usize = 1 << 10;
+const MAX_QUADS: usize = 1 << 20;
+const START_QUADS: usize = 1 << 16;
+// NOTE: Share of each grid cell its quad covers, so neighbours stay apart.
+const FILL: f32 = 0.8;
+
+// NOTE: Totals over every frame drawn at one quad count, in seconds.
+#[derive(Default)]
+struct Sample {
+    frames: u32,
+    submit: f64,
+    finish: f64,
+}
+
+impl Sample {
+    fn submit_ms(&self) -> f64 {
+        (self.submit * 1000.0) / f64::from(self.frames)
+    }
+
+    fn finish_ms(&self) -> f64 {
+        (self.finish * 1000.0) / f64::from(self.frames)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn quads_per_second(&self, n: usize) -> f64 {
+        (n as f64 * f64::from(self.frames)) / self.finish
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn upload_mb_per_second(&self, n: usize) -> f64 {
+        (mem::size_of::<Geom<f32>>() as f64 * n as f64 * f64::from(self.frames))
+            / (self.submit * 1_000_000.0)
+    }
+}
+
+// NOTE: `n` quads in a square grid filling clip space, shaded by position.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss
+)]
+fn grid(n: usize) -> Vec<Geom<f32>> {
+    let side = (n as f64).sqrt().ceil() as usize;
+    let cell = 2.0 / side as f32;
+    (0..n)
+        .map(|i| {
+            let (x, y) = ((i % side) as f32, (i / side) as f32);
+            Geom {
+                translate: Vec2 {
+                    x: (cell * (x + 0.5)) - 1.0,
+                    y: (cell * (y + 0.5)) - 1.0,
+                }
+                .into(),
+                scale: Vec2::from(cell * FILL).into(),
+                color: Vec4 {
+                    x: x / side as f32,
+                    y: y / side as f32,
+                    z: 0.75,
+                    w: 1.0,
+                }
+                .into(),
+                depth: Depth::default(),
+            }
+        })
+        .collect()
+}
+
+// NOTE: Draws a static grid of quads through the same instancing path as the main scene, uploading
+// the whole instance buffer every frame, with vsync off. `=` and `-` double and halve the count
+// (between `MIN_QUADS` and `MAX_QUADS`). The HUD shows the last second: `submit` covers the upload
+// and draw call, `finish` runs until the GPU is done. Totals per count are printed on exit.
+pub fn run(window: *mut ffi::GLFWwindow, input: &mut Input, gl: Gl) {
+    let mut n = START_QUADS;
+    let mut quads = grid(n);
+    let mut gl_state = GlState::default();
+    gl_state.use_program(gl.program);
+    gl_state.blend(true);
+    unsafe {
+        ffi::glfwSwapInterval(0);
+        ffi::glClearColor(0.0, 0.0, 0.0, 1.0);
+        for name in [c"view", c"projection"] {
+            ffi::glUniformMatrix4fv(
+                ffi::glGetUniformLocation(gl.program, name.as_ptr()),
+                1,
+                ffi::GL_FALSE,
+                IDENTITY.as_ptr().cast::<ffi::GLfloat>(),
+            );
+        }
+    }
+    let upload = |gl_state: &mut GlState, quads: &[Geom<f32>]| {
+        crate::buffers_and_attributes(
+            gl_state,
+            gl.program,
+            gl.vao[0],
+            gl.vbo[0],
+            gl.instance_vbo[0],
+            quads,
+            &QUAD_VERTICES,
+        );
+    };
+    upload(&mut gl_state, &quads);
+
+    let mut totals: Vec<(usize, Sample)> = vec![];
+    let mut second = Sample::default();
+    let mut now = time::Instant::now();
+    let mut hud_lines = 0;
+    while unsafe { ffi::glfwWindowShouldClose(window) } != 1 {
+        unsafe {
+            ffi::glfwPollEvents();
+            input.update(ffi::glfwGetTime());
+            if let Some(size) = input.resized.take() {
+                ffi::glViewport(0, 0, size.x, size.y);
+            }
+        }
+        let resized = if input.tapped(ffi::GLFW_KEY_EQUAL) {
+            (n * 2).min(MAX_QUADS)
+        } else if input.tapped(ffi::GLFW_KEY_MINUS) {
+            (n / 2).max(MIN_QUADS)
+        } else {
+            n
+        };
+        if resized != n {
+            n = resized;
+            quads = grid(n);
+            upload(&mut gl_state, &quads);
+            second = Sample::default();
+            now = time::Instant::now();
+        }
+
+        let start = time::Instant::now();
+        unsafe {
+            ffi::glClear(ffi::GL_COLOR_BUFFER_BIT);
+        }
+        crate::bind_and_draw(
+            &mut gl_state,
+            gl.vao[0],
+            gl.instance_vbo[0],
+            &quads,
+            &QUAD_VERTICES,
+            ffi::GL_TRIANGLE_STRIP,
+        );
+        let submit = start.elapsed().as_secs_f64();
+        unsafe {
+            ffi::glFinish();
+        }
+        let finish = start.elapsed().as_secs_f64();
+        unsafe {
+            ffi::glfwSwapBuffers(window);
+        }
+
+        second.frames += 1;
+        second.submit += submit;
+        second.finish += finish;
+        let k = totals.iter().position(|(other, _)| *other == n).unwrap_or_else(|| {
+            totals.push((n, Sample::default()));
+            totals.len() - 1
+        });
+        totals[k].1.frames += 1;
+        totals[k].1.submit += submit;
+        totals[k].1.finish += finish;
+
+        if 0 < now.elapsed().as_secs() {
+            let mut hud = String::new();
+            write!(
+                hud,
+                "{n:12} quads\n\
+                 {:12} frames\n\
+                 {:12.3} submit_ms / frame\n\
+                 {:12.3} finish_ms / frame\n\
+                 {:12.0} quads / s\n\
+                 {:12.1} upload MB / s\n",
+                second.frames,
+                second.submit_ms(),
+                second.finish_ms(),
+                second.quads_per_second(n),
+                second.upload_mb_per_second(n),
+            )
+            .unwrap();
+            if 0 < hud_lines {
+                print!("\x1B[{hud_lines}A");
+            }
+            print!("\x1B[J{hud}");
+            hud_lines = hud.lines().count();
+            second = Sample::default();
+            now = time::Instant::now();
+        }
+    }
+
+    totals.sort_unstable_by_key(|(n, _)| *n);
+    println!(
+        "{:>12} {:>8} {:>10} {:>10} {:>14} {:>10}",
+        "quads", "frames", "submit_ms", "finish_ms", "quads/s", "MB/s"
+    );
+    for (n, total) in &totals {
+        println!(
+            "{n:12} {:8} {:10.3} {:10.3} {:14.0} {:10.1}",
+            total.frames,
+            total.submit_ms(),
+            total.finish_ms(),
+            total.quads_per_second(*n),
+            total.upload_mb_per_second(*n),
+        );
+    }
+}
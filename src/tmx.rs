@@ -0,0 +1,176 @@
+use crate::level::{self, Error};
+use std::collections::HashMap;
+
+// NOTE: Imports the first tile layer of an orthogonal Tiled map as a floor plan, which then goes
+// through `level::parse` like any other. Tiles turn into glyphs through the map's `glyphs` string
+// property, e.g. `0:+ 1:. 4:~ 7:D`, keyed by tile id within the map's first tileset; empty cells
+// are open floor (` `). Only CSV layer data is read.
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+struct Tag<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, &'a str)>,
+    offset: usize,
+    end: usize,
+}
+
+fn fail(text: &str, offset: usize, message: &str) -> Error {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before[(before.rfind('\n').map_or(0, |i| i + 1))..].chars().count() + 1;
+    Error::Import {
+        format: "TMX",
+        message: format!("{message} at line {line}, column {column}"),
+    }
+}
+
+// NOTE: Opening (and self-closing) tags in document order; closing tags, comments and the
+// declaration are skipped.
+fn tags(text: &str) -> Result<Vec<Tag<'_>>, Error> {
+    let mut tags = vec![];
+    for (offset, _) in text.match_indices('<') {
+        let rest = &text[(offset + 1)..];
+        if rest.starts_with(['/', '?', '!']) {
+            continue;
+        }
+        let close = rest.find('>').ok_or_else(|| fail(text, offset, "unterminated tag"))?;
+        let tag = rest[..close].trim_end_matches('/');
+        let (name, rest) = tag.split_at(tag.find(char::is_whitespace).unwrap_or(tag.len()));
+        let mut attributes = vec![];
+        let mut rest = rest.trim_start();
+        while !rest.is_empty() {
+            let (key, value) = rest
+                .split_once("=\"")
+                .and_then(|(key, rest)| Some((key.trim(), rest.split_once('"')?)))
+                .ok_or_else(|| fail(text, offset, "malformed attributes"))?;
+            attributes.push((key, value.0));
+            rest = value.1.trim_start();
+        }
+        tags.push(Tag {
+            name,
+            attributes,
+            offset,
+            end: offset + close + 2,
+        });
+    }
+    Ok(tags)
+}
+
+impl<'a> Tag<'a> {
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes.iter().find(|(key, _)| *key == name).map(|(_, value)| *value)
+    }
+
+    fn required<T: std::str::FromStr>(&self, text: &str, name: &str) -> Result<T, Error> {
+        self.attribute(name)
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| fail(text, self.offset, &format!("expected a valid {name:?}")))
+    }
+}
+
+pub fn import(text: &str) -> Result<Vec<u8>, Error> {
+    let tags = tags(text)?;
+    let find = |name: &str| tags.iter().find(|tag| tag.name == name);
+    let map = find("map").ok_or_else(|| fail(text, 0, "expected a <map>"))?;
+    if map.attribute("orientation") != Some("orthogonal") {
+        return Err(fail(text, map.offset, "expected an orthogonal map"));
+    }
+    let width: usize = map.required(text, "width")?;
+    let height: usize = map.required(text, "height")?;
+    let first_gid: u32 =
+        find("tileset").map_or(Ok(1), |tileset| tileset.required(text, "firstgid"))?;
+
+    let property = tags
+        .iter()
+        .find(|tag| (tag.name == "property") && (tag.attribute("name") == Some("glyphs")))
+        .ok_or_else(|| fail(text, map.offset, "expected a \"glyphs\" map property"))?;
+    let mut glyphs = HashMap::new();
+    for entry in property.attribute("value").unwrap_or_default().split_whitespace() {
+        let (id, glyph) = entry
+            .split_once(':')
+            .and_then(|(id, glyph)| {
+                let mut chars = glyph.chars();
+                Some((id.parse::<u32>().ok()?, chars.next().filter(|_| chars.next().is_none())?))
+            })
+            .filter(|(_, glyph)| level::glyph(*glyph).is_some())
+            .ok_or_else(|| fail(text, property.offset, &format!("invalid glyph {entry:?}")))?;
+        glyphs.insert(id, glyph);
+    }
+
+    let data = find("data").ok_or_else(|| fail(text, map.offset, "expected a tile layer"))?;
+    if (data.attribute("encoding") != Some("csv")) || data.attribute("compression").is_some() {
+        return Err(fail(text, data.offset, "only CSV layer data is supported"));
+    }
+    let body = &text[data.end..];
+    let body = &body[..body
+        .find("</data>")
+        .ok_or_else(|| fail(text, data.offset, "unterminated <data>"))?];
+
+    let mut floor_plan = String::with_capacity((width + 1) * height);
+    let mut cells = 0;
+    let mut offset = data.end;
+    for field in body.split(',') {
+        let id = field.trim();
+        let start = offset + (field.len() - field.trim_start().len());
+        offset += field.len() + 1;
+        if id.is_empty() && (cells == width * height) {
+            continue;
+        }
+        let gid: u32 = id.parse().map_err(|_| fail(text, start, "expected a tile id"))?;
+        let gid = gid & !FLIP_FLAGS;
+        let glyph = if gid == 0 {
+            ' '
+        } else {
+            *gid.checked_sub(first_gid)
+                .and_then(|id| glyphs.get(&id))
+                .ok_or_else(|| fail(text, start, &format!("tile {gid} has no glyph")))?
+        };
+        if cells == width * height {
+            return Err(fail(text, start, "more tiles than the map holds"));
+        }
+        floor_plan.push(glyph);
+        cells += 1;
+        if cells % width == 0 {
+            floor_plan.push('\n');
+        }
+    }
+    if cells != width * height {
+        let message = format!("expected {} tiles, found {cells}", width * height);
+        return Err(fail(text, data.offset, &message));
+    }
+    Ok(floor_plan.into_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.10" orientation="orthogonal" width="4" height="3" tilewidth="16" tileheight="16">
+ <properties>
+  <property name="glyphs" value="0:+ 1:. 2:~"/>
+ </properties>
+ <tileset firstgid="1" source="tiles.tsx"/>
+ <layer id="1" name="floor" width="4" height="3">
+  <data encoding="csv">
+1,1,1,1,
+1,2,0,2147483651,
+1,1,1,1
+</data>
+ </layer>
+</map>
+"#;
+
+    #[test]
+    fn test_import() {
+        assert!(import(MAP).unwrap() == b"++++\n+. ~\n++++\n");
+        let level = level::parse(&import(MAP).unwrap(), false).unwrap();
+        assert!(level.waypoints.len() == 2);
+    }
+
+    #[test]
+    fn test_import_error() {
+        let error = import(&MAP.replace("1,1,1,1\n</data>", "1,1,1,9\n</data>")).err().unwrap();
+        assert!(error.to_string() == "TMX: tile 9 has no glyph at line 11, column 7");
+    }
+}
@@ -0,0 +1,49 @@
+use crate::ffi;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// NOTE: GL objects made through these wrappers and not yet deleted, so long runs can tell a leak
+// from a steady state (see `soak::run`). GL itself stays on the main thread; the counters are
+// atomic only because they are statics.
+static VERTEX_ARRAYS: AtomicUsize = AtomicUsize::new(0);
+static BUFFERS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Live {
+    pub vertex_arrays: usize,
+    pub buffers: usize,
+}
+
+pub fn live() -> Live {
+    Live {
+        vertex_arrays: VERTEX_ARRAYS.load(Ordering::Relaxed),
+        buffers: BUFFERS.load(Ordering::Relaxed),
+    }
+}
+
+pub fn gen_vertex_arrays(arrays: &mut [ffi::GLuint]) {
+    unsafe {
+        ffi::glGenVertexArrays(arrays.len().try_into().unwrap(), arrays.as_mut_ptr());
+    }
+    VERTEX_ARRAYS.fetch_add(arrays.len(), Ordering::Relaxed);
+}
+
+pub fn delete_vertex_arrays(arrays: &[ffi::GLuint]) {
+    unsafe {
+        ffi::glDeleteVertexArrays(arrays.len().try_into().unwrap(), arrays.as_ptr());
+    }
+    VERTEX_ARRAYS.fetch_sub(arrays.len(), Ordering::Relaxed);
+}
+
+pub fn gen_buffers(buffers: &mut [ffi::GLuint]) {
+    unsafe {
+        ffi::glGenBuffers(buffers.len().try_into().unwrap(), buffers.as_mut_ptr());
+    }
+    BUFFERS.fetch_add(buffers.len(), Ordering::Relaxed);
+}
+
+pub fn delete_buffers(buffers: &[ffi::GLuint]) {
+    unsafe {
+        ffi::glDeleteBuffers(buffers.len().try_into().unwrap(), buffers.as_ptr());
+    }
+    BUFFERS.fetch_sub(buffers.len(), Ordering::Relaxed);
+}
@@ -0,0 +1,147 @@
+use crate::geom::Geom;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Channel {
+    Alpha,
+    Scale,
+}
+
+impl Channel {
+    pub fn apply(self, geom: &mut Geom<f32>, value: f32) {
+        match self {
+            Self::Alpha => geom.color.0.w = value,
+            Self::Scale => geom.scale.0 = value.into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseOut,
+    // NOTE: Overshoots the end value by about 10% before settling, for spawn pops.
+    Pop,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseOut => t * (2.0 - t),
+            // NOTE: See `https://easings.net/#easeOutBack`.
+            Self::Pop => {
+                let c: f32 = 1.70158;
+                let u = t - 1.0;
+                (c + 1.0).mul_add(u.powi(3), c * u.powi(2)) + 1.0
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Tween<K> {
+    key: K,
+    channel: Channel,
+    from: f32,
+    to: f32,
+    frames: u32,
+    frame: u32,
+    easing: Easing,
+}
+
+// NOTE: Animates one channel of an instance from `from` to `to` over a number of frames. `K`
+// addresses instances (an index into a batch, or a pool handle); the caller writes each value
+// through `Channel::apply` since it owns the storage.
+pub struct Tweens<K> {
+    active: Vec<Tween<K>>,
+    finished: Vec<(K, Channel)>,
+}
+
+impl<K> Default for Tweens<K> {
+    fn default() -> Self {
+        Self { active: vec![], finished: vec![] }
+    }
+}
+
+impl<K: Copy + PartialEq> Tweens<K> {
+    // NOTE: Replaces any tween already running on the same instance and channel.
+    pub fn start(
+        &mut self,
+        key: K,
+        channel: Channel,
+        from: f32,
+        to: f32,
+        frames: u32,
+        easing: Easing,
+    ) {
+        self.active.retain(|tween| (tween.key, tween.channel) != (key, channel));
+        self.active.push(Tween {
+            key,
+            channel,
+            from,
+            to,
+            frames: frames.max(1),
+            frame: 0,
+            easing,
+        });
+    }
+
+    pub fn cancel(&mut self, key: K) {
+        self.active.retain(|tween| tween.key != key);
+    }
+
+    // NOTE: Advances every tween by a frame; those reaching their end value are dropped and listed
+    // in `finished` until the next call.
+    pub fn update<F: FnMut(K, Channel, f32)>(&mut self, mut apply: F) {
+        self.finished.clear();
+        let finished = &mut self.finished;
+        self.active.retain_mut(|tween| {
+            tween.frame += 1;
+            #[allow(clippy::cast_precision_loss)]
+            let t = tween.easing.apply((tween.frame as f32) / (tween.frames as f32));
+            apply(tween.key, tween.channel, (tween.to - tween.from).mul_add(t, tween.from));
+            if tween.frame < tween.frames {
+                return true;
+            }
+            finished.push((tween.key, tween.channel));
+            false
+        });
+    }
+
+    pub fn finished(&self) -> &[(K, Channel)] {
+        &self.finished
+    }
+
+    pub const fn len(&self) -> usize {
+        self.active.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tweens() {
+        let mut values = [0.0; 2];
+        let mut tweens = Tweens::default();
+        tweens.start(0, Channel::Alpha, 1.0, 0.0, 4, Easing::Linear);
+        tweens.start(1, Channel::Scale, 0.0, 2.0, 2, Easing::Pop);
+
+        tweens.update(|i, _, value| values[i] = value);
+        assert!(((values[0] - 0.75).abs() < 0.001) && (2.0 < values[1]));
+        tweens.update(|i, _, value| values[i] = value);
+        assert!((values[1] - 2.0).abs() < 0.001);
+        assert!(tweens.finished() == [(1, Channel::Scale)]);
+
+        // NOTE: Restarting a channel replaces its tween rather than stacking another.
+        tweens.start(0, Channel::Alpha, 1.0, 0.0, 4, Easing::EaseOut);
+        assert!(tweens.len() == 1);
+        tweens.update(|i, _, value| values[i] = value);
+        assert!((values[0] - 0.5625).abs() < 0.001);
+        assert!(tweens.finished().is_empty());
+
+        tweens.cancel(0);
+        assert!(tweens.len() == 0);
+    }
+}
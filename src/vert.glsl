@@ -4,6 +4,7 @@ layout(location = 0) in vec2 position;
 layout(location = 1) in vec2 translate;
 layout(location = 2) in vec2 scale;
 layout(location = 3) in vec4 color;
+layout(location = 4) in vec2 depth;
 
 uniform mat4 projection;
 uniform mat4 view;
@@ -11,6 +12,7 @@ uniform mat4 view;
 out vec4 color_vert;
 
 void main() {
-    gl_Position = projection * view * vec4((position * scale) + translate, 0.0, 1.0);
+    gl_Position = projection * view *
+                  vec4((position * scale) + translate, depth.x + (position.x * depth.y), 1.0);
     color_vert = color;
 }
@@ -0,0 +1,261 @@
+use crate::ffi;
+use crate::level::Level;
+use crate::math::{Distance, Vec2, Vec4};
+use crate::overlay::{DebugOverlay, DrawList, Frame};
+use crate::pathfinding::{self, Graph, Options, Path, PathResult};
+use std::cmp::Ordering;
+use std::ffi::c_int;
+
+const CORNER_SCALE: f32 = 5.0;
+const CORNER_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.85, z: 0.2, w: 0.9 };
+const EDGE_COLOR: Vec4<f32> = Vec4 { x: 0.95, y: 0.85, z: 0.2, w: 0.25 };
+
+// NOTE: An any-angle alternative to the waypoint graph. Its nodes are the waypoints that sit just
+// off a convex wall corner, joined wherever the straight line between them stays clear, so a
+// shortest path through it bends only at corners. Walls, closed doors and cells without a waypoint
+// block sight; one-way tiles, terrain costs and stairs are left out, which is why a query can
+// come back empty where the waypoint graph still has a path.
+pub struct Visibility {
+    pub graph: Graph<Vec2<f32>>,
+    // NOTE: The waypoint graph's node behind each corner.
+    pub waypoints: Vec<usize>,
+    // NOTE: The waypoint graph version this was built from.
+    pub version: u64,
+    solid: Vec<bool>,
+    // NOTE: The last query and its answer; the player asks again every frame.
+    last: Option<((usize, usize), PathResult)>,
+}
+
+fn solid_cells(level: &Level, graph: &Graph<Vec2<f32>>) -> Vec<bool> {
+    let n = graph.nodes.len();
+    let mut solid = vec![true; level.walls.len()];
+    for (cell, i) in &level.waypoint_indices {
+        solid[level.index(*cell)] = level.walls[level.index(*cell)]
+            || (level.doors.contains(cell) && (0..n).all(|j| graph.weight(*i, j) == f32::INFINITY));
+    }
+    solid
+}
+
+impl Visibility {
+    pub fn build(level: &Level, graph: &Graph<Vec2<f32>>) -> Self {
+        let solid = solid_cells(level, graph);
+        let blocked = |x: i32, y: i32| solid_at(level, &solid, x, y);
+        let mut waypoints = vec![];
+        for (i, cell) in level.waypoints.iter().enumerate() {
+            let (x, y) = (i32::from(cell.x), i32::from(cell.y));
+            if blocked(x, y) {
+                continue;
+            }
+            let corner = [(-1, -1), (1, -1), (-1, 1), (1, 1)].into_iter().any(|(dx, dy)| {
+                blocked(x + dx, y + dy) && !blocked(x + dx, y) && !blocked(x, y + dy)
+            });
+            if corner {
+                waypoints.push(i);
+            }
+        }
+
+        let mut visibility = Self {
+            graph: Graph::new(waypoints.iter().map(|i| graph.nodes[*i]).collect()),
+            waypoints,
+            version: graph.version,
+            solid,
+            last: None,
+        };
+        for a in 0..visibility.waypoints.len() {
+            for b in (a + 1)..visibility.waypoints.len() {
+                let (i, j) = (visibility.waypoints[a], visibility.waypoints[b]);
+                if visibility.clear(level, level.waypoints[i], level.waypoints[j]) {
+                    let weight = graph.nodes[i].distance(graph.nodes[j]);
+                    visibility.graph.set_weight(a, b, weight);
+                    visibility.graph.set_weight(b, a, weight);
+                }
+            }
+        }
+        visibility
+    }
+
+    // NOTE: Walks every cell the segment between the two cell centers passes through. Passing
+    // exactly through a cell corner needs both cells beside it open, so sight doesn't squeeze
+    // between two diagonal walls.
+    pub fn clear(&self, level: &Level, a: Vec2<u16>, b: Vec2<u16>) -> bool {
+        let blocked = |x: i32, y: i32| solid_at(level, &self.solid, x, y);
+        let (mut x, mut y) = (i32::from(a.x), i32::from(a.y));
+        let (dx, dy) = (i32::from(b.x) - x, i32::from(b.y) - y);
+        let (nx, ny) = (dx.abs(), dy.abs());
+        let (sx, sy) = (dx.signum(), dy.signum());
+        let (mut ix, mut iy) = (0, 0);
+        if blocked(x, y) {
+            return false;
+        }
+        while (ix < nx) || (iy < ny) {
+            // NOTE: Whichever of the next vertical or horizontal cell edge the segment meets first.
+            match ((1 + (2 * ix)) * ny).cmp(&((1 + (2 * iy)) * nx)) {
+                Ordering::Less => (x, ix) = (x + sx, ix + 1),
+                Ordering::Greater => (y, iy) = (y + sy, iy + 1),
+                Ordering::Equal => {
+                    if blocked(x + sx, y) || blocked(x, y + sy) {
+                        return false;
+                    }
+                    (x, y, ix, iy) = (x + sx, y + sy, ix + 1, iy + 1);
+                }
+            }
+            if blocked(x, y) {
+                return false;
+            }
+        }
+        true
+    }
+
+    // NOTE: Links `start` and `end` (waypoint graph nodes) into a copy of the graph for the length
+    // of the query. The path comes back in waypoint graph nodes, and is empty when there is none.
+    // `graph` must be the version this was built from.
+    pub fn shortest_path(
+        &mut self,
+        level: &Level,
+        graph: &Graph<Vec2<f32>>,
+        start: usize,
+        end: usize,
+    ) -> PathResult {
+        assert!(graph.version == self.version);
+        if let Some((_, result)) = self.last.as_ref().filter(|(query, _)| *query == (start, end)) {
+            return result.clone();
+        }
+        let result = self.search(level, graph, start, end);
+        self.last = Some(((start, end), result.clone()));
+        result
+    }
+
+    fn search(
+        &self,
+        level: &Level,
+        graph: &Graph<Vec2<f32>>,
+        start: usize,
+        end: usize,
+    ) -> PathResult {
+        if start == end {
+            return PathResult {
+                nodes: Path::from([start]),
+                ..PathResult::default()
+            };
+        }
+        let n = self.waypoints.len();
+        let mut nodes = self.graph.nodes.clone();
+        nodes.extend([graph.nodes[start], graph.nodes[end]]);
+        let mut linked = Graph::new(nodes);
+        for a in 0..n {
+            for b in 0..n {
+                linked.set_weight(a, b, self.graph.weight(a, b));
+            }
+        }
+        let ends = [(n, start), (n + 1, end)];
+        let others = self.waypoints.iter().copied().enumerate().chain(ends);
+        for (a, i) in others {
+            for (b, j) in ends {
+                if (a != b) && self.clear(level, level.waypoints[i], level.waypoints[j]) {
+                    let weight = graph.nodes[i].distance(graph.nodes[j]);
+                    linked.set_weight(a, b, weight);
+                    linked.set_weight(b, a, weight);
+                }
+            }
+        }
+
+        let mut result = pathfinding::shortest_path(&linked, n, n + 1, Options::default());
+        result.nodes = result
+            .nodes
+            .iter()
+            .map(|a| match a.checked_sub(n) {
+                None => self.waypoints[*a],
+                Some(0) => start,
+                Some(_) => end,
+            })
+            .collect();
+        result
+    }
+}
+
+fn solid_at(level: &Level, solid: &[bool], x: i32, y: i32) -> bool {
+    match (u16::try_from(x), u16::try_from(y)) {
+        (Ok(x), Ok(y)) if (x < level.bounds.x) && (y < level.bounds.y) => {
+            solid[level.index(Vec2 { x, y })]
+        }
+        _ => true,
+    }
+}
+
+// NOTE: Draws the corners and the sight lines between them, rebuilt whenever the waypoint graph
+// changes.
+#[derive(Default)]
+pub struct Overlay {
+    visibility: Option<Visibility>,
+}
+
+impl DebugOverlay for Overlay {
+    fn name(&self) -> &'static str {
+        "visibility_graph"
+    }
+
+    fn key(&self) -> c_int {
+        ffi::GLFW_KEY_3
+    }
+
+    fn render(&mut self, frame: &Frame<'_>, draw: &mut DrawList) {
+        if self.visibility.as_ref().map(|visibility| visibility.version)
+            != Some(frame.graph.version)
+        {
+            self.visibility = Some(Visibility::build(frame.level, frame.graph));
+        }
+        let graph = &self.visibility.as_ref().unwrap().graph;
+        for (a, from) in graph.nodes.iter().enumerate() {
+            draw.point(*from, CORNER_SCALE, CORNER_COLOR);
+            for (b, to) in graph.nodes.iter().enumerate().skip(a + 1) {
+                if graph.weight(a, b) < f32::INFINITY {
+                    draw.line(*from, *to, EDGE_COLOR);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::GridToWorld;
+    use crate::level;
+
+    #[test]
+    fn test_visibility() {
+        let level = level::parse(
+            b"+-------+\n|.......|\n|.|.....|\n|.|.....|\n|.......|\n+-------+\n",
+            false,
+        )
+        .unwrap();
+        let graph = level::graph(&level, GridToWorld::new(level.bounds, 1.0), &level::Standard);
+        let mut visibility = Visibility::build(&level, &graph);
+        let corners: Vec<Vec2<u16>> =
+            visibility.waypoints.iter().map(|i| level.waypoints[*i]).collect();
+        assert!(corners.len() == 4);
+        for (x, y) in [(1, 1), (3, 1), (1, 4), (3, 4)] {
+            assert!(corners.contains(&Vec2 { x, y }));
+        }
+        assert!(visibility.graph.weight(0, 1) < f32::INFINITY);
+        assert!(visibility.clear(&level, Vec2 { x: 3, y: 1 }, Vec2 { x: 7, y: 4 }));
+        assert!(!visibility.clear(&level, Vec2 { x: 1, y: 2 }, Vec2 { x: 3, y: 3 }));
+
+        let node = |x, y| level.waypoint_indices[&Vec2 { x, y }];
+        let (start, end) = (node(1, 3), node(3, 3));
+        let result = visibility.shortest_path(&level, &graph, start, end);
+        assert!(visibility.shortest_path(&level, &graph, start, end).nodes == result.nodes);
+        assert!(result.nodes.len() == 4);
+        assert!((result.nodes[0], result.nodes[3]) == (start, end));
+        assert!((result.cost - 4.0).abs() < 0.001);
+        for (i, j) in result.nodes.iter().zip(result.nodes.iter().skip(1)) {
+            assert!(visibility.clear(&level, level.waypoints[*i], level.waypoints[*j]));
+        }
+
+        let (start, end) = (node(3, 2), node(7, 4));
+        let open = visibility.shortest_path(&level, &graph, start, end);
+        let grid = pathfinding::shortest_path(&graph, start, end, Options::default());
+        assert!(open.nodes == [start, end]);
+        assert!(open.cost < grid.cost);
+    }
+}
@@ -0,0 +1,131 @@
+use crate::math::Distance;
+use crate::pathfinding::{Estimate, Graph, Options, PathCache, PathResult};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+enum Request<T> {
+    Graph(Graph<T>),
+    Path(usize, usize, Options),
+}
+
+pub struct Response {
+    pub start: usize,
+    pub end: usize,
+    pub options: Options,
+    pub result: PathResult,
+}
+
+// NOTE: Runs searches on a dedicated thread. Graph snapshots are sent whenever `version` moves and
+// the graph has settled (copying the whole graph on every frame of a rebuild is too slow); path
+// requests queued behind a running search are coalesced so only the newest one is served.
+pub struct Service<T> {
+    requests: Option<Sender<Request<T>>>,
+    responses: Receiver<Response>,
+    thread: Option<JoinHandle<()>>,
+    version: Option<u64>,
+    pending: Option<(usize, usize, Options, u64)>,
+}
+
+fn serve<T: Distance<f32> + Estimate + Copy>(
+    requests: &Receiver<Request<T>>,
+    responses: &Sender<Response>,
+) {
+    let mut graph = None;
+    let mut cache = PathCache::default();
+    while let Ok(request) = requests.recv() {
+        let mut path = None;
+        for request in std::iter::once(request).chain(requests.try_iter()) {
+            match request {
+                Request::Graph(update) => graph = Some(update),
+                Request::Path(start, end, options) => path = Some((start, end, options)),
+            }
+        }
+        let (Some(graph), Some((start, end, options))) = (&graph, path) else {
+            continue;
+        };
+        let response = Response {
+            start,
+            end,
+            options,
+            result: cache.shortest_path(graph, start, end, options),
+        };
+        if responses.send(response).is_err() {
+            return;
+        }
+    }
+}
+
+impl<T: Distance<f32> + Estimate + Copy + Send + 'static> Service<T> {
+    pub fn new() -> Self {
+        let (requests, worker_requests) = mpsc::channel();
+        let (worker_responses, responses) = mpsc::channel();
+        let thread = thread::spawn(move || serve(&worker_requests, &worker_responses));
+        Self {
+            requests: Some(requests),
+            responses,
+            thread: Some(thread),
+            version: None,
+            pending: None,
+        }
+    }
+
+    // NOTE: Repeated submissions of the in-flight query (against the same snapshot) are dropped.
+    // While `settled` is false the worker keeps searching the last snapshot it was sent.
+    pub fn submit(
+        &mut self,
+        graph: &Graph<T>,
+        start: usize,
+        end: usize,
+        options: Options,
+        settled: bool,
+    ) {
+        let requests = self.requests.as_ref().unwrap();
+        if (self.version != Some(graph.version)) && (settled || self.version.is_none()) {
+            requests.send(Request::Graph(graph.clone())).unwrap();
+            self.version = Some(graph.version);
+        }
+        let key = (start, end, options, self.version.unwrap());
+        if self.pending != Some(key) {
+            requests.send(Request::Path(start, end, options)).unwrap();
+            self.pending = Some(key);
+        }
+    }
+
+    pub fn latest(&self) -> Option<Response> {
+        self.responses.try_iter().last()
+    }
+}
+
+impl<T> Drop for Service<T> {
+    fn drop(&mut self) {
+        drop(self.requests.take());
+        if let Some(thread) = self.thread.take() {
+            thread.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec2;
+
+    #[test]
+    fn test_service() {
+        let mut graph =
+            Graph::new([0.0, 1.0, 2.0, 3.0].into_iter().map(|x| Vec2 { x, y: 0.0 }).collect());
+        for i in 0..3 {
+            graph.set_weight(i, i + 1, 1.0);
+        }
+        let mut service = Service::new();
+        service.submit(&graph, 0, 3, Options::default(), true);
+        let response = loop {
+            if let Some(response) = service.latest() {
+                break response;
+            }
+            thread::yield_now();
+        };
+        assert!((response.start, response.end) == (0, 3));
+        assert!(response.result.nodes == [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::level::{self, Error};
+use crate::math::Vec2;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// NOTE: A world file links several floor plans through named exits, one declaration per line:
+//
+//     map hall hall.txt
+//     map yard yard.txt
+//     exit hall.gate hall 9 3
+//     exit yard.gate yard 0 2
+//     link hall.gate yard.gate
+//
+// Blank lines and lines starting with `;` are skipped. Maps are stacked into one level like floors
+// (map `k` is floor `k`), so they may not carry directives or floors of their own. Exit cells are
+// relative to their map and must be waypoints; every exit is linked to exactly one other, and
+// stepping onto either end leads to the other. With `across`, links also become edges so paths can
+// be planned from one map into the next. `read` gives a map's file, or `None` if it can't be read.
+// `floor_plan` is the stacked plan, ready for `level::parse`.
+pub struct World {
+    pub floor_plan: Vec<u8>,
+    pub maps: Vec<String>,
+    pub exits: HashMap<Vec2<u16>, Vec2<u16>>,
+}
+
+const fn fail(message: String) -> Error {
+    Error::Import { format: "world", message }
+}
+
+fn fail_at(n: usize, message: &str) -> Error {
+    fail(format!("{message} at line {}", n + 1))
+}
+
+pub fn load<F: Fn(&str) -> Option<Vec<u8>>>(
+    text: &str,
+    across: bool,
+    auto_waypoints: bool,
+    read: F,
+) -> Result<World, Error> {
+    let mut maps: Vec<(String, String)> = vec![];
+    let mut exits: Vec<(String, usize, Vec2<u16>)> = vec![];
+    let mut links: Vec<(usize, usize)> = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words[..] {
+            ["map", name, path] => {
+                if maps.iter().any(|(other, _)| other == name) {
+                    return Err(fail_at(n, &format!("duplicate map {name:?}")));
+                }
+                let floor_plan =
+                    read(path).ok_or_else(|| fail_at(n, &format!("{path} can't be read")))?;
+                let floor_plan = String::from_utf8(floor_plan)
+                    .map_err(|_| fail_at(n, &format!("{path} is not UTF-8")))?;
+                if floor_plan.lines().any(|row| row.starts_with(['!', '='])) {
+                    return Err(fail_at(n, &format!("{path} has directives or floors of its own")));
+                }
+                maps.push((name.to_owned(), floor_plan));
+            }
+            ["exit", name, map, x, y] => {
+                if exits.iter().any(|(other, _, _)| other == name) {
+                    return Err(fail_at(n, &format!("duplicate exit {name:?}")));
+                }
+                let map = maps
+                    .iter()
+                    .position(|(other, _)| other == map)
+                    .ok_or_else(|| fail_at(n, &format!("unknown map {map:?}")))?;
+                let (Ok(x), Ok(y)) = (x.parse(), y.parse()) else {
+                    return Err(fail_at(n, "expected a cell"));
+                };
+                exits.push((name.to_owned(), map, Vec2 { x, y }));
+            }
+            ["link", a, b] => {
+                let exit = |name| {
+                    exits
+                        .iter()
+                        .position(|(other, _, _)| other == name)
+                        .ok_or_else(|| fail_at(n, &format!("unknown exit {name:?}")))
+                };
+                let (a, b) = (exit(a)?, exit(b)?);
+                if (a == b) || links.iter().any(|(c, d)| [a, b].iter().any(|k| [c, d].contains(&k)))
+                {
+                    return Err(fail_at(n, "exits may only be linked once"));
+                }
+                links.push((a, b));
+            }
+            _ => return Err(fail_at(n, &format!("unexpected {line:?}"))),
+        }
+    }
+    if let Some((name, _, _)) = exits
+        .iter()
+        .enumerate()
+        .find(|(k, _)| !links.iter().any(|(a, b)| (a == k) || (b == k)))
+        .map(|(_, exit)| exit)
+    {
+        return Err(fail(format!("exit {name:?} is not linked")));
+    }
+    if maps.is_empty() {
+        return Err(fail("no maps".to_owned()));
+    }
+
+    // NOTE: Rows are padded out to the widest map with open floor, and each map after the first
+    // starts one row further down for the wall `level::parse` puts between floors.
+    let width = maps
+        .iter()
+        .flat_map(|(_, floor_plan)| floor_plan.lines().map(|row| row.chars().count()))
+        .max()
+        .unwrap_or(0);
+    let mut first_rows = vec![];
+    let mut rows: u16 = 0;
+    let mut body = String::new();
+    for (k, (_, floor_plan)) in maps.iter().enumerate() {
+        writeln!(body, "=== floor {} ===", k + 1).unwrap();
+        first_rows.push(rows);
+        for row in floor_plan.lines().filter(|row| !row.is_empty()) {
+            writeln!(body, "{row}{}", " ".repeat(width - row.chars().count())).unwrap();
+            rows += 1;
+        }
+        rows += 1;
+    }
+    let global = |(_, map, cell): &(String, usize, Vec2<u16>)| Vec2 {
+        x: cell.x,
+        y: first_rows[*map] + cell.y,
+    };
+    let mut floor_plan = String::new();
+    if across {
+        for (a, b) in &links {
+            let (a, b) = (global(&exits[*a]), global(&exits[*b]));
+            writeln!(floor_plan, "!warp {} {} {} {}", a.x, a.y, b.x, b.y).unwrap();
+        }
+    }
+    floor_plan.push_str(&body);
+    let floor_plan = floor_plan.into_bytes();
+
+    let level = level::parse(&floor_plan, auto_waypoints)?;
+    if let Some((name, _, _)) =
+        exits.iter().find(|exit| !level.waypoint_indices.contains_key(&global(exit)))
+    {
+        return Err(fail(format!("exit {name:?} is not on a waypoint")));
+    }
+    let mut linked = HashMap::new();
+    for (a, b) in links {
+        linked.insert(global(&exits[a]), global(&exits[b]));
+        linked.insert(global(&exits[b]), global(&exits[a]));
+    }
+    Ok(World {
+        floor_plan,
+        maps: maps.into_iter().map(|(name, _)| name).collect(),
+        exits: linked,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geom::GridToWorld;
+    use crate::pathfinding;
+
+    const WORLD: &str = "; Two rooms joined by a gate.\n\
+                         map hall hall.txt\n\
+                         map yard yard.txt\n\
+                         exit hall.gate hall 2 1\n\
+                         exit yard.gate yard 0 0\n\
+                         link hall.gate yard.gate\n";
+
+    fn read(path: &str) -> Option<Vec<u8>> {
+        match path {
+            "hall.txt" => Some(b"+-+-\n|...\n+---\n".to_vec()),
+            "yard.txt" => Some(b"..\n..\n".to_vec()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_load() {
+        for across in [false, true] {
+            let world = load(WORLD, across, false, read).unwrap();
+            let level = &level::parse(&world.floor_plan, false).unwrap();
+            assert!(world.maps == ["hall", "yard"] && level.floors == [0, 4]);
+            let (gate, entry) = (Vec2 { x: 2, y: 1 }, Vec2 { x: 0, y: 4 });
+            assert!((world.exits[&gate], world.exits[&entry]) == (entry, gate));
+
+            let graph = level::graph(level, GridToWorld::new(level.bounds, 1.0), &level::Standard);
+            let reachable = pathfinding::flow_field(level.waypoint_indices[&entry], &graph)
+                [level.waypoint_indices[&Vec2 { x: 1, y: 1 }]]
+                .is_some();
+            assert!(reachable == across);
+        }
+    }
+
+    #[test]
+    fn test_load_error() {
+        let error = load(
+            &WORLD.replace("link hall.gate yard.gate", "link hall.gate yard.door"),
+            false,
+            false,
+            read,
+        )
+        .err()
+        .unwrap();
+        assert!(error.to_string() == "world: unknown exit \"yard.door\" at line 6");
+        let error = load(&WORLD.replace("yard.txt", "lawn.txt"), false, false, read).err().unwrap();
+        assert!(error.to_string() == "world: lawn.txt can't be read at line 3");
+    }
+}